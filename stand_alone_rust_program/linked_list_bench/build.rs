@@ -0,0 +1,23 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // The C list for the `ffi` benchmark, built at the optimisation level of
+    // the Rust code it is compared with (cc follows the profile's).
+    #[cfg(feature = "c-list")]
+    {
+        println!("cargo:rerun-if-changed=c/list.c");
+        let mut build = cc::Build::new();
+        build.file("c/list.c");
+        let compiler = build.get_compiler();
+        println!(
+            "cargo:rustc-env=CLIST_COMPILER={} {}",
+            compiler.path().display(),
+            compiler
+                .args()
+                .iter()
+                .map(|a| a.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        build.compile("clist");
+    }
+}
@@ -0,0 +1,63 @@
+/* The C side of the `ffi` parity benchmark: the same singly linked list
+ * as the Rust one in src/main.rs, node for node. Each node is one malloc,
+ * pushed at the head, so building 0..n gives the same allocation pattern
+ * and the same traversal order as `bench::build`. */
+
+#include <stddef.h>
+#include <stdlib.h>
+
+struct clist_node {
+    size_t data;
+    struct clist_node *next;
+};
+
+struct clist {
+    struct clist_node *head;
+    size_t count;
+};
+
+/* Returns an empty list, or NULL when out of memory. */
+struct clist *clist_new(void)
+{
+    struct clist *list = malloc(sizeof *list);
+    if (list) {
+        list->head = NULL;
+        list->count = 0;
+    }
+    return list;
+}
+
+/* Pushes `data` at the head. Returns 0 when out of memory. */
+int clist_push(struct clist *list, size_t data)
+{
+    struct clist_node *node = malloc(sizeof *node);
+    if (!node)
+        return 0;
+    node->data = data;
+    node->next = list->head;
+    list->head = node;
+    list->count++;
+    return 1;
+}
+
+/* The loop of `benchmark_traversal`: follows every link, touching nothing
+ * but the next pointers, and returns the number of nodes visited. */
+size_t clist_traverse(const struct clist *list)
+{
+    size_t visited = 0;
+    for (const struct clist_node *node = list->head; node; node = node->next)
+        visited++;
+    return visited;
+}
+
+/* Frees the nodes front to back, then the list. */
+void clist_free(struct clist *list)
+{
+    struct clist_node *node = list->head;
+    while (node) {
+        struct clist_node *next = node->next;
+        free(node);
+        node = next;
+    }
+    free(list);
+}
@@ -0,0 +1,142 @@
+//! `cargo +nightly fuzz run list_ops`: applies random sequences of list
+//! operations to a `LinkedList` and to the `Vec` that models it, and checks
+//! after each one that both hold the same values. The list module is built
+//! from its source, since the benchmark is a binary with no library.
+
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/list.rs"]
+mod list;
+
+use list::LinkedList;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Push(u8),
+    Pop,
+    InsertAt(u8, u8),
+    RemoveAt(u8),
+    SplitOff(u8),
+    Append(Vec<u8>),
+    Zip(Vec<u8>),
+    /// Keeps the values not divisible by this (plus one).
+    Retain(u8),
+    Dedup,
+    /// Builds two lists with `insert_sorted` and merges the second into the
+    /// first, against a stable sort.
+    Sorted(Vec<u8>, Vec<u8>),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut list = LinkedList::new();
+    let mut model: Vec<u8> = Vec::new();
+    for op in ops {
+        match op {
+            Op::Push(v) => {
+                list.push(v);
+                model.insert(0, v);
+            }
+            Op::Pop => {
+                let expected = (!model.is_empty()).then(|| model.remove(0));
+                assert_eq!(list.pop(), expected);
+            }
+            Op::InsertAt(i, v) => {
+                let i = usize::from(i);
+                list.insert_at(i, v);
+                model.insert(i.min(model.len()), v);
+            }
+            Op::RemoveAt(i) => {
+                let i = usize::from(i);
+                let expected = (i < model.len()).then(|| model.remove(i));
+                assert_eq!(list.remove_at(i), expected);
+            }
+            Op::SplitOff(i) => {
+                let i = usize::from(i);
+                let tail = list.split_off(i);
+                let expected = model.split_off(i.min(model.len()));
+                assert_eq!(tail.count, expected.len());
+                assert_eq!(tail.into_vec(), expected);
+            }
+            Op::Append(values) => {
+                list.append(LinkedList::from_vec(values.clone()));
+                model.extend(values);
+            }
+            Op::Zip(values) => {
+                list.zip(LinkedList::from_vec(values.clone()));
+                let mut zipped = Vec::with_capacity(model.len() + values.len());
+                let (mut ours, mut theirs) = (model.into_iter(), values.into_iter());
+                loop {
+                    match (ours.next(), theirs.next()) {
+                        (None, None) => break,
+                        (a, b) => zipped.extend(a.into_iter().chain(b)),
+                    }
+                }
+                model = zipped;
+            }
+            Op::Retain(d) => {
+                let d = d.saturating_add(1);
+                list.retain(|v| v % d != 0);
+                model.retain(|v| v % d != 0);
+            }
+            Op::Dedup => {
+                list.dedup();
+                model.dedup();
+            }
+            Op::Sorted(ours, theirs) => sorted(&ours, &theirs),
+        }
+        assert_eq!(list.count, model.len());
+        let mut current = &list.head;
+        for &expected in &model {
+            let node = current.as_ref().expect("list shorter than its model");
+            assert_eq!(node.data, expected);
+            current = &node.next;
+        }
+        assert!(current.is_none(), "list longer than its model");
+    }
+});
+
+/// Sorts by the high nibble alone, so the low one tells equal keys apart
+/// and shows whether their order was kept.
+fn sorted(ours: &[u8], theirs: &[u8]) {
+    #[derive(Debug)]
+    struct Keyed(u8);
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 >> 4 == other.0 >> 4
+        }
+    }
+    impl Eq for Keyed {}
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            (self.0 >> 4).cmp(&(other.0 >> 4))
+        }
+    }
+
+    let build = |values: &[u8]| {
+        let mut list = LinkedList::new();
+        for &v in values {
+            list.insert_sorted(Keyed(v));
+        }
+        list
+    };
+    let stable_sort = |values: &[u8]| {
+        let mut values = values.to_vec();
+        values.sort_by_key(|v| v >> 4);
+        values
+    };
+    let mut list = build(ours);
+    list.merge(build(theirs));
+    let mut model = stable_sort(ours);
+    model.extend(stable_sort(theirs));
+    let merged: Vec<u8> = list.into_vec().into_iter().map(|keyed| keyed.0).collect();
+    assert_eq!(merged, stable_sort(&model));
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use crate::isolate;
+use crate::results;
+use crate::stats::{self, Summary};
+
+/// `ab <binary-a> <binary-b> [--rounds <r>] [-- <sweep options>]`: runs the
+/// sweep suite of two builds of this benchmark (e.g. two commits or two sets
+/// of compiler flags) and reports per-size deltas with a Welch t-test.
+///
+/// Runs are interleaved A B B A A B ... so slow drift of the machine (thermal
+/// state, background load) hits both binaries equally.
+pub fn run(args: &[String]) {
+    if args.len() < 2 {
+        eprintln!(
+            "Usage: cargo run -- ab <binary-a> <binary-b> [--rounds <r>] [-- <sweep options>]"
+        );
+        return;
+    }
+    let (binary_a, binary_b) = (&args[0], &args[1]);
+
+    let mut rounds: usize = 10;
+    let mut sweep_args: Vec<String> = Vec::new();
+    let mut iter = args[2..].iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--rounds", Some(v)) => rounds = v.parse().unwrap_or(rounds),
+            ("--", first) => {
+                sweep_args.extend(first.cloned());
+                sweep_args.extend(iter.by_ref().cloned());
+            }
+            _ => {
+                eprintln!("Error: unknown or incomplete ab option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    // nodes -> median cycles/node of each round, per binary.
+    let mut results_a: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+    let mut results_b: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+
+    for round in 0..rounds.max(1) {
+        eprintln!("[ab] round {}/{}", round + 1, rounds);
+        let order = if round % 2 == 0 {
+            [true, false]
+        } else {
+            [false, true]
+        };
+        for is_a in order {
+            let (binary, results) = if is_a {
+                (binary_a, &mut results_a)
+            } else {
+                (binary_b, &mut results_b)
+            };
+            match run_sweep(binary, &sweep_args) {
+                Ok(rows) => {
+                    for (nodes, cycles) in rows {
+                        results.entry(nodes).or_default().push(cycles);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: could not run {}: {}", binary, e);
+                    return;
+                }
+            }
+        }
+    }
+
+    println!("--- A/B Comparison ({} rounds) ---", rounds);
+    println!("A: {}", binary_a);
+    println!("B: {}", binary_b);
+    println!(
+        "\n{:>12} {:>14} {:>14} {:>9} {:>8} {:>9}",
+        "Nodes", "A cycles/node", "B cycles/node", "Delta", "t", "p-value"
+    );
+    for (nodes, a) in &results_a {
+        let Some(b) = results_b.get(nodes) else {
+            continue;
+        };
+        let (mean_a, mean_b) = (Summary::of(a).mean, Summary::of(b).mean);
+        let delta = (mean_b / mean_a - 1.0) * 100.0;
+        let (t, p_value, marker) = match stats::welch_t_test(a, b) {
+            Some(test) => (
+                format!("{:.2}", test.statistic),
+                format!("{:.4}", test.p_value),
+                if test.p_value < 0.05 { " *" } else { "" },
+            ),
+            None => ("-".to_string(), "-".to_string(), ""),
+        };
+        println!(
+            "{:>12} {:>14.2} {:>14.2} {:>+8.1}% {:>8} {:>9}{}",
+            nodes, mean_a, mean_b, delta, t, p_value, marker
+        );
+    }
+    println!("\n* significant at p < 0.05 (Welch's t-test over per-round medians)");
+}
+
+/// Runs `<binary> sweep <args> --save <scratch file>` and returns (nodes,
+/// median cycles/node) of every traversal it saved. Reading the samples
+/// file rather than the printed table keeps extra columns and placeholders
+/// out of the way; a size with every sample flagged has no median and is
+/// left out of that round, with a note.
+pub fn run_sweep(binary: &str, args: &[String]) -> std::io::Result<Vec<(usize, f64)>> {
+    let path = isolate::scratch_path("ab");
+    let output = Command::new(binary)
+        .arg("sweep")
+        .args(args)
+        .arg("--save")
+        .arg(&path)
+        .output();
+    let loaded = output.and_then(|output| {
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "exited with {}",
+                output.status
+            )));
+        }
+        results::load(&path.to_string_lossy())
+    });
+    let _ = std::fs::remove_file(&path);
+    let results = loaded?.1;
+    // Reads come first; `--write` adds a series of writes after them.
+    let Some(first) = results.first() else {
+        return Err(std::io::Error::other("the sweep saved no results"));
+    };
+    let mut rows = Vec::new();
+    for result in results.iter().filter(|r| r.name == first.name) {
+        let cycles = result.cycles_per_node();
+        if cycles.count == 0 {
+            eprintln!(
+                "[ab] {}: every sample of {} nodes was flagged, left out of this round",
+                binary, result.nodes
+            );
+        } else {
+            rows.push((result.nodes, cycles.median));
+        }
+    }
+    Ok(rows)
+}
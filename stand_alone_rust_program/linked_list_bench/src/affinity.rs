@@ -0,0 +1,137 @@
+//! Keeping the benchmark thread on one CPU at a raised priority, through
+//! whatever the operating system offers. Every step is best effort: a
+//! refused request (no privilege, unsupported platform) is reported and the
+//! run continues unpinned, where migrations still get flagged per sample.
+
+/// Pins the calling thread to the CPU it is running on and asks for a
+/// higher scheduling priority. Returns one line per step describing what
+/// happened, for stderr.
+pub fn pin_current_thread() -> Vec<String> {
+    imp::pin_current_thread()
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+mod imp {
+    pub fn pin_current_thread() -> Vec<String> {
+        let mut notes = Vec::new();
+        // SAFETY: plain libc calls on a zero-initialised cpu_set_t owned by
+        // this frame; pid 0 means the calling thread.
+        unsafe {
+            let cpu = libc::sched_getcpu();
+            if cpu < 0 {
+                notes.push("could not determine the current CPU, not pinned".to_string());
+            } else {
+                let mut set: libc::cpu_set_t = std::mem::zeroed();
+                libc::CPU_SET(cpu as usize, &mut set);
+                if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0 {
+                    notes.push(format!("pinned to CPU {}", cpu));
+                } else {
+                    notes.push(format!(
+                        "could not pin to CPU {}: {}",
+                        cpu,
+                        std::io::Error::last_os_error()
+                    ));
+                }
+            }
+            // A negative nice value needs CAP_SYS_NICE; most users get EACCES.
+            if libc::setpriority(libc::PRIO_PROCESS as _, 0, -10) == 0 {
+                notes.push("priority raised to nice -10".to_string());
+            } else {
+                notes.push(format!(
+                    "priority unchanged: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        notes
+    }
+}
+
+#[cfg(all(windows, not(miri)))]
+mod imp {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcessorNumber, GetCurrentThread, SetThreadAffinityMask, SetThreadPriority,
+        THREAD_PRIORITY_HIGHEST,
+    };
+
+    pub fn pin_current_thread() -> Vec<String> {
+        let mut notes = Vec::new();
+        // SAFETY: GetCurrentThread returns a pseudo-handle that needs no
+        // closing, and these calls only change that thread's scheduling.
+        unsafe {
+            let thread = GetCurrentThread();
+            // The number is within the thread's processor group, which is
+            // also what the affinity mask is relative to.
+            let cpu = GetCurrentProcessorNumber();
+            if SetThreadAffinityMask(thread, 1usize << cpu) != 0 {
+                notes.push(format!("pinned to CPU {}", cpu));
+            } else {
+                notes.push(format!(
+                    "could not pin to CPU {}: {}",
+                    cpu,
+                    std::io::Error::last_os_error()
+                ));
+            }
+            if SetThreadPriority(thread, THREAD_PRIORITY_HIGHEST) != 0 {
+                notes.push("priority raised to THREAD_PRIORITY_HIGHEST".to_string());
+            } else {
+                notes.push(format!(
+                    "priority unchanged: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        notes
+    }
+}
+
+#[cfg(all(target_os = "macos", not(miri)))]
+mod imp {
+    pub fn pin_current_thread() -> Vec<String> {
+        let mut notes = Vec::new();
+        // macOS has no hard affinity. The affinity policy is a hint that
+        // threads sharing a tag should share an L2 (Intel Macs only; Apple
+        // Silicon answers KERN_NOT_SUPPORTED), and the user-interactive QoS
+        // class is what keeps a thread on the performance cores.
+        // SAFETY: the policy struct is the size the count says, and the port
+        // from pthread_mach_thread_np is borrowed, not a new reference.
+        unsafe {
+            let mut policy = libc::thread_affinity_policy_data_t { affinity_tag: 1 };
+            let status = libc::thread_policy_set(
+                libc::pthread_mach_thread_np(libc::pthread_self()),
+                libc::THREAD_AFFINITY_POLICY as _,
+                &mut policy as *mut _ as libc::thread_policy_t,
+                libc::THREAD_AFFINITY_POLICY_COUNT,
+            );
+            notes.push(if status == libc::KERN_SUCCESS {
+                "affinity tag set (Intel Mac L2 sharing hint)".to_string()
+            } else {
+                format!("affinity hint not supported (kern_return {})", status)
+            });
+            let status = libc::pthread_set_qos_class_self_np(
+                libc::qos_class_t::QOS_CLASS_USER_INTERACTIVE,
+                0,
+            );
+            notes.push(if status == 0 {
+                "QoS raised to user-interactive (prefers performance cores)".to_string()
+            } else {
+                format!(
+                    "QoS unchanged: {}",
+                    std::io::Error::from_raw_os_error(status)
+                )
+            });
+        }
+        notes
+    }
+}
+
+#[cfg(not(any(
+    all(target_os = "linux", not(miri)),
+    all(windows, not(miri)),
+    all(target_os = "macos", not(miri))
+)))]
+mod imp {
+    pub fn pin_current_thread() -> Vec<String> {
+        vec!["thread pinning is not supported on this platform".to_string()]
+    }
+}
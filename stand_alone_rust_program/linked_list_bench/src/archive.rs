@@ -0,0 +1,319 @@
+//! Result bundles: `export` packs the files runs saved with `--save` into
+//! one archive, `render` turns an archive back into any output format
+//! without measuring again, so how results are presented can be iterated
+//! on long after the slow part is done, on another machine if need be.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bench::BenchResult;
+use crate::metadata::Metadata;
+use crate::plot;
+use crate::report::{self, Format};
+use crate::results;
+
+const HEADER: &str = "# linked_list_bench archive v1";
+
+/// The key and value of every `param` line, in order.
+type Params = Vec<(String, String)>;
+
+/// One saved results file inside an archive.
+struct Section {
+    /// Where it was exported from, for its label and for errors.
+    source: String,
+    metadata: Metadata,
+    /// What the run was started with, when the file recorded it.
+    command: Option<String>,
+    results: Vec<BenchResult>,
+}
+
+/// `export --out <bundle> [--title <t>] <results.tsv>...`: packs files
+/// written by `--save` (raw samples, the machine and commit, the command
+/// line) into one archive, also tab-separated text:
+///
+/// ```text
+/// # linked_list_bench archive v1
+/// param    <key>    <value>
+/// section  <source path>
+/// <the saved file, verbatim>
+/// ```
+///
+/// `param` keys are `title`, `exported` (Unix seconds) and `version`, the
+/// exporting build's. Every file is checked to load before it is packed.
+pub fn export(args: &[String]) {
+    let mut out: Option<String> = None;
+    let mut title: Option<String> = None;
+    let mut sources: Vec<String> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            sources.push(arg.clone());
+            continue;
+        }
+        match (arg.as_str(), iter.next()) {
+            ("--out", Some(v)) => out = Some(v.clone()),
+            ("--title", Some(v)) => title = Some(v.clone()),
+            _ => {
+                eprintln!("Error: unknown or incomplete export option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let (Some(out), false) = (out, sources.is_empty()) else {
+        eprintln!("Usage: cargo run -- export --out <bundle> [--title <t>] <results.tsv>...");
+        return;
+    };
+
+    let mut texts = Vec::new();
+    for source in &sources {
+        let loaded = fs::read_to_string(source)
+            .and_then(|text| results::parse(&text, source).map(|loaded| (text, loaded)));
+        match loaded {
+            Ok((text, (_, results))) => {
+                eprintln!("[export] {}: {} results", source, results.len());
+                texts.push(text);
+            }
+            Err(e) => {
+                eprintln!("Error: could not load results: {}", e);
+                return;
+            }
+        }
+    }
+    // A lone run's own command line makes the best default title.
+    let title = title
+        .or_else(|| match texts.as_slice() {
+            [text] => command_of(text),
+            _ => Some(format!("linked_list_bench results ({} runs)", texts.len())),
+        })
+        .unwrap_or_else(|| "linked_list_bench results".to_string());
+
+    let written = fs::File::create(&out).and_then(|file| {
+        let mut file = io::BufWriter::new(file);
+        writeln!(file, "{}", HEADER)?;
+        writeln!(file, "param\ttitle\t{}", title.replace(['\t', '\n'], " "))?;
+        writeln!(
+            file,
+            "param\texported\t{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs())
+        )?;
+        writeln!(file, "param\tversion\t{}", env!("CARGO_PKG_VERSION"))?;
+        for (source, text) in sources.iter().zip(&texts) {
+            writeln!(file, "section\t{}", source)?;
+            file.write_all(text.as_bytes())?;
+            if !text.ends_with('\n') {
+                writeln!(file)?;
+            }
+        }
+        file.flush()
+    });
+    match written {
+        Ok(()) => eprintln!("Archive of {} files written to {}", sources.len(), out),
+        Err(e) => eprintln!("Error: could not write archive {}: {}", out, e),
+    }
+}
+
+/// `render <bundle> [--format text|html|markdown|ndjson|github-benchmark]
+/// [--title <t>] [--plot <file.svg|file.png>]`: prints an archive written
+/// by `export` in any of the formats a run can print, and optionally draws
+/// the sweep chart, from the samples alone. With more than one section,
+/// each result's name gains its file's stem, so the sections stay apart in
+/// tables and charts; html and github-benchmark, which describe one
+/// machine, take the first section's.
+pub fn render(args: &[String]) {
+    let Some((path, options)) = args.split_first() else {
+        eprintln!("Usage: cargo run -- render <bundle> [--format <text|html|markdown|ndjson|github-benchmark>] [--title <t>] [--plot <file.svg|file.png>]");
+        return;
+    };
+    let mut format = Format::Text;
+    let mut title: Option<String> = None;
+    let mut plot_path: Option<String> = None;
+
+    let mut iter = options.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--format", Some(v)) => match Format::parse(v) {
+                Some(f) => format = f,
+                None => {
+                    eprintln!(
+                        "Error: unknown format '{}' (expected text, html, markdown, ndjson or github-benchmark)",
+                        v
+                    );
+                    return;
+                }
+            },
+            ("--title", Some(v)) => title = Some(v.clone()),
+            ("--plot", Some(v)) => plot_path = Some(v.clone()),
+            _ => {
+                eprintln!("Error: unknown or incomplete render option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    let (params, mut sections) = match load(path) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: could not load archive: {}", e);
+            return;
+        }
+    };
+    if sections.is_empty() {
+        eprintln!("Error: {} holds no results", path);
+        return;
+    }
+    let title = title
+        .or_else(|| param(&params, "title"))
+        .unwrap_or_else(|| "linked_list_bench results".to_string());
+
+    let labelled = sections.len() > 1;
+    let mut results: Vec<BenchResult> = Vec::new();
+    let mut counts = Vec::new();
+    for section in &mut sections {
+        let label = label(&section.source);
+        counts.push(section.results.len());
+        results.extend(section.results.drain(..).map(|mut result| {
+            if labelled {
+                result.name = format!("{}/{}", label, result.name);
+            }
+            result
+        }));
+    }
+    let mut rest = &results[..];
+    let per_section: Vec<&[BenchResult]> = counts
+        .iter()
+        .map(|&count| {
+            let (section, tail) = rest.split_at(count);
+            rest = tail;
+            section
+        })
+        .collect();
+    let metadata = &sections[0].metadata;
+
+    match format {
+        Format::Text => {
+            println!("--- {} ---", title);
+            for section in &sections {
+                println!(
+                    "{}: host {}, cpu {}, commit {}{}",
+                    section.source,
+                    section.metadata.host,
+                    section.metadata.cpu,
+                    section.metadata.git_commit,
+                    section
+                        .command
+                        .as_ref()
+                        .map_or(String::new(), |c| format!(", command '{}'", c))
+                );
+            }
+            if let Some(exported) = param(&params, "exported") {
+                println!(
+                    "Exported at {} (Unix seconds) by version {}",
+                    exported,
+                    param(&params, "version").unwrap_or_else(|| "unknown".to_string())
+                );
+            }
+            print!("\n{}", report::text(&results));
+        }
+        Format::Html => print!("{}", report::html(&title, metadata, &results)),
+        Format::Markdown => {
+            // One document per section, each with its own machine.
+            for (index, (section, results)) in sections.iter().zip(&per_section).enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                let title = if labelled {
+                    format!("{}: {}", title, section.source)
+                } else {
+                    title.clone()
+                };
+                print!("{}", report::markdown(&title, &section.metadata, results));
+            }
+        }
+        Format::Ndjson => {
+            for (section, results) in sections.iter().zip(&per_section) {
+                for result in *results {
+                    println!("{}", report::ndjson(&title, &section.metadata, result));
+                }
+            }
+        }
+        Format::GithubBenchmark => print!("{}", report::github_benchmark(metadata, &results)),
+    }
+
+    if let Some(plot_path) = plot_path {
+        let series: Vec<(&str, &[BenchResult])> = results
+            .chunk_by(|a, b| a.name == b.name)
+            .map(|run| (run[0].name.as_str(), run))
+            .collect();
+        match plot::cycles_per_node(&plot_path, &series) {
+            Ok(()) => eprintln!("\nPlot written to {}", plot_path),
+            Err(e) => eprintln!("Error: could not write plot {}: {}", plot_path, e),
+        }
+    }
+}
+
+/// Reads an archive written by [`export`]: its params, then its sections.
+fn load(path: &str) -> io::Result<(Params, Vec<Section>)> {
+    let text = fs::read_to_string(path)?;
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: not a linked_list_bench archive", path),
+        ));
+    }
+    let mut params = Vec::new();
+    // (source, the saved file's lines)
+    let mut chunks: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        if let Some(source) = line.strip_prefix("section\t") {
+            chunks.push((source.to_string(), String::new()));
+        } else if let Some((_, text)) = chunks.last_mut() {
+            text.push_str(line);
+            text.push('\n');
+        } else if let Some((key, value)) = line
+            .strip_prefix("param\t")
+            .and_then(|param| param.split_once('\t'))
+        {
+            params.push((key.to_string(), value.to_string()));
+        }
+    }
+    let sections = chunks
+        .into_iter()
+        .map(|(source, text)| {
+            let (metadata, results) = results::parse(&text, &format!("{} [{}]", path, source))?;
+            Ok(Section {
+                command: command_of(&text),
+                source,
+                metadata,
+                results,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok((params, sections))
+}
+
+fn param(params: &[(String, String)], key: &str) -> Option<String> {
+    params
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.clone())
+}
+
+/// The command line a saved file recorded, if it did.
+fn command_of(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("meta\tcommand\t"))
+        .map(str::to_string)
+}
+
+/// A section's name in labelled results: its file name without extension.
+fn label(source: &str) -> String {
+    Path::new(source)
+        .file_stem()
+        .map_or_else(|| source.to_string(), |s| s.to_string_lossy().into_owned())
+}
@@ -0,0 +1,155 @@
+/// Sentinel `next` index marking the end of an [`ArenaList`].
+pub const NIL: u64 = u64::MAX;
+
+/// A node in an arena list: the payload and the index of the successor,
+/// laid out as two adjacent words so the arena can also be read as a flat
+/// `[u64]`.
+#[repr(C)]
+pub struct ArenaNode {
+    pub data: u64,
+    pub next: u64,
+}
+
+/// A singly linked list whose nodes live in one `Vec` and link by index.
+/// Traversal still follows the links, but the storage is contiguous, so
+/// order-independent work (like summing) can stream over it instead.
+pub struct ArenaList {
+    pub nodes: Vec<ArenaNode>,
+    pub head: u64,
+}
+
+impl ArenaList {
+    pub fn new() -> Self {
+        ArenaList {
+            nodes: Vec::new(),
+            head: NIL,
+        }
+    }
+
+    /// Prepends `data`, like `LinkedList::push`.
+    pub fn push(&mut self, data: u64) {
+        self.nodes.push(ArenaNode {
+            data,
+            next: self.head,
+        });
+        self.head = (self.nodes.len() - 1) as u64;
+    }
+
+    /// An arena of `order.len()` nodes with payload `i` in slot `i`, linked
+    /// so that a traversal visits the slots in `order`.
+    pub fn linked_in_order(order: &[usize]) -> Self {
+        let mut nodes: Vec<ArenaNode> = (0..order.len() as u64)
+            .map(|data| ArenaNode { data, next: NIL })
+            .collect();
+        for pair in order.windows(2) {
+            nodes[pair[0]].next = pair[1] as u64;
+        }
+        ArenaList {
+            nodes,
+            head: order.first().map_or(NIL, |&first| first as u64),
+        }
+    }
+
+    /// Sums the payloads in list order by following the links.
+    pub fn sum_by_links(&self) -> u64 {
+        let mut sum: u64 = 0;
+        let mut current = self.head;
+        while current != NIL {
+            let node = &self.nodes[current as usize];
+            sum = sum.wrapping_add(node.data);
+            current = node.next;
+        }
+        sum
+    }
+
+    /// The arena as `data, next, data, next, ...` words.
+    pub fn words(&self) -> &[u64] {
+        // SAFETY: `ArenaNode` is `repr(C)` with two `u64` fields and no
+        // padding, so `n` nodes are exactly `2 * n` initialised `u64`s.
+        unsafe {
+            std::slice::from_raw_parts(self.nodes.as_ptr() as *const u64, self.nodes.len() * 2)
+        }
+    }
+}
+
+/// The struct-of-arrays counterpart of [`ArenaList`]: links and payloads in
+/// separate arrays indexed by slot, so a walk that only follows links never
+/// pulls payloads into the cache and a scan of payloads never reads links.
+pub struct SoaList {
+    pub next: Vec<u64>,
+    pub data: Vec<u64>,
+    pub head: u64,
+}
+
+impl SoaList {
+    /// Same contents and link order as [`ArenaList::linked_in_order`].
+    pub fn linked_in_order(order: &[usize]) -> Self {
+        let mut next = vec![NIL; order.len()];
+        for pair in order.windows(2) {
+            next[pair[0]] = pair[1] as u64;
+        }
+        SoaList {
+            next,
+            data: (0..order.len() as u64).collect(),
+            head: order.first().map_or(NIL, |&first| first as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// The payloads in link order, as `sum_by_links` visits them.
+    fn by_links(head: u64, next: impl Fn(usize) -> u64, data: impl Fn(usize) -> u64) -> Vec<u64> {
+        let mut values = Vec::new();
+        let mut current = head;
+        while current != NIL {
+            values.push(data(current as usize));
+            current = next(current as usize);
+        }
+        values
+    }
+
+    proptest! {
+        #![proptest_config(crate::testing::proptest_config())]
+
+        #[test]
+        fn pushes_match_a_vec(values in prop::collection::vec(any::<u64>(), 0..40)) {
+            let mut list = ArenaList::new();
+            let mut model: Vec<u64> = Vec::new();
+            for &value in &values {
+                list.push(value);
+                model.insert(0, value);
+                let walked = by_links(list.head, |i| list.nodes[i].next, |i| list.nodes[i].data);
+                prop_assert_eq!(&walked, &model);
+            }
+            let sum = model.iter().fold(0u64, |sum, &v| sum.wrapping_add(v));
+            prop_assert_eq!(list.sum_by_links(), sum);
+            // Slots in push order, each its payload then its link.
+            let words = list.words();
+            prop_assert_eq!(words.len(), 2 * values.len());
+            for (slot, &value) in values.iter().enumerate() {
+                prop_assert_eq!(words[2 * slot], value);
+                let next = if slot == 0 { NIL } else { slot as u64 - 1 };
+                prop_assert_eq!(words[2 * slot + 1], next);
+            }
+        }
+
+        #[test]
+        fn linked_in_order_visits_the_slots_in_order(
+            order in (0..40usize).prop_flat_map(|n| Just((0..n).collect::<Vec<_>>()).prop_shuffle())
+        ) {
+            let model: Vec<u64> = order.iter().map(|&slot| slot as u64).collect();
+            let arena = ArenaList::linked_in_order(&order);
+            let walked = by_links(arena.head, |i| arena.nodes[i].next, |i| arena.nodes[i].data);
+            prop_assert_eq!(&walked, &model);
+            prop_assert_eq!(arena.sum_by_links(), model.iter().sum::<u64>());
+
+            let soa = SoaList::linked_in_order(&order);
+            let walked = by_links(soa.head, |i| soa.next[i], |i| soa.data[i]);
+            prop_assert_eq!(&walked, &model);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::process;
+use std::time::Duration;
+
+use crate::cache::{self, Layout};
+use crate::clock::{Anomaly, Reading};
+use crate::cold;
+use crate::mlock;
+use crate::rng::Rng;
+use crate::stats::Summary;
+use crate::thermal;
+use crate::{measure, LinkedList, Node};
+
+/// Bytes of node data (payload + link) a traversal of a `LinkedList<usize>`
+/// touches per visited node, not counting allocator padding.
+pub const NODE_BYTES: usize = std::mem::size_of::<Node<usize>>();
+
+/// One timed traversal.
+#[derive(Clone, Copy)]
+pub struct Sample {
+    pub visited: usize,
+    pub time: Duration,
+    pub cycles: u64,
+    pub anomaly: Option<Anomaly>,
+}
+
+impl Sample {
+    pub fn new(visited: usize, reading: Reading) -> Self {
+        Sample {
+            visited,
+            time: reading.time,
+            cycles: reading.cycles,
+            anomaly: reading.anomaly,
+        }
+    }
+
+    pub fn ns_per_node(&self) -> f64 {
+        self.time.as_nanos() as f64 / self.visited.max(1) as f64
+    }
+
+    pub fn cycles_per_node(&self) -> f64 {
+        self.cycles as f64 / self.visited.max(1) as f64
+    }
+
+    pub fn nodes_per_second(&self) -> f64 {
+        self.visited as f64 / self.time.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+
+    /// Effective bandwidth in bytes of node data per second.
+    pub fn bytes_per_second(&self) -> f64 {
+        self.nodes_per_second() * NODE_BYTES as f64
+    }
+}
+
+/// All samples taken for one benchmark at one list size.
+pub struct BenchResult {
+    pub name: String,
+    pub nodes: usize,
+    pub samples: Vec<Sample>,
+    pub layout: Layout,
+}
+
+impl BenchResult {
+    /// Samples whose cycle count can be trusted; statistics only use these.
+    pub fn valid_samples(&self) -> impl Iterator<Item = &Sample> {
+        self.samples.iter().filter(|s| s.anomaly.is_none())
+    }
+
+    /// Number of samples excluded from statistics as anomalous.
+    pub fn flagged(&self) -> usize {
+        self.samples.len() - self.valid_samples().count()
+    }
+
+    /// Flagged samples by kind: (CPU migration, counter went backwards,
+    /// implausible frequency).
+    pub fn flagged_by_kind(&self) -> (usize, usize, usize) {
+        self.samples
+            .iter()
+            .fold((0, 0, 0), |(m, b, f), s| match s.anomaly {
+                Some(Anomaly::Migrated { .. }) => (m + 1, b, f),
+                Some(Anomaly::Backwards) => (m, b + 1, f),
+                Some(Anomaly::Frequency { .. }) => (m, b, f + 1),
+                None => (m, b, f),
+            })
+    }
+
+    pub fn ns_per_node(&self) -> Summary {
+        Summary::of(
+            &self
+                .valid_samples()
+                .map(Sample::ns_per_node)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn nodes_per_second(&self) -> Summary {
+        Summary::of(
+            &self
+                .valid_samples()
+                .map(Sample::nodes_per_second)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn bytes_per_second(&self) -> Summary {
+        Summary::of(
+            &self
+                .valid_samples()
+                .map(Sample::bytes_per_second)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn cycles_per_node(&self) -> Summary {
+        Summary::of(
+            &self
+                .valid_samples()
+                .map(Sample::cycles_per_node)
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// Times `iterations` back-to-back traversals of an already built list.
+pub fn time_traversals<T>(list: &LinkedList<T>, iterations: usize) -> Vec<Sample> {
+    (0..iterations.max(1))
+        .map(|_| {
+            cold::before(list);
+            let (visited, reading) = list.benchmark_traversal();
+            thermal::tick();
+            Sample::new(visited, reading)
+        })
+        .collect()
+}
+
+/// Times `iterations` write traversals (every payload incremented) of an
+/// already built list.
+pub fn time_write_traversals(list: &mut LinkedList<usize>, iterations: usize) -> Vec<Sample> {
+    (0..iterations.max(1))
+        .map(|_| {
+            cold::before(list);
+            let (visited, reading) = list.benchmark_write_traversal();
+            thermal::tick();
+            Sample::new(visited, reading)
+        })
+        .collect()
+}
+
+/// Times `iterations` runs of `f`, each of which performs `operations` of
+/// whatever a microbenchmark counts (loads, accesses, elements). The
+/// per-node accessors of the returned samples then read as per-operation.
+pub fn time_repeated<R>(
+    iterations: usize,
+    operations: usize,
+    mut f: impl FnMut() -> R,
+) -> Vec<Sample> {
+    (0..iterations.max(1))
+        .map(|_| {
+            let (result, reading) = measure(&mut f);
+            std::hint::black_box(result);
+            thermal::tick();
+            Sample::new(operations, reading)
+        })
+        .collect()
+}
+
+/// Median-ready ns and cycles per operation over the unflagged samples, plus
+/// the number of flagged ones.
+pub fn per_operation(samples: &[Sample]) -> (Summary, Summary, usize) {
+    let valid: Vec<&Sample> = samples.iter().filter(|s| s.anomaly.is_none()).collect();
+    let ns = Summary::of(&valid.iter().map(|s| s.ns_per_node()).collect::<Vec<_>>());
+    let cycles = Summary::of(
+        &valid
+            .iter()
+            .map(|s| s.cycles_per_node())
+            .collect::<Vec<_>>(),
+    );
+    (ns, cycles, samples.len() - valid.len())
+}
+
+/// Printed in place of a statistic when every sample it would come from was
+/// flagged, where a 0 would read as a measurement. One word, so tables stay
+/// split into the same columns on whitespace.
+pub const ALL_FLAGGED: &str = "flagged";
+
+/// `value`, one of `summary`'s statistics, to `precision` places; or
+/// [`ALL_FLAGGED`] when `summary` has no samples.
+pub fn stat(summary: &Summary, value: f64, precision: usize) -> String {
+    if summary.count == 0 {
+        ALL_FLAGGED.to_string()
+    } else {
+        format!("{:.*}", precision, value)
+    }
+}
+
+/// The ratio of two medians as `1.23x`; [`ALL_FLAGGED`] when either has no
+/// samples.
+pub fn ratio(value: &Summary, baseline: &Summary, precision: usize) -> String {
+    if value.count == 0 || baseline.count == 0 {
+        ALL_FLAGGED.to_string()
+    } else {
+        format!(
+            "{:.*}x",
+            precision,
+            value.median / baseline.median.max(f64::MIN_POSITIVE)
+        )
+    }
+}
+
+/// Checksum `benchmark_checked_traversal` must produce for a list built by
+/// pushing `0..nodes`, i.e. holding `nodes - 1, nodes - 2, ..., 0`.
+pub fn expected_checksum(nodes: usize) -> u64 {
+    (0..nodes).fold(0u64, |sum, position| {
+        let data = (nodes - 1 - position) as u64;
+        sum.wrapping_add(data.wrapping_mul(position as u64 + 1))
+    })
+}
+
+/// Like [`time_traversals`], but each iteration checks the visited count and
+/// payload checksum of a list built from `0..n`, and exits the process on any
+/// mismatch: a traversal that skips nodes must not pass as a fast one.
+pub fn time_verified_traversals(list: &LinkedList<usize>, iterations: usize) -> Vec<Sample> {
+    let expected = expected_checksum(list.count);
+    (0..iterations.max(1))
+        .map(|iteration| {
+            cold::before(list);
+            let (visited, checksum, reading) = list.benchmark_checked_traversal();
+            if visited != list.count || checksum != expected {
+                eprintln!(
+                    "VERIFICATION FAILED on iteration {}: visited {} of {} nodes, \
+                     checksum {:#018x}, expected {:#018x}",
+                    iteration + 1,
+                    visited,
+                    list.count,
+                    checksum,
+                    expected
+                );
+                process::exit(2);
+            }
+            thermal::tick();
+            Sample::new(visited, reading)
+        })
+        .collect()
+}
+
+/// Warm-up traversals run before estimating the cost of one.
+const WARMUPS: usize = 3;
+
+/// Bounds on the iteration count a time budget picks: enough samples for
+/// the statistics to mean something, few enough that tiny lists don't
+/// collect millions of them.
+pub const MIN_BUDGET_SAMPLES: usize = 5;
+pub const MAX_BUDGET_SAMPLES: usize = 10_000;
+
+/// How many traversals to time: a fixed count, or as many as fit in a
+/// wall-clock budget.
+#[derive(Clone, Copy)]
+pub enum Iterations {
+    Count(usize),
+    Budget(Duration),
+}
+
+impl Iterations {
+    /// The count for `list`. A budget runs [`WARMUPS`] traversals, takes
+    /// the fastest as the cost of one and divides the budget by it, within
+    /// [`MIN_BUDGET_SAMPLES`]..=[`MAX_BUDGET_SAMPLES`].
+    pub fn resolve<T>(self, list: &LinkedList<T>) -> usize {
+        match self {
+            Iterations::Count(count) => count.max(1),
+            Iterations::Budget(budget) => {
+                let one = time_traversals(list, WARMUPS)
+                    .iter()
+                    .map(|s| s.time)
+                    .min()
+                    .unwrap_or_default();
+                let fit = budget.as_nanos() / one.as_nanos().max(1);
+                (fit.min(MAX_BUDGET_SAMPLES as u128) as usize).max(MIN_BUDGET_SAMPLES)
+            }
+        }
+    }
+}
+
+/// Parses a `--measure-for` budget: a number with an `ms`, `s` or `m`
+/// suffix, seconds when there is none.
+pub fn parse_budget(text: &str) -> Option<Duration> {
+    let (number, scale) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 1e-3)
+    } else if let Some(s) = text.strip_suffix('s') {
+        (s, 1.0)
+    } else if let Some(m) = text.strip_suffix('m') {
+        (m, 60.0)
+    } else {
+        (text, 1.0)
+    };
+    let seconds = number.parse::<f64>().ok()? * scale;
+    (seconds.is_finite() && seconds > 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Builds a list of `nodes` elements and times `iterations` traversals of it,
+/// checking each one when `verify` is set.
+pub fn traverse(nodes: usize, iterations: Iterations, verify: bool) -> BenchResult {
+    let list = build(nodes);
+    let _locked = mlock::before(&list);
+    let iterations = iterations.resolve(&list);
+    let samples = if verify {
+        time_verified_traversals(&list, iterations)
+    } else {
+        time_traversals(&list, iterations)
+    };
+    BenchResult {
+        name: "traverse".to_string(),
+        nodes,
+        samples,
+        layout: cache::node_layout(&list),
+    }
+}
+
+/// Like [`traverse`], then times as many write traversals of the same list
+/// as reads, so both results share one layout. Returns (read, write).
+pub fn traverse_read_write(
+    nodes: usize,
+    iterations: Iterations,
+    verify: bool,
+) -> (BenchResult, BenchResult) {
+    let mut list = build(nodes);
+    let _locked = mlock::before(&list);
+    let iterations = iterations.resolve(&list);
+    let read_samples = if verify {
+        time_verified_traversals(&list, iterations)
+    } else {
+        time_traversals(&list, iterations)
+    };
+    let write_samples = time_write_traversals(&mut list, iterations);
+    let layout = cache::node_layout(&list);
+    let result = |name: &str, samples| BenchResult {
+        name: name.to_string(),
+        nodes,
+        samples,
+        layout,
+    };
+    (
+        result("traverse", read_samples),
+        result("traverse-write", write_samples),
+    )
+}
+
+/// A list of `nodes` elements (same payloads as [`traverse`] builds) whose
+/// links visit the nodes in a random order of their addresses, so neither
+/// the allocator's sequential layout nor the prefetcher helps a traversal.
+pub fn build_shuffled(nodes: usize, seed: u64) -> LinkedList<usize> {
+    let mut boxes: Vec<Box<Node<usize>>> = (0..nodes)
+        .map(|i| {
+            Box::new(Node {
+                data: i,
+                next: None,
+            })
+        })
+        .collect();
+    Rng::new(seed).shuffle(&mut boxes);
+    let mut list = LinkedList::new();
+    for node in boxes {
+        list.push_node(node);
+    }
+    list
+}
+
+/// A list built by pushing `0..nodes`, nodes in allocation order.
+pub fn build(nodes: usize) -> LinkedList<usize> {
+    let mut list = LinkedList::new();
+    for i in 0..nodes {
+        list.push(i);
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fully_flagged_statistics_say_so() {
+        let none = Summary::of(&[]);
+        let some = Summary::of(&[2.0, 4.0, 3.0]);
+        assert_eq!(stat(&some, some.median, 2), "3.00");
+        assert_eq!(stat(&none, none.median, 2), ALL_FLAGGED);
+        assert_eq!(ratio(&some, &Summary::of(&[1.5]), 1), "2.0x");
+        assert_eq!(ratio(&some, &none, 2), ALL_FLAGGED);
+        assert_eq!(ratio(&none, &some, 2), ALL_FLAGGED);
+    }
+}
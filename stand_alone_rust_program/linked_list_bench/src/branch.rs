@@ -0,0 +1,158 @@
+use std::hint::black_box;
+
+use crate::bench::{self, Sample};
+use crate::counters::{Counter, Event};
+use crate::rng::Rng;
+use crate::stats;
+use crate::{measure, LinkedList};
+
+// Payloads are uniform in 0..RANGE and the branch is taken for the upper
+// half, so a shuffled list mispredicts about half the time.
+const RANGE: usize = 256;
+const THRESHOLD: usize = RANGE / 2;
+
+/// `branch [--nodes <n>] [--iterations <k>]`: the "why is sorted data
+/// faster" demo on a linked list. Two lists hold the same payloads, one in
+/// sorted order and one shuffled, and a traversal sums only the payloads
+/// above a threshold. The work is identical; only the predictability of the
+/// branch differs, so the gap in cycles per node is the cost of
+/// mispredictions. Where the kernel allows it, branch and branch-miss
+/// counters confirm the cause.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 16;
+    let mut iterations: usize = 20;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete branch option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    let mut rng = Rng::new(nodes as u64);
+    let shuffled: Vec<usize> = (0..nodes).map(|_| rng.below(RANGE)).collect();
+    let mut sorted = shuffled.clone();
+    sorted.sort_unstable();
+
+    let mut branches = Counter::open(Event::Branches);
+    let mut misses = Counter::open(Event::BranchMisses);
+
+    println!("--- Branch Prediction (sorted vs shuffled) ---");
+    println!(
+        "{} nodes, payloads in 0..{}, branch taken when >= {}",
+        nodes, RANGE, THRESHOLD
+    );
+    println!(
+        "{:<10} {:>6} {:>10} {:>12} {:>10} {:>14} {:>12} {:>8}",
+        "Order",
+        "Iters",
+        "ns/node",
+        "cycles/node",
+        "stddev",
+        "branches/node",
+        "miss rate",
+        "Flagged"
+    );
+
+    let mut summaries = Vec::new();
+    let mut cycle_samples = Vec::new();
+    for (name, payloads) in [("sorted", &sorted), ("shuffled", &shuffled)] {
+        let mut list = LinkedList::new();
+        for &value in payloads.iter().rev() {
+            list.push(value);
+        }
+        black_box(conditional_sum(&list));
+
+        let mut samples = Vec::new();
+        let (mut branch_count, mut miss_count) = (0u64, 0u64);
+        for _ in 0..iterations.max(1) {
+            for counter in [&branches, &misses].into_iter().flatten() {
+                counter.reset_and_enable();
+            }
+            let (sum, reading) = measure(|| conditional_sum(&list));
+            miss_count += misses.as_mut().map_or(0, Counter::disable_and_read);
+            branch_count += branches.as_mut().map_or(0, Counter::disable_and_read);
+            black_box(sum);
+            samples.push(Sample::new(list.count, reading));
+        }
+
+        let (ns, cycles, flagged) = bench::per_operation(&samples);
+        let visited = (samples.len() * list.count).max(1) as f64;
+        let per_node = match &branches {
+            Some(_) => format!("{:.2}", branch_count as f64 / visited),
+            None => "unavailable".to_string(),
+        };
+        let miss_rate = match (&branches, &misses) {
+            (Some(_), Some(_)) if branch_count > 0 => {
+                format!("{:.2}%", miss_count as f64 / branch_count as f64 * 100.0)
+            }
+            _ => "unavailable".to_string(),
+        };
+        println!(
+            "{:<10} {:>6} {:>10} {:>12} {:>10} {:>14} {:>12} {:>8}",
+            name,
+            samples.len(),
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            per_node,
+            miss_rate,
+            flagged
+        );
+        summaries.push(cycles);
+        cycle_samples.push(
+            samples
+                .iter()
+                .filter(|s| s.anomaly.is_none())
+                .map(Sample::cycles_per_node)
+                .collect::<Vec<f64>>(),
+        );
+    }
+
+    let (sorted, shuffled) = (&summaries[0], &summaries[1]);
+    let delta = if sorted.count == 0 || shuffled.count == 0 {
+        bench::ALL_FLAGGED.to_string()
+    } else {
+        format!("{:+.2}", shuffled.median - sorted.median)
+    };
+    println!(
+        "\nShuffled costs {} cycles/node ({} sorted)",
+        delta,
+        bench::ratio(shuffled, sorted, 2)
+    );
+    match stats::welch_t_test(&cycle_samples[0], &cycle_samples[1]) {
+        Some(test) => println!(
+            "Welch's t-test: t = {:.2}, p = {:.4}{}",
+            test.statistic,
+            test.p_value,
+            if test.p_value < 0.05 {
+                " (significant)"
+            } else {
+                ""
+            }
+        ),
+        None => println!(
+            "Welch's t-test: undefined (needs two unflagged samples per order and some variance)"
+        ),
+    }
+}
+
+/// Sums the payloads at or above the threshold. The add goes through
+/// `black_box` so the compiler keeps a real branch instead of turning the
+/// condition into a conditional move, which would hide the effect.
+fn conditional_sum(list: &LinkedList<usize>) -> usize {
+    let mut sum = 0;
+    let mut current = &list.head;
+    while let Some(node) = current {
+        if node.data >= THRESHOLD {
+            sum = black_box(sum + node.data);
+        }
+        current = &node.next;
+    }
+    sum
+}
@@ -0,0 +1,378 @@
+use std::mem;
+
+use crate::counters::{Counter, Event};
+use crate::{LinkedList, Node};
+
+// Rough load-to-use latencies in cycles by where the line lives. They vary by
+// a factor of two between microarchitectures, which is fine for a model
+// whose job is to flag order-of-magnitude surprises.
+const L1_LATENCY: f64 = 4.0;
+const L2_LATENCY: f64 = 14.0;
+const L3_LATENCY: f64 = 45.0;
+const DRAM_LATENCY: f64 = 200.0;
+
+/// A data (or unified) cache level of CPU 0.
+pub struct CacheLevel {
+    pub level: u32,
+    pub size: usize,
+    pub line: usize,
+}
+
+/// Reads the data cache hierarchy from sysfs (sysctl on macOS), smallest
+/// level first. Empty when the kernel does not expose it (or under Miri,
+/// which has neither).
+pub fn detect() -> Vec<CacheLevel> {
+    if cfg!(miri) {
+        return Vec::new();
+    }
+    #[cfg(target_os = "macos")]
+    return detect_sysctl();
+    #[cfg(not(target_os = "macos"))]
+    detect_sysfs()
+}
+
+/// Apple Silicon reports per-cluster sizes under `hw.perflevel0` (the
+/// performance cores); Intel Macs only have the flat `hw.l*cachesize`.
+#[cfg(target_os = "macos")]
+fn detect_sysctl() -> Vec<CacheLevel> {
+    use crate::sysctl;
+
+    let line = sysctl::u64("hw.cachelinesize").unwrap_or(64) as usize;
+    (1..=3)
+        .filter_map(|level| {
+            let name = if level == 1 {
+                "l1dcachesize".to_string()
+            } else {
+                format!("l{}cachesize", level)
+            };
+            let size = sysctl::u64(&format!("hw.perflevel0.{}", name))
+                .or_else(|| sysctl::u64(&format!("hw.{}", name)))
+                .filter(|&size| size > 0)?;
+            Some(CacheLevel {
+                level,
+                size: size as usize,
+                line,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn detect_sysfs() -> Vec<CacheLevel> {
+    let mut levels = Vec::new();
+    for index in 0.. {
+        let dir = format!("/sys/devices/system/cpu/cpu0/cache/index{}", index);
+        let read = |name: &str| {
+            std::fs::read_to_string(format!("{}/{}", dir, name)).map(|s| s.trim().to_string())
+        };
+        let Ok(kind) = read("type") else {
+            break;
+        };
+        if kind == "Instruction" {
+            continue;
+        }
+        let level = read("level").ok().and_then(|l| l.parse().ok());
+        let size = read("size").ok().and_then(|s| parse_size(&s));
+        let line = read("coherency_line_size")
+            .ok()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(64);
+        if let (Some(level), Some(size)) = (level, size) {
+            levels.push(CacheLevel { level, size, line });
+        }
+    }
+    levels.sort_by_key(|l| l.level);
+    levels
+}
+
+#[cfg(not(target_os = "macos"))]
+fn parse_size(text: &str) -> Option<usize> {
+    let (digits, scale) = match text.as_bytes().last()? {
+        b'K' => (&text[..text.len() - 1], 1 << 10),
+        b'M' => (&text[..text.len() - 1], 1 << 20),
+        b'G' => (&text[..text.len() - 1], 1 << 30),
+        _ => (text, 1),
+    };
+    digits.parse::<usize>().ok().map(|n| n * scale)
+}
+
+/// Where the nodes of a list actually ended up in memory.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    /// Median distance in bytes between a node and its successor.
+    pub median_stride: usize,
+    /// Fraction of links pointing at a node at most one cache line away.
+    pub adjacent_fraction: f64,
+    /// Misses the hardware counted over one traversal; `None` where the
+    /// counters can't be read, and for results loaded from a file.
+    pub misses: Option<Misses>,
+}
+
+/// Cache misses per visited node, from the hardware's event counters.
+#[derive(Clone, Copy)]
+pub struct Misses {
+    pub l1d: f64,
+    pub llc: f64,
+}
+
+/// Walks the list and measures how the allocator laid the nodes out, and
+/// what that costs in misses where the counters allow. Run it after timing:
+/// it warms the cache like any other traversal.
+pub fn node_layout<T>(list: &LinkedList<T>) -> Layout {
+    let mut strides = Vec::with_capacity(list.count.saturating_sub(1));
+    let mut current = &list.head;
+    while let Some(node) = current {
+        if let Some(next) = &node.next {
+            let from = &**node as *const Node<T> as usize;
+            let to = &**next as *const Node<T> as usize;
+            strides.push(from.abs_diff(to));
+        }
+        current = &node.next;
+    }
+
+    if strides.is_empty() {
+        return Layout {
+            median_stride: mem::size_of::<Node<T>>(),
+            adjacent_fraction: 1.0,
+            misses: count_misses(list),
+        };
+    }
+    let adjacent = strides.iter().filter(|&&s| s <= 64).count();
+    let adjacent_fraction = adjacent as f64 / strides.len() as f64;
+    strides.sort_unstable();
+    Layout {
+        median_stride: strides[strides.len() / 2],
+        adjacent_fraction,
+        misses: count_misses(list),
+    }
+}
+
+/// Counts L1d and last-level read misses over one plain traversal of a
+/// list already in cache, the state the timed traversals run in.
+fn count_misses<T>(list: &LinkedList<T>) -> Option<Misses> {
+    let mut l1d = Counter::open(Event::L1dReadMisses)?;
+    let mut llc = Counter::open(Event::LlcReadMisses)?;
+    l1d.reset_and_enable();
+    llc.reset_and_enable();
+    let mut visited = 0usize;
+    let mut current = &list.head;
+    while let Some(node) = current {
+        visited += 1;
+        current = std::hint::black_box(&node.next);
+    }
+    let llc_count = llc.disable_and_read();
+    let l1d_count = l1d.disable_and_read();
+    let visited = visited.max(1) as f64;
+    Some(Misses {
+        l1d: l1d_count as f64 / visited,
+        llc: llc_count as f64 / visited,
+    })
+}
+
+/// Expected traversal cost of a list from its size, its layout and the
+/// cache hierarchy.
+pub struct Model {
+    /// Cache level the whole list fits in ("L1", "L2", ..., or "DRAM").
+    pub resident: String,
+    /// Bytes of memory each node occupies including allocator overhead.
+    pub footprint: usize,
+    /// Misses of the level above `resident` per visited node: counted when
+    /// the layout carries hardware counts, else expected from the layout.
+    pub misses_per_node: f64,
+    /// Whether `misses_per_node` was counted rather than modelled.
+    pub counted: bool,
+    /// Expected cycles per node with the measured layout.
+    pub expected_cycles: f64,
+    /// Expected cycles per node if every link were a random jump.
+    pub random_cycles: f64,
+}
+
+pub fn model<T>(nodes: usize, layout: &Layout, levels: &[CacheLevel]) -> Model {
+    let node_size = mem::size_of::<Node<T>>();
+    // A densely packed list shows the allocator's real chunk size; a
+    // scattered one falls back to the node plus a malloc header.
+    let footprint = if layout.adjacent_fraction > 0.5 {
+        layout.median_stride.max(node_size)
+    } else {
+        node_size + 16
+    };
+    let working_set = nodes * footprint;
+    let line = levels.first().map(|l| l.line).unwrap_or(64);
+
+    let latencies = [L1_LATENCY, L2_LATENCY, L3_LATENCY];
+    let (resident, latency) = levels
+        .iter()
+        .zip(latencies)
+        .find(|(level, _)| working_set <= level.size)
+        .map(|(level, latency)| (format!("L{}", level.level), latency))
+        .unwrap_or(("DRAM".to_string(), DRAM_LATENCY));
+
+    if latency == L1_LATENCY {
+        return Model {
+            resident,
+            footprint,
+            misses_per_node: 0.0,
+            counted: false,
+            expected_cycles: L1_LATENCY,
+            random_cycles: L1_LATENCY,
+        };
+    }
+
+    // A list resident in L2 or L3 costs its L1 misses, which mostly miss
+    // L2 too once the list outgrows it; a DRAM-resident list costs its
+    // last-level misses.
+    let counted = layout.misses.map(|misses| {
+        if latency == DRAM_LATENCY {
+            misses.llc
+        } else {
+            misses.l1d
+        }
+    });
+    // Otherwise: adjacent nodes share a line (and the hardware prefetcher
+    // sees the stream), so they cost a fraction of a miss each; every other
+    // link is a full miss to wherever the list lives.
+    let sequential_misses = (footprint as f64 / line as f64).min(1.0);
+    let misses_per_node = counted
+        .unwrap_or(layout.adjacent_fraction * sequential_misses + (1.0 - layout.adjacent_fraction));
+    let expected_cycles = L1_LATENCY + misses_per_node * (latency - L1_LATENCY);
+    Model {
+        resident,
+        footprint,
+        misses_per_node,
+        counted: counted.is_some(),
+        expected_cycles,
+        random_cycles: latency,
+    }
+}
+
+/// Explains a measured cycles-per-node figure against the model, in one line.
+pub fn verdict(model: &Model, layout: &Layout, measured: f64) -> &'static str {
+    if measured < model.random_cycles / 3.0 && layout.adjacent_fraction > 0.5 {
+        "much faster than a random layout: the allocator handed out contiguous nodes"
+    } else if measured > model.expected_cycles * 2.0 {
+        if model.counted {
+            "slower than the counted misses explain: suspect TLB misses, frequency scaling or contention"
+        } else {
+            "slower than the model: suspect TLB misses, frequency scaling or contention"
+        }
+    } else if measured < model.expected_cycles / 2.0 {
+        if model.counted {
+            "faster than the counted misses explain: the misses overlap, or this CPU's latencies are lower"
+        } else {
+            "faster than the model: the prefetcher or a larger effective cache is hiding misses"
+        }
+    } else if model.counted {
+        "consistent with the counted misses"
+    } else {
+        "consistent with the model"
+    }
+}
+
+/// Prints the model for one measured result; `measured` is `None` when
+/// every sample was flagged.
+pub fn print_model<T>(nodes: usize, layout: &Layout, measured: Option<f64>) {
+    let levels = detect();
+    let model = model::<T>(nodes, layout, &levels);
+    println!("\n[Cache Model]");
+    if levels.is_empty() {
+        println!("Cache sizes:     unknown (no sysfs cache info), assuming DRAM-resident");
+    } else {
+        let sizes: Vec<String> = levels
+            .iter()
+            .map(|l| format!("L{} {} KiB", l.level, l.size >> 10))
+            .collect();
+        println!("Cache sizes:     {}", sizes.join(", "));
+    }
+    println!(
+        "Node footprint:  {} bytes ({} bytes payload+link)",
+        model.footprint,
+        mem::size_of::<Node<T>>()
+    );
+    println!(
+        "Layout:          {:.0}% of links adjacent, median stride {} bytes",
+        layout.adjacent_fraction * 100.0,
+        layout.median_stride
+    );
+    println!(
+        "Working set:     {} KiB, resident in {}",
+        (nodes * model.footprint) >> 10,
+        model.resident
+    );
+    if model.counted {
+        println!(
+            "Counted misses:  {:.2} per node (hardware counters)",
+            model.misses_per_node
+        );
+    } else {
+        println!("Expected misses: {:.2} per node", model.misses_per_node);
+    }
+    println!(
+        "Expected cycles: {:.2} per node (random layout: {:.2})",
+        model.expected_cycles, model.random_cycles
+    );
+    match measured {
+        Some(measured) => println!(
+            "Measured cycles: {:.2} per node -> {}",
+            measured,
+            verdict(&model, layout, measured)
+        ),
+        None => println!("Measured cycles: none, every sample was flagged"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels() -> Vec<CacheLevel> {
+        [(1, 32 << 10), (2, 1 << 20), (3, 32 << 20)]
+            .into_iter()
+            .map(|(level, size)| CacheLevel {
+                level,
+                size,
+                line: 64,
+            })
+            .collect()
+    }
+
+    fn scattered(misses: Option<Misses>) -> Layout {
+        Layout {
+            median_stride: 4096,
+            adjacent_fraction: 0.0,
+            misses,
+        }
+    }
+
+    #[test]
+    fn model_assumes_a_miss_per_scattered_node_without_counters() {
+        let model = model::<usize>(1 << 24, &scattered(None), &levels());
+        assert_eq!(model.resident, "DRAM");
+        assert!(!model.counted);
+        assert_eq!(model.misses_per_node, 1.0);
+        assert_eq!(model.expected_cycles, DRAM_LATENCY);
+    }
+
+    #[test]
+    fn model_uses_counted_misses_of_the_resident_level() {
+        let misses = Some(Misses {
+            l1d: 0.5,
+            llc: 0.25,
+        });
+        let dram = model::<usize>(1 << 24, &scattered(misses), &levels());
+        assert!(dram.counted);
+        assert_eq!(dram.misses_per_node, 0.25);
+        assert_eq!(
+            dram.expected_cycles,
+            L1_LATENCY + 0.25 * (DRAM_LATENCY - L1_LATENCY)
+        );
+        assert_eq!(
+            verdict(&dram, &scattered(misses), dram.expected_cycles),
+            "consistent with the counted misses"
+        );
+
+        // 4096 nodes * 32 bytes fits in L2, whose hits are the L1 misses.
+        let l2 = model::<usize>(4096, &scattered(misses), &levels());
+        assert_eq!(l2.resident, "L2");
+        assert_eq!(l2.misses_per_node, 0.5);
+    }
+}
@@ -0,0 +1,83 @@
+use std::hint::black_box;
+
+use crate::bench;
+use crate::rng::Rng;
+
+/// `chase [--min <bytes>] [--max <bytes>] [--steps <k>] [--iterations <n>]`:
+/// the classic memory-latency microbenchmark. A buffer of indices holds one
+/// random cycle through all of its slots, so every load depends on the one
+/// before it and neither the out-of-order core nor the prefetcher can run
+/// ahead. Cycles per load is the latency of whichever level the buffer fits
+/// in: the floor a linked list traversal with scattered nodes can reach.
+pub fn run(args: &[String]) {
+    let mut min_bytes: usize = 1 << 12;
+    let mut max_bytes: usize = 1 << 26;
+    let mut steps: usize = 1 << 20;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--min", Some(v)) => min_bytes = v.parse().unwrap_or(min_bytes),
+            ("--max", Some(v)) => max_bytes = v.parse().unwrap_or(max_bytes),
+            ("--steps", Some(v)) => steps = v.parse().unwrap_or(steps),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete chase option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    println!("--- Pointer Chase Latency ---");
+    println!("{} dependent loads per sample", steps);
+    println!(
+        "{:>12} {:>12} {:>6} {:>10} {:>12} {:>10} {:>8}",
+        "Bytes", "Slots", "Iters", "ns/load", "cycles/load", "stddev", "Flagged"
+    );
+
+    let slot = std::mem::size_of::<usize>();
+    let mut bytes = min_bytes.max(2 * slot);
+    while bytes <= max_bytes {
+        let buffer = random_cycle(bytes / slot, bytes as u64);
+        // One lap to fault in the pages and warm whatever caches can hold it.
+        black_box(chase(&buffer, buffer.len()));
+
+        let samples = bench::time_repeated(iterations, steps, || chase(&buffer, steps));
+        let (ns, cycles, flagged) = bench::per_operation(&samples);
+        println!(
+            "{:>12} {:>12} {:>6} {:>10} {:>12} {:>10} {:>8}",
+            bytes,
+            buffer.len(),
+            samples.len(),
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            flagged
+        );
+
+        match bytes.checked_mul(2) {
+            Some(next) => bytes = next,
+            None => break,
+        }
+    }
+}
+
+/// A permutation of `0..slots` that is a single cycle (Sattolo's algorithm),
+/// so a chase from any slot visits every slot before repeating.
+fn random_cycle(slots: usize, seed: u64) -> Vec<usize> {
+    let mut next: Vec<usize> = (0..slots).collect();
+    let mut rng = Rng::new(seed);
+    for i in (1..slots).rev() {
+        next.swap(i, rng.below(i));
+    }
+    next
+}
+
+fn chase(buffer: &[usize], steps: usize) -> usize {
+    let mut index = 0;
+    for _ in 0..steps {
+        index = buffer[index];
+    }
+    black_box(index)
+}
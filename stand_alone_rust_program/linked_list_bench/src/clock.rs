@@ -0,0 +1,503 @@
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm", not(miri)))]
+use js::Instant;
+#[cfg(all(
+    not(target_os = "macos"),
+    not(all(target_arch = "wasm32", feature = "wasm")),
+    not(miri)
+))]
+use std::time::Instant;
+
+// These are specific to x86 processors; 32-bit x86 has the same intrinsics
+// in its own module.
+#[cfg(all(target_arch = "x86", not(miri)))]
+use std::arch::x86::{_mm_lfence, _rdtsc};
+#[cfg(all(target_arch = "x86_64", not(miri)))]
+use std::arch::x86_64::{_mm_lfence, _rdtsc};
+
+/// Why a measurement's cycle count can't be trusted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anomaly {
+    /// The thread started and finished on different CPUs, whose cycle
+    /// counters need not agree (different sockets, unsynchronised TSCs).
+    Migrated { from: i32, to: i32 },
+    /// The counter read lower at the end than at the start.
+    Backwards,
+    /// Cycles per nanosecond fell outside the plausible band for this CPU
+    /// (SMIs, frequency transitions, VM stolen time, a clock jump).
+    Frequency { ghz: f64 },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Anomaly::Migrated { from, to } => write!(f, "migrated:{}:{}", from, to),
+            Anomaly::Backwards => write!(f, "backwards"),
+            Anomaly::Frequency { ghz } => write!(f, "frequency:{:.3}", ghz),
+        }
+    }
+}
+
+impl Anomaly {
+    /// Inverse of the `Display` form, as stored in results files.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.split(':').collect::<Vec<_>>().as_slice() {
+            ["migrated", from, to] => Some(Anomaly::Migrated {
+                from: from.parse().ok()?,
+                to: to.parse().ok()?,
+            }),
+            ["backwards"] => Some(Anomaly::Backwards),
+            ["frequency", ghz] => Some(Anomaly::Frequency {
+                ghz: ghz.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Elapsed wall time and cycles of one measurement.
+pub struct Reading {
+    pub time: Duration,
+    /// Zero when the counter went backwards.
+    pub cycles: u64,
+    pub anomaly: Option<Anomaly>,
+}
+
+/// Both clocks as read at the start of a measurement; see [`start`].
+pub struct Start {
+    cpu: Option<i32>,
+    #[cfg(all(not(target_os = "macos"), not(miri)))]
+    time: Instant,
+    /// `mach_absolute_time` ticks.
+    #[cfg(all(target_os = "macos", not(miri)))]
+    time: u64,
+    #[cfg(miri)]
+    time: Duration,
+    cycles: u64,
+}
+
+/// Notes the current CPU, then reads wall time and the cycle counter.
+pub fn start() -> Start {
+    let cpu = current_cpu();
+    let time = wall_now();
+    Start {
+        cpu,
+        time,
+        cycles: cycles_now(),
+    }
+}
+
+/// Elapsed wall time and cycles since `start`: the cycle counter is read
+/// first so the measured region ends as early as possible. A migration to
+/// another CPU or a counter that went backwards is flagged rather than
+/// turned into a nonsense (or underflowed) cycle count.
+pub fn stop(start: &Start) -> Reading {
+    let end_cycles = cycles_now();
+    let time = wall_elapsed(start);
+    let cpu = current_cpu();
+
+    let Some(cycles) = end_cycles.checked_sub(start.cycles) else {
+        return Reading {
+            time,
+            cycles: 0,
+            anomaly: Some(Anomaly::Backwards),
+        };
+    };
+    let anomaly = match (start.cpu, cpu) {
+        (Some(from), Some(to)) if from != to => Some(Anomaly::Migrated { from, to }),
+        _ => check_frequency(time, cycles),
+    };
+    Reading {
+        time,
+        cycles,
+        anomaly,
+    }
+}
+
+/// The raw cycle counter, for timestamps inside one measured region. Only
+/// differences between reads on the same CPU mean anything.
+pub fn timestamp() -> u64 {
+    cycles_now()
+}
+
+/// Median cycles between two back-to-back [`timestamp`]s: what a region
+/// bracketed by them pays for the reads themselves.
+pub fn timestamp_cost() -> f64 {
+    let gaps: Vec<f64> = (0..1000)
+        .map(|_| {
+            let a = timestamp();
+            let b = timestamp();
+            b.saturating_sub(a) as f64
+        })
+        .collect();
+    crate::stats::Summary::of(&gaps).median
+}
+
+/// Range of cycles-per-nanosecond a sane measurement can show on this CPU.
+pub struct FrequencyBand {
+    pub low_ghz: f64,
+    pub high_ghz: f64,
+    pub source: &'static str,
+    /// Shortest measurement judged against the band; see [`min_checked`].
+    pub min_checked: Duration,
+}
+
+// Allowed deviation from the expected rate. Wall-clock reads cost tens of
+// nanoseconds, so short measurements legitimately wobble by a few percent.
+const FREQUENCY_TOLERANCE: f64 = 0.10;
+
+// Below this no measurement is judged, however cheap the wall clock.
+const MIN_CHECKED_TIME: Duration = Duration::from_micros(1);
+
+/// The band is computed once per process. With an invariant TSC (or any
+/// counter that isn't a core clock) the rate is fixed, so it is calibrated
+/// against the wall clock; a core-clock counter may legitimately run anywhere
+/// between the cpufreq minimum and maximum.
+pub fn frequency_band() -> &'static FrequencyBand {
+    static BAND: OnceLock<FrequencyBand> = OnceLock::new();
+    BAND.get_or_init(|| {
+        if let Some((min_ghz, max_ghz)) = core_clock_range() {
+            return FrequencyBand {
+                low_ghz: min_ghz * (1.0 - FREQUENCY_TOLERANCE),
+                high_ghz: max_ghz * (1.0 + FREQUENCY_TOLERANCE),
+                source: "cpufreq min..max (counter is not invariant)",
+                min_checked: min_checked(),
+            };
+        }
+        let ghz = calibrate_ghz();
+        FrequencyBand {
+            low_ghz: ghz * (1.0 - FREQUENCY_TOLERANCE),
+            high_ghz: ghz * (1.0 + FREQUENCY_TOLERANCE),
+            source: "counter calibrated against wall clock",
+            min_checked: min_checked(),
+        }
+    })
+}
+
+/// [`start`] reads the cycle counter after the wall clock and [`stop`]
+/// before it, so a measurement's wall time also covers the two clock reads
+/// around it and its rate reads low by their share. Judged only from the
+/// length where that share is at most half the tolerance, so a valid short
+/// measurement is never flagged for the overhead alone.
+fn min_checked() -> Duration {
+    let mut empty: Vec<Duration> = (0..100)
+        .map(|_| {
+            let begin = Start {
+                cpu: None,
+                time: wall_now(),
+                cycles: cycles_now(),
+            };
+            cycles_now();
+            wall_elapsed(&begin)
+        })
+        .collect();
+    empty.sort_unstable();
+    empty[empty.len() / 2]
+        .mul_f64(2.0 / FREQUENCY_TOLERANCE)
+        .max(MIN_CHECKED_TIME)
+}
+
+fn check_frequency(time: Duration, cycles: u64) -> Option<Anomaly> {
+    let band = frequency_band();
+    if time < band.min_checked {
+        return None;
+    }
+    let ghz = cycles as f64 / time.as_nanos() as f64;
+    (ghz < band.low_ghz || ghz > band.high_ghz).then_some(Anomaly::Frequency { ghz })
+}
+
+/// Counter rate in GHz over a ~20ms busy wait.
+#[cfg(all(not(windows), not(miri)))]
+fn calibrate_ghz() -> f64 {
+    let begin = Start {
+        cpu: None,
+        time: wall_now(),
+        cycles: cycles_now(),
+    };
+    loop {
+        let elapsed = wall_elapsed(&begin);
+        if elapsed >= Duration::from_millis(20) {
+            let cycles = cycles_now().saturating_sub(begin.cycles);
+            return cycles as f64 / elapsed.as_nanos() as f64;
+        }
+    }
+}
+
+/// Windows documents QueryPerformanceCounter as the reference clock to
+/// measure the TSC against, so calibrate with it directly rather than
+/// through `Instant`.
+#[cfg(all(windows, not(miri)))]
+fn calibrate_ghz() -> f64 {
+    use windows_sys::Win32::System::Performance::{
+        QueryPerformanceCounter, QueryPerformanceFrequency,
+    };
+
+    let qpc = || {
+        let mut ticks = 0i64;
+        // SAFETY: writes one i64 through a valid pointer; cannot fail on
+        // Windows XP and later.
+        unsafe { QueryPerformanceCounter(&mut ticks) };
+        ticks
+    };
+    let mut frequency = 0i64;
+    // SAFETY: as above.
+    unsafe { QueryPerformanceFrequency(&mut frequency) };
+    let frequency = frequency.max(1);
+
+    let (begin_ticks, begin_cycles) = (qpc(), cycles_now());
+    loop {
+        let ticks = qpc() - begin_ticks;
+        if ticks >= frequency / 50 {
+            let cycles = cycles_now().saturating_sub(begin_cycles);
+            let ns = ticks as f64 * 1e9 / frequency as f64;
+            return cycles as f64 / ns;
+        }
+    }
+}
+
+#[cfg(miri)]
+fn calibrate_ghz() -> f64 {
+    mock::GHZ
+}
+
+/// cpufreq limits in GHz when the cycle counter follows the core clock, i.e.
+/// an x86 CPU without `constant_tsc`. `None` when the counter rate is fixed.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "x86"),
+    not(miri)
+))]
+fn core_clock_range() -> Option<(f64, f64)> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    let flags = cpuinfo.lines().find(|l| l.starts_with("flags"))?;
+    if flags.split_whitespace().any(|f| f == "constant_tsc") {
+        return None;
+    }
+    let read_khz = |name: &str| -> Option<f64> {
+        std::fs::read_to_string(format!("/sys/devices/system/cpu/cpu0/cpufreq/{}", name))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    Some((
+        read_khz("cpuinfo_min_freq")? / 1e6,
+        read_khz("cpuinfo_max_freq")? / 1e6,
+    ))
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "x86"),
+    not(miri)
+)))]
+fn core_clock_range() -> Option<(f64, f64)> {
+    None
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+pub fn current_cpu() -> Option<i32> {
+    // vDSO call on Linux, cheap enough to sit just outside the timed region.
+    // SAFETY: no arguments and no preconditions; failure is a negative return.
+    let cpu = unsafe { libc::sched_getcpu() };
+    (cpu >= 0).then_some(cpu)
+}
+
+#[cfg(all(windows, not(miri)))]
+pub fn current_cpu() -> Option<i32> {
+    // Number within the current processor group; a move between groups goes
+    // unnoticed, which only matters on machines with more than 64 CPUs.
+    // SAFETY: no arguments and no preconditions.
+    let cpu = unsafe { windows_sys::Win32::System::Threading::GetCurrentProcessorNumber() };
+    Some(cpu as i32)
+}
+
+#[cfg(not(any(all(target_os = "linux", not(miri)), all(windows, not(miri)))))]
+pub fn current_cpu() -> Option<i32> {
+    None
+}
+
+#[cfg(all(not(target_os = "macos"), not(miri)))]
+fn wall_now() -> Instant {
+    Instant::now()
+}
+
+#[cfg(all(not(target_os = "macos"), not(miri)))]
+fn wall_elapsed(start: &Start) -> Duration {
+    start.time.elapsed()
+}
+
+// On macOS the wall clock is mach_absolute_time scaled by the mach
+// timebase: the cheapest monotonic clock there, and the one Instant and the
+// CPU counters are specified against.
+#[cfg(all(target_os = "macos", not(miri)))]
+fn wall_now() -> u64 {
+    // SAFETY: no preconditions.
+    unsafe { mach2::mach_time::mach_absolute_time() }
+}
+
+#[cfg(all(target_os = "macos", not(miri)))]
+fn wall_elapsed(start: &Start) -> Duration {
+    static TIMEBASE: OnceLock<(u64, u64)> = OnceLock::new();
+    let (numer, denom) = *TIMEBASE.get_or_init(|| {
+        let mut info = mach2::mach_time::mach_timebase_info { numer: 0, denom: 0 };
+        // SAFETY: writes the two fields of `info`.
+        unsafe { mach2::mach_time::mach_timebase_info(&mut info) };
+        (info.numer.max(1) as u64, info.denom.max(1) as u64)
+    });
+    let ticks = wall_now().saturating_sub(start.time) as u128;
+    Duration::from_nanos((ticks * numer as u128 / denom as u128) as u64)
+}
+
+#[cfg(all(any(target_arch = "x86_64", target_arch = "x86"), not(miri)))]
+fn cycles_now() -> u64 {
+    unsafe {
+        // Serializing fence: ensures all previous instructions
+        // are finished before we read the cycle count. LFENCE came with
+        // SSE2, which i686 targets assume but i586 ones do not.
+        #[cfg(any(target_arch = "x86_64", target_feature = "sse2"))]
+        _mm_lfence();
+        _rdtsc()
+    }
+}
+
+/// The generic timer's virtual count on aarch64 (Linux and macOS both
+/// allow user-space reads). It ticks at a fixed rate, 24 MHz on Apple M1/M2
+/// and 1 GHz on ARMv8.6+ parts, not at the core clock, so "cycles" here are
+/// timer ticks; the ISB keeps earlier instructions from drifting past it
+/// like the LFENCE does for RDTSC.
+#[cfg(all(target_arch = "aarch64", not(miri)))]
+fn cycles_now() -> u64 {
+    let ticks: u64;
+    // SAFETY: CNTVCT_EL0 is readable at EL0 and the read has no side effects.
+    unsafe {
+        std::arch::asm!(
+            "isb",
+            "mrs {}, cntvct_el0",
+            out(reg) ticks,
+            options(nostack, nomem, preserves_flags)
+        );
+    }
+    ticks
+}
+
+/// No cycle counter on this target: report nanoseconds since the first read
+/// instead, so cycles/ns comes out as 1 "GHz" rather than garbage.
+#[cfg(all(
+    not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")),
+    not(miri)
+))]
+fn cycles_now() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Browser builds: `performance.now()`, imported from the embedding page as
+/// `env.performance_now` (milliseconds as a double). Browsers coarsen it to
+/// 5-100us depending on cross-origin isolation, so only traversals long
+/// enough to span many ticks give meaningful per-node numbers.
+#[cfg(all(target_arch = "wasm32", feature = "wasm", not(miri)))]
+mod js {
+    use std::time::Duration;
+
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        fn performance_now() -> f64;
+    }
+
+    /// The subset of `std::time::Instant` the clock uses.
+    #[derive(Clone, Copy)]
+    pub struct Instant(f64);
+
+    impl Instant {
+        pub fn now() -> Self {
+            // SAFETY: the import takes no arguments and has no preconditions.
+            Instant(unsafe { performance_now() })
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Duration::from_secs_f64(((Self::now().0 - self.0) / 1e3).max(0.0))
+        }
+    }
+}
+
+// Under Miri there is no cycle counter and real time depends on how fast the
+// interpreter runs, so both clocks are driven by a deterministic mock that
+// advances a fixed amount on every read.
+#[cfg(miri)]
+mod mock {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Every read advances the mock wall clock by 1us and the mock cycle
+    /// counter by 3000 cycles: a steady 3 GHz machine.
+    const NS_PER_READ: u64 = 1_000;
+    const CYCLES_PER_READ: u64 = 3_000;
+    pub const GHZ: f64 = CYCLES_PER_READ as f64 / NS_PER_READ as f64;
+
+    static NOW_NS: AtomicU64 = AtomicU64::new(0);
+    static NOW_CYCLES: AtomicU64 = AtomicU64::new(0);
+
+    pub fn ns() -> u64 {
+        NOW_NS.fetch_add(NS_PER_READ, Ordering::Relaxed) + NS_PER_READ
+    }
+
+    pub fn cycles() -> u64 {
+        NOW_CYCLES.fetch_add(CYCLES_PER_READ, Ordering::Relaxed) + CYCLES_PER_READ
+    }
+}
+
+#[cfg(miri)]
+fn wall_now() -> Duration {
+    Duration::from_nanos(mock::ns())
+}
+
+#[cfg(miri)]
+fn wall_elapsed(start: &Start) -> Duration {
+    Duration::from_nanos(mock::ns()) - start.time
+}
+
+#[cfg(miri)]
+fn cycles_now() -> u64 {
+    mock::cycles()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anomalies_round_trip_through_their_text() {
+        for anomaly in [
+            Anomaly::Migrated { from: 2, to: 7 },
+            Anomaly::Backwards,
+            Anomaly::Frequency { ghz: 0.125 },
+        ] {
+            assert_eq!(Anomaly::parse(&anomaly.to_string()), Some(anomaly));
+        }
+        assert_eq!(Anomaly::parse("frequency"), None);
+    }
+
+    #[test]
+    fn short_valid_measurements_are_not_flagged() {
+        // Regions from empty to a few microseconds: the clock reads around
+        // them are the largest share of the shortest.
+        let regions = if cfg!(miri) { 50 } else { 1000 };
+        let flagged = (0..regions)
+            .filter(|&i| {
+                let start = start();
+                std::hint::black_box((0..i % 64 * 16).sum::<usize>());
+                matches!(stop(&start).anomaly, Some(Anomaly::Frequency { .. }))
+            })
+            .count();
+        // An interrupt landing between two reads can still flag one.
+        assert!(
+            flagged * 100 < regions,
+            "{} of {} flagged",
+            flagged,
+            regions
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::cache;
+use crate::LinkedList;
+
+/// The eviction buffer when the caches can't be read: bigger than any
+/// last-level cache this is likely to run on.
+const DEFAULT_THRASH_BYTES: usize = 64 << 20;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// The smallest cache line size, which flushes step through nodes by.
+static LINE: AtomicUsize = AtomicUsize::new(64);
+/// Written a line at a time to evict everything else, where the CPU has no
+/// user-space flush instruction this knows.
+static THRASH: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Makes every timed traversal from now on start with none of the list's
+/// nodes in any cache level. Returns how, for the run's notes.
+pub fn enable() -> String {
+    ENABLED.store(true, Ordering::Relaxed);
+    if cfg!(miri) {
+        return "no-op under Miri, which has no caches".to_string();
+    }
+    let levels = cache::detect();
+    if imp::FLUSH.is_some() {
+        let line = levels.iter().map(|l| l.line).min().unwrap_or(64).max(1);
+        LINE.store(line, Ordering::Relaxed);
+        return format!(
+            "{} of every line of every node before each timed traversal (TLB stays warm)",
+            imp::NAME
+        );
+    }
+    let largest = levels.iter().map(|l| l.size).max();
+    let bytes = largest.map_or(DEFAULT_THRASH_BYTES, |size| size * 2);
+    *THRASH.lock().unwrap() = vec![0; bytes];
+    format!(
+        "writing a {} MiB buffer before each timed traversal (no user-space cache flush here)",
+        bytes >> 20
+    )
+}
+
+/// Evicts `list` from the caches when `--cold` is on. The timing helpers
+/// call this before each measured region, never inside one.
+pub fn before<T>(list: &LinkedList<T>) {
+    if !ENABLED.load(Ordering::Relaxed) || cfg!(miri) {
+        return;
+    }
+    match imp::FLUSH {
+        Some(flush) => {
+            let line = LINE.load(Ordering::Relaxed);
+            let mut current = &list.head;
+            while let Some(node) = current {
+                let start = &**node as *const _ as *const u8;
+                let size = std::mem::size_of_val(&**node);
+                // One address a line apart and the last byte reach every line
+                // the node spans, however it straddles them.
+                for offset in (0..size).step_by(line).chain([size - 1]) {
+                    // SAFETY: the address is inside the live node; flushing a
+                    // line changes no data.
+                    unsafe { flush(start.add(offset)) };
+                }
+                current = &node.next;
+            }
+            // SAFETY: a fence only orders memory operations.
+            unsafe { imp::fence() };
+        }
+        None => {
+            let mut buffer = THRASH.lock().unwrap();
+            // One write per 64 bytes touches every line of 64- and 128-byte
+            // line caches alike.
+            for byte in buffer.iter_mut().step_by(64) {
+                *byte = byte.wrapping_add(1);
+            }
+            std::hint::black_box(&mut *buffer);
+        }
+    }
+}
+
+#[cfg(all(
+    any(
+        target_arch = "x86_64",
+        all(target_arch = "x86", target_feature = "sse2")
+    ),
+    not(miri)
+))]
+mod imp {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{_mm_clflush, _mm_mfence};
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{_mm_clflush, _mm_mfence};
+
+    pub const NAME: &str = "clflush";
+    pub const FLUSH: Option<unsafe fn(*const u8)> = Some(flush);
+
+    /// Writes the line back if dirty and invalidates it in every level.
+    unsafe fn flush(address: *const u8) {
+        _mm_clflush(address);
+    }
+
+    /// clflush is only ordered by mfence: without one the traversal could
+    /// start loading lines that are still being flushed.
+    pub unsafe fn fence() {
+        _mm_mfence();
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux", not(miri)))]
+mod imp {
+    use std::arch::asm;
+
+    pub const NAME: &str = "dc civac";
+    pub const FLUSH: Option<unsafe fn(*const u8)> = Some(flush);
+
+    /// Clean and invalidate by address to the point of coherency. Linux
+    /// sets SCTLR_EL1.UCI, so EL0 may issue it.
+    unsafe fn flush(address: *const u8) {
+        asm!("dc civac, {}", in(reg) address, options(nostack, preserves_flags));
+    }
+
+    pub unsafe fn fence() {
+        asm!("dsb ish", options(nostack, preserves_flags));
+    }
+}
+
+#[cfg(not(all(
+    any(
+        target_arch = "x86_64",
+        all(target_arch = "x86", target_feature = "sse2"),
+        all(target_arch = "aarch64", target_os = "linux")
+    ),
+    not(miri)
+)))]
+mod imp {
+    pub const NAME: &str = "none";
+    pub const FLUSH: Option<unsafe fn(*const u8)> = None;
+
+    pub unsafe fn fence() {}
+}
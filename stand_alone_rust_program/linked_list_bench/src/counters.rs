@@ -0,0 +1,146 @@
+//! Hardware event counters through Linux `perf_event_open(2)`, for the few
+//! experiments whose point is an event rate rather than a time. Opening a
+//! counter fails (and callers print "unavailable") when the kernel forbids
+//! it (`perf_event_paranoid`), inside most containers and VMs, under Miri
+//! and on other operating systems.
+
+/// A hardware event this program knows how to count.
+#[derive(Clone, Copy)]
+pub enum Event {
+    Branches,
+    BranchMisses,
+    /// Loads that missed the level 1 data cache.
+    L1dReadMisses,
+    /// Loads that missed the last-level cache and went to memory.
+    LlcReadMisses,
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+mod imp {
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    use super::Event;
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+    const PERF_TYPE_HW_CACHE: u32 = 3;
+    const PERF_COUNT_HW_BRANCH_INSTRUCTIONS: u64 = 4;
+    const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+    // Cache events are `cache | operation << 8 | result << 16`.
+    const PERF_COUNT_HW_CACHE_L1D: u64 = 0;
+    const PERF_COUNT_HW_CACHE_LL: u64 = 2;
+    const PERF_COUNT_HW_CACHE_OP_READ: u64 = 0;
+    const PERF_COUNT_HW_CACHE_RESULT_MISS: u64 = 1;
+
+    // Bits of the attribute's flag word.
+    const DISABLED: u64 = 1 << 0;
+    const EXCLUDE_KERNEL: u64 = 1 << 5;
+    const EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+    const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+    const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+
+    /// The leading fields of `struct perf_event_attr`, zero-padded to the
+    /// 128-byte `PERF_ATTR_SIZE_VER7` layout every current kernel accepts.
+    #[repr(C)]
+    struct Attr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        rest: [u64; 10],
+    }
+
+    pub struct Counter {
+        file: File,
+    }
+
+    impl Counter {
+        pub fn open(event: Event) -> Option<Self> {
+            let read_misses = |cache| {
+                cache | (PERF_COUNT_HW_CACHE_OP_READ << 8) | (PERF_COUNT_HW_CACHE_RESULT_MISS << 16)
+            };
+            let (kind, config) = match event {
+                Event::Branches => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_INSTRUCTIONS),
+                Event::BranchMisses => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_MISSES),
+                Event::L1dReadMisses => (PERF_TYPE_HW_CACHE, read_misses(PERF_COUNT_HW_CACHE_L1D)),
+                Event::LlcReadMisses => (PERF_TYPE_HW_CACHE, read_misses(PERF_COUNT_HW_CACHE_LL)),
+            };
+            let attr = Attr {
+                kind,
+                size: std::mem::size_of::<Attr>() as u32,
+                config,
+                sample_period: 0,
+                sample_type: 0,
+                read_format: 0,
+                flags: DISABLED | EXCLUDE_KERNEL | EXCLUDE_HV,
+                rest: [0; 10],
+            };
+            // SAFETY: `attr` is a valid, fully initialised perf_event_attr of
+            // the size it declares; pid 0 / cpu -1 means this thread on any CPU.
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_perf_event_open,
+                    &attr as *const Attr,
+                    0 as libc::pid_t,
+                    -1 as libc::c_int,
+                    -1 as libc::c_int,
+                    0 as libc::c_ulong,
+                )
+            };
+            if fd < 0 {
+                return None;
+            }
+            // SAFETY: the syscall returned a fresh descriptor we now own.
+            let file = unsafe { File::from_raw_fd(fd as i32) };
+            Some(Counter { file })
+        }
+
+        pub fn reset_and_enable(&self) {
+            self.ioctl(PERF_EVENT_IOC_RESET);
+            self.ioctl(PERF_EVENT_IOC_ENABLE);
+        }
+
+        pub fn disable_and_read(&mut self) -> u64 {
+            self.ioctl(PERF_EVENT_IOC_DISABLE);
+            let mut value = [0u8; 8];
+            match self.file.read_exact(&mut value) {
+                Ok(()) => u64::from_ne_bytes(value),
+                Err(_) => 0,
+            }
+        }
+
+        fn ioctl(&self, request: libc::c_ulong) {
+            // SAFETY: perf ioctls without an argument on a perf event fd.
+            unsafe {
+                libc::ioctl(self.file.as_raw_fd(), request as _, 0);
+            }
+        }
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(miri))))]
+mod imp {
+    use super::Event;
+
+    pub struct Counter;
+
+    impl Counter {
+        pub fn open(_event: Event) -> Option<Self> {
+            None
+        }
+
+        pub fn reset_and_enable(&self) {}
+
+        pub fn disable_and_read(&mut self) -> u64 {
+            0
+        }
+    }
+}
+
+pub use imp::Counter;
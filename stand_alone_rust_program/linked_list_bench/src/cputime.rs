@@ -0,0 +1,149 @@
+use std::time::{Duration, Instant};
+
+/// CPU time the process has used so far, split by privilege level, with
+/// the page faults taken along the way where the OS counts them.
+struct Usage {
+    user: Duration,
+    system: Duration,
+    /// (minor, major) faults.
+    faults: Option<(u64, u64)>,
+}
+
+#[cfg(all(any(target_os = "linux", target_os = "macos"), not(miri)))]
+fn usage() -> Option<Usage> {
+    let time = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+    };
+    // SAFETY: getrusage fills in the struct it is given and nothing else.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    Some(Usage {
+        user: time(usage.ru_utime),
+        system: time(usage.ru_stime),
+        faults: Some((usage.ru_minflt as u64, usage.ru_majflt as u64)),
+    })
+}
+
+#[cfg(all(windows, not(miri)))]
+fn usage() -> Option<Usage> {
+    use windows_sys::Win32::Foundation::FILETIME;
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, GetProcessTimes};
+
+    let zero = || FILETIME {
+        dwLowDateTime: 0,
+        dwHighDateTime: 0,
+    };
+    let (mut creation, mut exit, mut kernel, mut user) = (zero(), zero(), zero(), zero());
+    // SAFETY: the pseudo-handle needs no closing and the four out-pointers
+    // are valid FILETIMEs.
+    let ok = unsafe {
+        GetProcessTimes(
+            GetCurrentProcess(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+    };
+    // FILETIMEs count 100ns units.
+    let time = |t: FILETIME| {
+        Duration::from_nanos((((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64) * 100)
+    };
+    (ok != 0).then(|| Usage {
+        user: time(user),
+        system: time(kernel),
+        faults: None,
+    })
+}
+
+#[cfg(not(all(any(target_os = "linux", target_os = "macos", windows), not(miri))))]
+fn usage() -> Option<Usage> {
+    None
+}
+
+struct Phase {
+    name: String,
+    wall: Duration,
+    user: Duration,
+    system: Duration,
+    faults: Option<(u64, u64)>,
+}
+
+/// User and system CPU time of each phase of a run next to its wall time,
+/// so a build that spends its time in the kernel (mmap, zeroing pages on
+/// first touch) stands apart from user-space work. Phases must not overlap.
+pub struct Phases {
+    open: Option<(String, Usage, Instant)>,
+    done: Vec<Phase>,
+}
+
+impl Phases {
+    pub fn new() -> Self {
+        Phases {
+            open: None,
+            done: Vec::new(),
+        }
+    }
+
+    pub fn begin(&mut self, name: &str) {
+        if let Some(start) = usage() {
+            self.open = Some((name.to_string(), start, Instant::now()));
+        }
+    }
+
+    pub fn end(&mut self) {
+        let Some((name, start, began)) = self.open.take() else {
+            return;
+        };
+        let wall = began.elapsed();
+        let Some(end) = usage() else {
+            return;
+        };
+        let faults = start
+            .faults
+            .zip(end.faults)
+            .map(|(s, e)| (e.0.saturating_sub(s.0), e.1.saturating_sub(s.1)));
+        self.done.push(Phase {
+            name,
+            wall,
+            user: end.user.saturating_sub(start.user),
+            system: end.system.saturating_sub(start.system),
+            faults,
+        });
+    }
+
+    pub fn print(&self) {
+        if self.done.is_empty() {
+            return;
+        }
+        let ms = |d: Duration| d.as_secs_f64() * 1e3;
+        println!("\n[CPU Time]");
+        println!(
+            "{:<16} {:>12} {:>12} {:>12} {:>7} {:>12} {:>8}",
+            "Phase", "Wall", "User", "System", "CPU%", "Minor flt", "Major"
+        );
+        for phase in &self.done {
+            let (minor, major) = phase.faults.map_or_else(
+                || ("-".to_string(), "-".to_string()),
+                |(minor, major)| (minor.to_string(), major.to_string()),
+            );
+            println!(
+                "{:<16} {:>9.1} ms {:>9.1} ms {:>9.1} ms {:>6.0}% {:>12} {:>8}",
+                phase.name,
+                ms(phase.wall),
+                ms(phase.user),
+                ms(phase.system),
+                (phase.user + phase.system).as_secs_f64() * 100.0
+                    / phase.wall.as_secs_f64().max(f64::MIN_POSITIVE),
+                minor,
+                major
+            );
+        }
+        // Linux splits user and system time by sampling at the scheduler
+        // tick unless built with precise accounting; Windows counts in
+        // 15.6 ms quanta.
+        println!("User/system split is tick-sampled: short phases read as 0 or one tick");
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::HashSet;
+use std::fs;
+
+use serde_json::{Map, Value};
+
+use crate::bench::{self, Sample};
+use crate::cache;
+use crate::rng::Rng;
+use crate::LinkedList;
+
+/// One row of a data file. Every field is its own heap allocation, so a
+/// node holds pointers to payload elsewhere rather than the payload.
+struct Record {
+    key: String,
+    fields: Vec<String>,
+}
+
+/// Column names and rows of a loaded file.
+struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+/// `payload --data <file.csv|file.json|file.ndjson> [--key <column>] [--searches <k>]
+/// [--iterations <n>]`: loads real records into the list instead of
+/// sequential integers and times a links-only walk, a walk that reads every
+/// key's bytes and key searches, each next to the same operation on an
+/// integer list of the same length.
+///
+/// A string key is a second pointer to chase: the node says where its
+/// bytes are, and comparing against a search key needs them. The key
+/// column is the first one unless `--key` names another.
+pub fn run(args: &[String]) {
+    let mut path: Option<String> = None;
+    let mut key_column: Option<String> = None;
+    let mut searches: usize = 200;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--data", Some(v)) => path = Some(v.clone()),
+            ("--key", Some(v)) => key_column = Some(v.clone()),
+            ("--searches", Some(v)) => searches = v.parse().unwrap_or(searches),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete payload option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("Error: payload needs --data <file>");
+        return;
+    };
+    let table = match load(&path) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Error: could not load {}: {}", path, e);
+            return;
+        }
+    };
+    let key_index = match key_index(&table, key_column.as_deref()) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("Error: {} in {}", e, path);
+            return;
+        }
+    };
+    if table.rows.is_empty() {
+        eprintln!("Error: {} holds no records", path);
+        return;
+    }
+
+    let nodes = table.rows.len();
+    let keys: Vec<String> = table
+        .rows
+        .iter()
+        .map(|row| row[key_index].clone())
+        .collect();
+    let payload_bytes: usize = table.rows.iter().flatten().map(String::len).sum();
+    let records = LinkedList::from_vec(
+        table
+            .rows
+            .into_iter()
+            .map(|fields| Record {
+                key: fields[key_index].clone(),
+                fields,
+            })
+            .collect(),
+    );
+    let integers = LinkedList::from_vec((0..nodes as u64).collect());
+
+    // Hits are keys drawn from the file; misses are the same keys made
+    // absent from it, so they compare as much of each key and walk the
+    // whole list.
+    let mut rng = Rng::new(nodes as u64);
+    let hits: Vec<usize> = (0..searches.max(1)).map(|_| rng.below(nodes)).collect();
+    let hit_keys: Vec<&str> = hits.iter().map(|&i| keys[i].as_str()).collect();
+    let present: HashSet<&str> = keys.iter().map(String::as_str).collect();
+    let miss_keys: Vec<String> = hit_keys.iter().map(|k| miss_key(k, &present)).collect();
+    let hit_walk: usize = hits.iter().map(|&i| i + 1).sum();
+    // A key that repeats is found at its first occurrence.
+    let key_walk: usize = hit_keys
+        .iter()
+        .map(|&k| keys.iter().position(|other| other == k).unwrap_or(0) + 1)
+        .sum();
+    let miss_walk = nodes * miss_keys.len();
+
+    println!("--- Heap Payloads ---");
+    println!(
+        "{}: {} records, {} columns, key column '{}'",
+        path,
+        nodes,
+        table.columns.len(),
+        table.columns[key_index]
+    );
+    println!(
+        "Average key {:.1} bytes, {:.1} payload bytes per record in {} allocations",
+        keys.iter().map(String::len).sum::<usize>() as f64 / nodes as f64,
+        payload_bytes as f64 / nodes as f64,
+        table.columns.len() + 1
+    );
+    // Parsing leaves freed allocations behind, and the lists' nodes fill
+    // them: how contiguous each list came out is part of its result.
+    let (integer_layout, record_layout) =
+        (cache::node_layout(&integers), cache::node_layout(&records));
+    println!(
+        "Node layout: u64 {:.0}% adjacent, records {:.0}% adjacent",
+        integer_layout.adjacent_fraction * 100.0,
+        record_layout.adjacent_fraction * 100.0
+    );
+    println!(
+        "{:<14} {:<10} {:>12} {:>14} {:>12} {:>8}",
+        "Benchmark", "Payload", "ns/node", "cycles/node", "vs integers", "Flagged"
+    );
+
+    let rows: [(&str, Vec<Sample>, Vec<Sample>); 4] = [
+        (
+            "links only",
+            bench::time_traversals(&integers, iterations),
+            bench::time_traversals(&records, iterations),
+        ),
+        (
+            "read keys",
+            bench::time_repeated(iterations, nodes, || fold(&integers, |&v| v)),
+            bench::time_repeated(iterations, nodes, || {
+                fold(&records, |r| r.key.bytes().map(u64::from).sum())
+            }),
+        ),
+        (
+            "search hit",
+            bench::time_repeated(iterations, hit_walk, || {
+                hits.iter()
+                    .map(|&i| find(&integers, |&v| v == i as u64))
+                    .sum::<usize>()
+            }),
+            bench::time_repeated(iterations, key_walk, || {
+                hit_keys
+                    .iter()
+                    .map(|&k| find(&records, |r| r.key == k))
+                    .sum::<usize>()
+            }),
+        ),
+        (
+            "search miss",
+            bench::time_repeated(iterations, miss_walk, || {
+                (0..miss_keys.len())
+                    .map(|_| find(&integers, |&v| v == nodes as u64))
+                    .sum::<usize>()
+            }),
+            bench::time_repeated(iterations, miss_walk, || {
+                miss_keys
+                    .iter()
+                    .map(|k| find(&records, |r| r.key == *k))
+                    .sum::<usize>()
+            }),
+        ),
+    ];
+    for (name, integer_samples, record_samples) in &rows {
+        let (_, baseline, _) = bench::per_operation(integer_samples);
+        for (payload, samples) in [("u64", integer_samples), ("records", record_samples)] {
+            let (ns, cycles, flagged) = bench::per_operation(samples);
+            println!(
+                "{:<14} {:<10} {:>12} {:>14} {:>12} {:>8}",
+                name,
+                payload,
+                bench::stat(&ns, ns.median, 2),
+                bench::stat(&cycles, cycles.median, 2),
+                bench::ratio(&cycles, &baseline, 2),
+                flagged
+            );
+        }
+    }
+    // The other fields are only there to sit on the heap between the keys,
+    // as they would in a real record.
+    std::hint::black_box(fold(&records, |r| r.fields.len() as u64));
+}
+
+/// The index of the key column: `name`'s, or the first column's.
+fn key_index(table: &Table, name: Option<&str>) -> Result<usize, String> {
+    if table.columns.is_empty() {
+        return Err("no columns".to_string());
+    }
+    match name {
+        Some(name) => table.columns.iter().position(|c| c == name).ok_or_else(|| {
+            format!(
+                "no column '{}' (columns: {})",
+                name,
+                table.columns.join(", ")
+            )
+        }),
+        None => Ok(0),
+    }
+}
+
+/// `key` with NULs appended until it is none of the `present` keys.
+fn miss_key(key: &str, present: &HashSet<&str>) -> String {
+    let mut miss = format!("{}\u{0}", key);
+    while present.contains(miss.as_str()) {
+        miss.push('\u{0}');
+    }
+    miss
+}
+
+/// Visits every node and folds `value` of each into a checksum.
+fn fold<T>(list: &LinkedList<T>, value: impl Fn(&T) -> u64) -> u64 {
+    let mut current = &list.head;
+    let mut sum: u64 = 0;
+    while let Some(node) = current {
+        sum = sum.wrapping_add(value(&node.data));
+        current = &node.next;
+    }
+    sum
+}
+
+/// Nodes visited up to and including the first match, or the whole list.
+fn find<T>(list: &LinkedList<T>, matches: impl Fn(&T) -> bool) -> usize {
+    let mut current = &list.head;
+    let mut visited = 0;
+    while let Some(node) = current {
+        visited += 1;
+        if matches(&node.data) {
+            break;
+        }
+        current = &node.next;
+    }
+    visited
+}
+
+/// Reads a table by the file's extension: CSV with a header row, a JSON
+/// array of objects, or newline-delimited JSON objects. JSON columns are
+/// the first object's keys; nested values are kept as their JSON text.
+fn load(path: &str) -> Result<Table, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let extension = path.rsplit_once('.').map_or("", |(_, e)| e);
+    parse(&text, &extension.to_ascii_lowercase())
+}
+
+/// Parses `text` as the format `extension` names.
+fn parse(text: &str, extension: &str) -> Result<Table, String> {
+    match extension {
+        "csv" => parse_csv(text),
+        "json" => table_of(serde_json::from_str(text).map_err(|e| e.to_string())?),
+        "ndjson" | "jsonl" => {
+            let mut objects = Vec::new();
+            for (number, line) in text.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let object = serde_json::from_str(line)
+                    .map_err(|e| format!("line {}: {}", number + 1, e))?;
+                objects.push(object);
+            }
+            table_of(objects)
+        }
+        _ => Err("unknown data format (expected .csv, .json or .ndjson)".to_string()),
+    }
+}
+
+/// RFC 4180 CSV: quoted fields may hold commas, newlines and doubled
+/// quotes. Every row must have as many fields as the header.
+fn parse_csv(text: &str) -> Result<Table, String> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let rows = reader
+        .records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(str::to_string).collect())
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Table { columns, rows })
+}
+
+fn table_of(objects: Vec<Map<String, Value>>) -> Result<Table, String> {
+    let columns: Vec<String> = objects
+        .first()
+        .map(|o| o.keys().cloned().collect())
+        .unwrap_or_default();
+    let rows = objects
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut object)| {
+            columns
+                .iter()
+                .map(|column| match object.remove(column) {
+                    Some(Value::String(text)) => Ok(text),
+                    Some(value) => Ok(value.to_string()),
+                    None => Err(format!("record {} has no '{}'", index + 1, column)),
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Table { columns, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_may_be_quoted() {
+        let table = parse(
+            "id,name\n1,\"Smith, Jo\"\n2,\"say \"\"hi\"\"\nthere\"\r\n",
+            "csv",
+        )
+        .unwrap();
+        assert_eq!(table.columns, ["id", "name"]);
+        assert_eq!(table.rows, [["1", "Smith, Jo"], ["2", "say \"hi\"\nthere"]]);
+        assert!(parse("id,name\n1\n", "csv").is_err());
+    }
+
+    #[test]
+    fn json_columns_are_the_first_objects_keys() {
+        let text = r#"[{"z":"a","y":1,"x":{"n":[1,2]}},{"x":null,"y":2.5,"z":"bé"}]"#;
+        let table = parse(text, "json").unwrap();
+        assert_eq!(table.columns, ["z", "y", "x"]);
+        assert_eq!(
+            table.rows,
+            [["a", "1", "{\"n\":[1,2]}"], ["bé", "2.5", "null"]]
+        );
+
+        let table = parse("{\"k\":\"a\"}\n\n{\"k\":\"b\"}\n", "ndjson").unwrap();
+        assert_eq!(table.rows, [["a"], ["b"]]);
+        assert!(parse("{\"k\":\"a\"}\n{\"j\":\"b\"}\n", "ndjson").is_err());
+        assert!(parse("{\"k\":\"a\"}\n{\"k\":\n", "ndjson").is_err());
+    }
+
+    #[test]
+    fn key_index_needs_the_named_column() {
+        let table = parse("id,name\n1,a\n", "csv").unwrap();
+        assert_eq!(key_index(&table, None), Ok(0));
+        assert_eq!(key_index(&table, Some("name")), Ok(1));
+        assert!(key_index(&table, Some("email")).is_err());
+
+        for empty in [parse("", "csv"), parse("[]", "json"), parse("[{}]", "json")] {
+            assert!(key_index(&empty.unwrap(), None).is_err());
+        }
+    }
+
+    #[test]
+    fn miss_keys_are_absent_from_the_file() {
+        let present = HashSet::from(["a", "a\0", "a\0\0", "b"]);
+        assert_eq!(miss_key("a", &present), "a\0\0\0");
+        assert_eq!(miss_key("b", &present), "b\0");
+    }
+}
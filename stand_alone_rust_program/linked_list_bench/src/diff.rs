@@ -0,0 +1,73 @@
+use crate::bench::Sample;
+use crate::results;
+use crate::stats::{self, Summary};
+
+/// `diff <a.tsv> <b.tsv>`: compares two files written with `--save`,
+/// benchmark by benchmark, with a Mann-Whitney U test on the per-iteration
+/// cycles-per-node samples.
+pub fn run(args: &[String]) {
+    let [path_a, path_b] = args else {
+        eprintln!("Usage: cargo run -- diff <a.tsv> <b.tsv>");
+        return;
+    };
+
+    let loaded = results::load(path_a).and_then(|a| Ok((a, results::load(path_b)?)));
+    let ((meta_a, results_a), (meta_b, results_b)) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: could not load results: {}", e);
+            return;
+        }
+    };
+
+    println!("--- Result Diff ---");
+    println!(
+        "A: {} (commit {}, {})",
+        path_a, meta_a.git_commit, meta_a.host
+    );
+    println!(
+        "B: {} (commit {}, {})",
+        path_b, meta_b.git_commit, meta_b.host
+    );
+    println!(
+        "\n{:<12} {:>12} {:>10} {:>10} {:>9} {:>10} {:>9} {:>8}",
+        "Benchmark", "Nodes", "A median", "B median", "Delta", "U", "p-value", "Effect"
+    );
+
+    for a in &results_a {
+        let Some(b) = results_b
+            .iter()
+            .find(|b| b.name == a.name && b.nodes == a.nodes)
+        else {
+            continue;
+        };
+        let samples_a: Vec<f64> = a.valid_samples().map(Sample::cycles_per_node).collect();
+        let samples_b: Vec<f64> = b.valid_samples().map(Sample::cycles_per_node).collect();
+        let (median_a, median_b) = (
+            Summary::of(&samples_a).median,
+            Summary::of(&samples_b).median,
+        );
+        let delta = (median_b / median_a - 1.0) * 100.0;
+
+        let (u, p_value, effect, marker) = match stats::mann_whitney_u(&samples_a, &samples_b) {
+            Some(test) => {
+                // Rank-biserial correlation: -1 = B always smaller, +1 = always larger.
+                let pairs = (samples_a.len() * samples_b.len()) as f64;
+                let effect = 1.0 - 2.0 * test.statistic / pairs;
+                (
+                    format!("{:.1}", test.statistic),
+                    format!("{:.4}", test.p_value),
+                    format!("{:+.2}", effect),
+                    if test.p_value < 0.05 { " *" } else { "" },
+                )
+            }
+            None => ("-".to_string(), "-".to_string(), "-".to_string(), ""),
+        };
+        println!(
+            "{:<12} {:>12} {:>10.2} {:>10.2} {:>+8.1}% {:>10} {:>9} {:>8}{}",
+            a.name, a.nodes, median_a, median_b, delta, u, p_value, effect, marker
+        );
+    }
+    println!("\n* significant at p < 0.05 (two-sided Mann-Whitney U on cycles/node samples)");
+    println!("Effect is the rank-biserial correlation; positive means B is slower.");
+}
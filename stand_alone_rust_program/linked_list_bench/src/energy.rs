@@ -0,0 +1,217 @@
+use std::fs;
+use std::time::{Duration, Instant};
+
+const POWERCAP: &str = "/sys/class/powercap";
+
+// RAPL counters update about once a millisecond; a phase shorter than a few
+// updates measures the update phase more than the workload.
+const MIN_PHASE: Duration = Duration::from_millis(10);
+
+/// One RAPL power domain: a package, or a part of one (core, uncore, dram).
+struct Domain {
+    name: String,
+    energy_path: String,
+    /// The counter wraps to zero after this many microjoules.
+    range_uj: u64,
+}
+
+/// The RAPL domains of this machine, as exposed by the powercap driver
+/// (`intel-rapl`, which also covers AMD since Zen).
+pub struct Meter {
+    domains: Vec<Domain>,
+}
+
+/// Every domain's counter at one instant, in microjoules.
+pub struct Energy(Vec<u64>);
+
+impl Meter {
+    /// All readable domains, or why there are none. energy_uj has been
+    /// root-only since the PLATYPUS side channel, so the usual failure on
+    /// a supported machine is permission.
+    pub fn open() -> Result<Self, String> {
+        if cfg!(miri) {
+            return Err("RAPL is not readable under Miri".to_string());
+        }
+        let entries = fs::read_dir(POWERCAP)
+            .map_err(|e| format!("no powercap interface at {}: {}", POWERCAP, e))?;
+        let mut zones: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("intel-rapl:"))
+            .collect();
+        zones.sort();
+
+        let mut domains = Vec::new();
+        let mut denied = None;
+        for zone in zones {
+            let dir = format!("{}/{}", POWERCAP, zone);
+            let read = |file: &str| {
+                fs::read_to_string(format!("{}/{}", dir, file)).map(|s| s.trim().to_string())
+            };
+            let Ok(name) = read("name") else {
+                continue;
+            };
+            // Subzones (intel-rapl:0:1) are parts of the package above them.
+            let name = match zone.matches(':').count() {
+                1 => name,
+                _ => {
+                    let package = zone.rsplit_once(':').map_or("", |(parent, _)| parent);
+                    let parent = fs::read_to_string(format!("{}/{}/name", POWERCAP, package))
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_else(|_| package.to_string());
+                    format!("{}/{}", parent, name)
+                }
+            };
+            if let Err(e) = read("energy_uj") {
+                denied = Some(e);
+                continue;
+            }
+            domains.push(Domain {
+                name,
+                energy_path: format!("{}/energy_uj", dir),
+                range_uj: read("max_energy_range_uj")
+                    .ok()
+                    .and_then(|r| r.parse().ok())
+                    .unwrap_or(u64::MAX),
+            });
+        }
+        match (domains.is_empty(), denied) {
+            (false, _) => Ok(Meter { domains }),
+            (true, Some(e)) => Err(format!(
+                "RAPL domains present but not readable ({}); run as root or relax the permissions of energy_uj",
+                e
+            )),
+            (true, None) => Err("no RAPL domains (needs an Intel or AMD CPU and the intel_rapl driver)".to_string()),
+        }
+    }
+
+    pub fn read(&self) -> Energy {
+        Energy(
+            self.domains
+                .iter()
+                .map(|d| {
+                    fs::read_to_string(&d.energy_path)
+                        .ok()
+                        .and_then(|s| s.trim().parse().ok())
+                        .unwrap_or(0)
+                })
+                .collect(),
+        )
+    }
+
+    /// Joules each domain used between two readings, allowing for one
+    /// counter wrap.
+    fn joules(&self, from: &Energy, to: &Energy) -> Vec<(String, f64)> {
+        self.domains
+            .iter()
+            .zip(from.0.iter().zip(&to.0))
+            .map(|(domain, (&from, &to))| {
+                let uj = if to >= from {
+                    to - from
+                } else {
+                    domain.range_uj - from + to
+                };
+                (domain.name.clone(), uj as f64 / 1e6)
+            })
+            .collect()
+    }
+}
+
+struct Phase {
+    name: String,
+    operations: usize,
+    elapsed: Duration,
+    joules: Vec<(String, f64)>,
+}
+
+/// Energy used by each phase of a run, a no-op unless a meter could be
+/// opened. Phases must not overlap.
+pub struct Phases {
+    meter: Option<Meter>,
+    open: Option<(String, Energy, Instant)>,
+    done: Vec<Phase>,
+}
+
+impl Phases {
+    /// Warns when `enabled` but RAPL can't be read, then records nothing.
+    pub fn new(enabled: bool) -> Self {
+        let meter = enabled.then(Meter::open).and_then(|m| {
+            m.map_err(|e| eprintln!("Warning: no energy readings: {}", e))
+                .ok()
+        });
+        Phases {
+            meter,
+            open: None,
+            done: Vec::new(),
+        }
+    }
+
+    pub fn begin(&mut self, name: &str) {
+        if let Some(meter) = &self.meter {
+            self.open = Some((name.to_string(), meter.read(), Instant::now()));
+        }
+    }
+
+    /// Ends the current phase, which did `operations` units of work (nodes
+    /// built or visited).
+    pub fn end(&mut self, operations: usize) {
+        let (Some(meter), Some((name, start, began))) = (&self.meter, self.open.take()) else {
+            return;
+        };
+        let elapsed = began.elapsed();
+        let joules = meter.joules(&start, &meter.read());
+        self.done.push(Phase {
+            name,
+            operations,
+            elapsed,
+            joules,
+        });
+    }
+
+    /// Prints the table as a section of a text report, or to stderr when
+    /// stdout carries a machine-readable format.
+    pub fn print(&self, text: bool) {
+        if self.done.is_empty() {
+            return;
+        }
+        let mut lines = vec![
+            "\n[Energy (RAPL)]".to_string(),
+            format!(
+                "{:<16} {:>12} {:<18} {:>10} {:>8} {:>10}",
+                "Phase", "Time", "Domain", "Joules", "Watts", "nJ/node"
+            ),
+        ];
+        let mut short = false;
+        for phase in &self.done {
+            short |= phase.elapsed < MIN_PHASE;
+            for (domain, joules) in &phase.joules {
+                lines.push(format!(
+                    "{:<16} {:>9.1} ms {:<18} {:>10.4} {:>8.2} {:>10.2}",
+                    phase.name,
+                    phase.elapsed.as_secs_f64() * 1e3,
+                    domain,
+                    joules,
+                    joules / phase.elapsed.as_secs_f64().max(f64::MIN_POSITIVE),
+                    joules * 1e9 / phase.operations.max(1) as f64
+                ));
+            }
+        }
+        if short {
+            lines.push(format!(
+                "Phases under {} ms span few RAPL updates; use more nodes or iterations",
+                MIN_PHASE.as_millis()
+            ));
+        }
+        lines.push(
+            "Domains count the whole package, so idle cores and other processes are included"
+                .to_string(),
+        );
+        for line in lines {
+            if text {
+                println!("{}", line);
+            } else {
+                eprintln!("{}", line);
+            }
+        }
+    }
+}
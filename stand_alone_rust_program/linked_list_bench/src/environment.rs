@@ -0,0 +1,230 @@
+use std::fs;
+
+/// Linux's USER_HZ, the unit of /proc/stat: 100 on every mainstream
+/// architecture regardless of the kernel's own tick rate.
+const USER_HZ: u64 = 100;
+
+/// Counters that grow while something outside the benchmark takes CPU time
+/// away from it. Take one before a run and pass it to [`warnings`] after.
+pub struct Snapshot {
+    /// Ticks the hypervisor ran other work on our vCPUs, summed over CPUs.
+    steal_ticks: Option<u64>,
+    /// Periods in which the cgroup hit its CPU quota, and the time it
+    /// spent throttled in microseconds.
+    throttled: Option<(u64, u64)>,
+}
+
+pub fn snapshot() -> Snapshot {
+    if cfg!(miri) {
+        return Snapshot {
+            steal_ticks: None,
+            throttled: None,
+        };
+    }
+    Snapshot {
+        steal_ticks: steal_ticks(),
+        throttled: throttling(),
+    }
+}
+
+/// Everything about the VM or container the run happened in that makes its
+/// cycle and GHz figures misleading, one sentence each. Empty on bare metal
+/// with the machine to itself, and off Linux, where none of it is exposed.
+pub fn warnings(since: &Snapshot) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if cfg!(miri) {
+        return warnings;
+    }
+
+    if let Some(cpus) = cpu_quota() {
+        warnings.push(format!(
+            "cgroup CPU quota of {:.2} CPUs: bursts past it are throttled and the stall shows up as wall time",
+            cpus
+        ));
+    }
+    if let (Some((periods, usec)), Some((start_periods, start_usec))) =
+        (throttling(), since.throttled)
+    {
+        let periods = periods.saturating_sub(start_periods);
+        if periods > 0 {
+            warnings.push(format!(
+                "throttled by the CPU quota in {} scheduling periods ({} ms) during the run",
+                periods,
+                usec.saturating_sub(start_usec) / 1000
+            ));
+        }
+    }
+    if let (Some(allowed), Some(online)) = (cpuset_cpus(), online_cpus()) {
+        if allowed < online {
+            warnings.push(format!(
+                "cpuset restricts the container to {} of {} online CPUs: neighbours may share its cores and caches",
+                allowed, online
+            ));
+        }
+    }
+    if hypervisor() {
+        warnings.push(
+            "running under a hypervisor: the cycle counter and cache sizes are what the host chooses to expose"
+                .to_string(),
+        );
+    }
+    if let (Some(ticks), Some(start)) = (steal_ticks(), since.steal_ticks) {
+        let stolen = ticks.saturating_sub(start);
+        if stolen > 0 {
+            warnings.push(format!(
+                "the hypervisor stole {} ms of CPU time during the run: those samples include time we did not run",
+                stolen * 1000 / USER_HZ
+            ));
+        }
+    }
+    warnings
+}
+
+/// Prints the [`warnings`] as a section of a text report, or to stderr when
+/// stdout carries a machine-readable format.
+pub fn report(since: &Snapshot, text: bool) {
+    let warnings = warnings(since);
+    if text && !warnings.is_empty() {
+        println!("\n[Environment]");
+    }
+    for warning in warnings {
+        if text {
+            println!("Warning: {}", warning);
+        } else {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+}
+
+fn read(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// This process's directories in the unified (v2) hierarchy, leaf first.
+/// Inside a container the namespace root is mounted at /sys/fs/cgroup and
+/// the path reads "/", which leaves just that.
+fn v2_dirs() -> Vec<String> {
+    let Some(path) = read("/proc/self/cgroup").and_then(|text| {
+        text.lines()
+            .find_map(|l| l.strip_prefix("0::").map(str::to_string))
+    }) else {
+        return Vec::new();
+    };
+    let mut dirs = Vec::new();
+    let mut path = path.trim_end_matches('/');
+    loop {
+        dirs.push(format!("/sys/fs/cgroup{}", path));
+        match path.rfind('/') {
+            Some(slash) => path = &path[..slash],
+            None => break,
+        }
+    }
+    dirs
+}
+
+/// This process's directory for `controller` in a v1 hierarchy, falling
+/// back to the mount root when the path isn't visible from here.
+fn v1_dir(controller: &str) -> Option<String> {
+    let text = read("/proc/self/cgroup")?;
+    let path = text.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+        controllers
+            .split(',')
+            .any(|c| c == controller)
+            .then(|| path.to_string())
+    })?;
+    let dir = format!(
+        "/sys/fs/cgroup/{}{}",
+        controller,
+        path.trim_end_matches('/')
+    );
+    if fs::metadata(&dir).is_ok() {
+        Some(dir)
+    } else {
+        Some(format!("/sys/fs/cgroup/{}", controller))
+    }
+}
+
+/// The tightest CPU quota on the way to the root, in CPUs.
+fn cpu_quota() -> Option<f64> {
+    let v2 = v2_dirs()
+        .iter()
+        .filter_map(|dir| {
+            let max = read(&format!("{}/cpu.max", dir))?;
+            let (quota, period) = max.split_once(' ')?;
+            Some(quota.parse::<f64>().ok()? / period.parse::<f64>().ok()?)
+        })
+        .reduce(f64::min);
+    v2.or_else(|| {
+        let dir = v1_dir("cpu")?;
+        let quota: f64 = read(&format!("{}/cpu.cfs_quota_us", dir))?.parse().ok()?;
+        let period: f64 = read(&format!("{}/cpu.cfs_period_us", dir))?.parse().ok()?;
+        (quota > 0.0).then(|| quota / period)
+    })
+}
+
+fn throttling() -> Option<(u64, u64)> {
+    let field = |text: &str, name: &str| {
+        text.lines().find_map(|l| {
+            l.strip_prefix(name)
+                .and_then(|v| v.strip_prefix(' '))
+                .and_then(|v| v.parse::<u64>().ok())
+        })
+    };
+    if let Some(stat) = v2_dirs()
+        .first()
+        .and_then(|dir| read(&format!("{}/cpu.stat", dir)))
+    {
+        if let (Some(periods), Some(usec)) =
+            (field(&stat, "nr_throttled"), field(&stat, "throttled_usec"))
+        {
+            return Some((periods, usec));
+        }
+    }
+    let stat = read(&format!("{}/cpu.stat", v1_dir("cpu")?))?;
+    // v1 reports nanoseconds.
+    Some((
+        field(&stat, "nr_throttled")?,
+        field(&stat, "throttled_time")? / 1000,
+    ))
+}
+
+fn cpuset_cpus() -> Option<usize> {
+    v2_dirs()
+        .first()
+        .and_then(|dir| read(&format!("{}/cpuset.cpus.effective", dir)))
+        .or_else(|| read(&format!("{}/cpuset.effective_cpus", v1_dir("cpuset")?)))
+        .and_then(|list| count_cpu_list(&list))
+}
+
+fn online_cpus() -> Option<usize> {
+    count_cpu_list(&read("/sys/devices/system/cpu/online")?)
+}
+
+/// Counts the CPUs in a kernel list such as "0-3,8,10-11".
+fn count_cpu_list(list: &str) -> Option<usize> {
+    if list.is_empty() {
+        return None;
+    }
+    list.split(',').try_fold(0, |count, range| {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        let (first, last): (usize, usize) = (first.parse().ok()?, last.parse().ok()?);
+        Some(count + last.checked_sub(first)? + 1)
+    })
+}
+
+fn hypervisor() -> bool {
+    read("/proc/cpuinfo").is_some_and(|info| {
+        info.lines()
+            .find(|l| l.starts_with("flags"))
+            .is_some_and(|flags| flags.split_whitespace().any(|f| f == "hypervisor"))
+    })
+}
+
+/// The aggregate `steal` column of /proc/stat.
+fn steal_ticks() -> Option<u64> {
+    let stat = read("/proc/stat")?;
+    let cpu = stat.lines().next()?.strip_prefix("cpu ")?;
+    cpu.split_whitespace().nth(7)?.parse().ok()
+}
@@ -0,0 +1,200 @@
+//! The `ffi` subcommand. Everything that touches the C list is behind the
+//! `c-list` feature; without it the subcommand only says how to enable it.
+
+#[cfg(feature = "c-list")]
+pub use imp::run;
+
+#[cfg(not(feature = "c-list"))]
+pub fn run(_args: &[String]) {
+    eprintln!("Error: ffi needs the C list: rebuild with --features c-list");
+}
+
+#[cfg(feature = "c-list")]
+mod imp {
+    use std::mem;
+
+    use crate::bench::{self, Sample};
+    use crate::cache::{self, Layout};
+
+    /// The list of c/list.c, driven through its four functions.
+    mod c {
+        use std::ptr::NonNull;
+
+        /// `struct clist_node`: laid out exactly like `Node<usize>`.
+        #[repr(C)]
+        pub struct Node {
+            pub data: usize,
+            pub next: *const Node,
+        }
+
+        #[repr(C)]
+        pub struct RawList {
+            pub head: *const Node,
+            pub count: usize,
+        }
+
+        extern "C" {
+            fn clist_new() -> *mut RawList;
+            fn clist_push(list: *mut RawList, data: usize) -> i32;
+            fn clist_traverse(list: *const RawList) -> usize;
+            fn clist_free(list: *mut RawList);
+        }
+
+        /// Owns a C list and frees it on drop.
+        pub struct List(NonNull<RawList>);
+
+        impl List {
+            /// Pushes `0..nodes`, like `bench::build`. Panics when C's malloc
+            /// fails, as a Rust allocation failure would abort.
+            pub fn build(nodes: usize) -> Self {
+                // SAFETY: clist_new returns a valid empty list or NULL.
+                let list = NonNull::new(unsafe { clist_new() }).expect("clist_new: out of memory");
+                for i in 0..nodes {
+                    // SAFETY: `list` is live and only this thread touches it.
+                    assert!(
+                        unsafe { clist_push(list.as_ptr(), i) } != 0,
+                        "clist_push: out of memory"
+                    );
+                }
+                List(list)
+            }
+
+            pub fn traverse(&self) -> usize {
+                // SAFETY: the list is live; clist_traverse only reads it.
+                unsafe { clist_traverse(self.0.as_ptr()) }
+            }
+
+            pub fn raw(&self) -> &RawList {
+                // SAFETY: the pointer is live for as long as `self` is.
+                unsafe { self.0.as_ref() }
+            }
+        }
+
+        impl Drop for List {
+            fn drop(&mut self) {
+                // SAFETY: the list was made by clist_new and is freed only here.
+                unsafe { clist_free(self.0.as_ptr()) }
+            }
+        }
+    }
+
+    /// `ffi [--nodes <n>] [--iterations <k>]`: builds and traverses the Rust
+    /// list and the C list of c/list.c (compiled by build.rs at the same
+    /// optimisation level) under the same timer, and reports the two side by
+    /// side. Both push `0..n` with one malloc per 16-byte node, so the layouts
+    /// match as well; the table shows that they do.
+    pub fn run(args: &[String]) {
+        let mut nodes: usize = 1 << 20;
+        let mut iterations: usize = 10;
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match (arg.as_str(), iter.next()) {
+                ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+                ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+                _ => {
+                    eprintln!("Error: unknown or incomplete ffi option '{}'", arg);
+                    return;
+                }
+            }
+        }
+        let nodes = nodes.max(1);
+
+        // Both lists are built from the same fresh heap before anything has
+        // been freed, so each gets the allocator's contiguous layout, and
+        // the traversals alternate so drift hits both alike.
+        let rust_list = bench::build(nodes);
+        let c_list = c::List::build(nodes);
+        let visited = c_list.traverse();
+        if visited != nodes {
+            eprintln!(
+                "Error: the C list visited {} nodes, expected {}",
+                visited, nodes
+            );
+            return;
+        }
+        let rust_layout = cache::node_layout(&rust_list);
+        let c_layout = c_layout(c_list.raw());
+        let (mut rust_traverse, mut c_traverse) = (Vec::new(), Vec::new());
+        for _ in 0..iterations.max(1) {
+            rust_traverse.extend(bench::time_traversals(&rust_list, 1));
+            c_traverse.extend(bench::time_repeated(1, nodes, || c_list.traverse()));
+        }
+        drop((rust_list, c_list));
+
+        let (mut rust_build, mut c_build) = (Vec::new(), Vec::new());
+        for _ in 0..iterations.max(1) {
+            rust_build.extend(bench::time_repeated(1, nodes, || bench::build(nodes)));
+            c_build.extend(bench::time_repeated(1, nodes, || c::List::build(nodes)));
+        }
+
+        println!("--- Rust vs C Linked List ---");
+        println!(
+            "{} nodes; C compiled with {}",
+            nodes,
+            env!("CLIST_COMPILER")
+        );
+        println!(
+            "Node size: Rust {} bytes, C {} bytes",
+            mem::size_of::<crate::Node<usize>>(),
+            mem::size_of::<c::Node>()
+        );
+        println!(
+            "{:<10} {:<6} {:>12} {:>14} {:>14} {:>10} {:>9} {:>8}",
+            "Phase", "Impl", "ns/node", "cycles/node", "stddev", "Stride", "Adjacent", "Flagged"
+        );
+        for (phase, rust, c) in [
+            ("build", &rust_build, &c_build),
+            ("traverse", &rust_traverse, &c_traverse),
+        ] {
+            let rust_cycles = row(phase, "Rust", rust, &rust_layout);
+            let c_cycles = row(phase, "C", c, &c_layout);
+            println!(
+                "{:<10} {:<6} {:>11.2}x C/Rust median cycles per node",
+                "",
+                "",
+                c_cycles / rust_cycles.max(f64::MIN_POSITIVE)
+            );
+        }
+    }
+
+    /// Prints one implementation's row and returns its median cycles per node.
+    fn row(phase: &str, name: &str, samples: &[Sample], layout: &Layout) -> f64 {
+        let (ns, cycles, flagged) = bench::per_operation(samples);
+        println!(
+            "{:<10} {:<6} {:>12} {:>14} {:>14} {:>8} B {:>8.0}% {:>8}",
+            phase,
+            name,
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            layout.median_stride,
+            layout.adjacent_fraction * 100.0,
+            flagged
+        );
+        cycles.median
+    }
+
+    /// `cache::node_layout` for the C list, read through its `repr(C)` mirror.
+    fn c_layout(list: &c::RawList) -> Layout {
+        let mut strides = Vec::with_capacity(list.count.saturating_sub(1));
+        let mut current = list.head;
+        // SAFETY: every non-null `next` of a live C list points at a live node.
+        while let Some(node) = unsafe { current.as_ref() } {
+            if !node.next.is_null() {
+                strides.push((current as usize).abs_diff(node.next as usize));
+            }
+            current = node.next;
+        }
+        let adjacent = strides.iter().filter(|&&s| s <= 64).count();
+        strides.sort_unstable();
+        Layout {
+            median_stride: strides
+                .get(strides.len() / 2)
+                .copied()
+                .unwrap_or(mem::size_of::<c::Node>()),
+            adjacent_fraction: adjacent as f64 / strides.len().max(1) as f64,
+            misses: None,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::bench::{self, Sample};
+use crate::rng::Rng;
+use crate::stats::Summary;
+use crate::LinkedList;
+
+/// `filter [--nodes <n>] [--keep <percent>] [--run <length>] [--iterations <k>]`:
+/// times `retain` (drop the values failing a predicate) and `dedup` (drop
+/// consecutive repeats) on the linked list and on a `Vec` holding the same
+/// values.
+///
+/// Removal is where a list should win: unlinking a node is O(1) wherever
+/// it is, while `Vec` compacts the survivors. But both do it in one pass,
+/// so the `Vec` only moves each survivor once, through the cache, while the
+/// list pays a dependent load per node and a `free` per removal. Inputs are
+/// random so neither gets a predictable branch.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 20;
+    let mut keep: u64 = 50;
+    let mut run_length: usize = 4;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--keep", Some(v)) => keep = v.parse().unwrap_or(keep).min(100),
+            ("--run", Some(v)) => run_length = v.parse().unwrap_or(run_length).max(1),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete filter option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+
+    let mut rng = Rng::new(nodes as u64);
+    let random: Vec<u64> = (0..nodes).map(|_| rng.next_u64()).collect();
+    // Runs of 1..2*run-1 equal values, averaging `run_length`.
+    let mut runs = Vec::with_capacity(nodes);
+    let mut value = 0;
+    while runs.len() < nodes {
+        let length = 1 + rng.below(2 * run_length - 1);
+        runs.extend(std::iter::repeat_n(value, length.min(nodes - runs.len())));
+        value += 1;
+    }
+    let kept = move |v: &u64| v % 100 < keep;
+
+    println!("--- Filter In Place ---");
+    println!(
+        "{} nodes; retain keeps {}% of random values, dedup runs average {}",
+        nodes, keep, run_length
+    );
+    println!(
+        "{:<8} {:<10} {:>6} {:>12} {:>14} {:>10} {:>9} {:>8}",
+        "Op", "Structure", "Iters", "ns/node", "cycles/node", "Removed", "vs list", "Flagged"
+    );
+
+    compare(
+        "retain",
+        &random,
+        iterations,
+        |list| list.retain(kept),
+        |vec| vec.retain(kept),
+    );
+    compare(
+        "dedup",
+        &runs,
+        iterations,
+        |list| list.dedup(),
+        |vec| vec.dedup(),
+    );
+    println!("\nvs list: how many times faster than the linked list per input node");
+}
+
+/// Times `on_list` and `on_vec` on fresh copies of `input`, built before
+/// each timed run, and prints a row for each. The two must leave the same
+/// values behind.
+fn compare(
+    op: &str,
+    input: &[u64],
+    iterations: usize,
+    mut on_list: impl FnMut(&mut LinkedList<u64>),
+    mut on_vec: impl FnMut(&mut Vec<u64>),
+) {
+    let (mut list_samples, mut vec_samples) = (Vec::new(), Vec::new());
+    let (mut list_left, mut vec_left) = (Vec::new(), Vec::new());
+    for _ in 0..iterations.max(1) {
+        let mut list = LinkedList::from_vec(input.to_vec());
+        list_samples.extend(bench::time_repeated(1, input.len(), || on_list(&mut list)));
+        list_left = list.into_vec();
+
+        let mut vec = input.to_vec();
+        vec_samples.extend(bench::time_repeated(1, input.len(), || on_vec(&mut vec)));
+        vec_left = vec;
+    }
+    if list_left != vec_left {
+        eprintln!("Error: list and Vec {} left different values", op);
+        return;
+    }
+
+    let removed = input.len() - vec_left.len();
+    let (_, list_cycles, _) = bench::per_operation(&list_samples);
+    for (name, samples) in [("list", &list_samples), ("vec", &vec_samples)] {
+        row(op, name, samples, removed, &list_cycles);
+    }
+}
+
+fn row(op: &str, name: &str, samples: &[Sample], removed: usize, list_cycles: &Summary) {
+    let (ns, cycles, flagged) = bench::per_operation(samples);
+    println!(
+        "{:<8} {:<10} {:>6} {:>12} {:>14} {:>10} {:>9} {:>8}",
+        op,
+        name,
+        samples.len(),
+        bench::stat(&ns, ns.median, 2),
+        bench::stat(&cycles, cycles.median, 2),
+        removed,
+        bench::ratio(list_cycles, &cycles, 1),
+        flagged
+    );
+}
@@ -0,0 +1,279 @@
+use std::collections::VecDeque;
+use std::hint::black_box;
+
+use crate::bench;
+use crate::rng::Rng;
+use crate::stats::Summary;
+use crate::LinkedList;
+
+/// Elements each depth-curve sample visits in all, spread over as many
+/// searches as that takes, so shallow depths aren't lost in timer overhead.
+const VISITS_PER_SAMPLE: usize = 1 << 16;
+
+/// A structure searched front to back for a key, stopping at the first
+/// match.
+trait Searched {
+    fn from_values(values: &[usize]) -> Self;
+    /// Position of `key`, counting from the front.
+    fn find(&self, key: usize) -> Option<usize>;
+}
+
+impl Searched for LinkedList<usize> {
+    fn from_values(values: &[usize]) -> Self {
+        LinkedList::from_vec(values.to_vec())
+    }
+
+    fn find(&self, key: usize) -> Option<usize> {
+        let mut current = &self.head;
+        let mut position = 0;
+        while let Some(node) = current {
+            if node.data == key {
+                return Some(position);
+            }
+            position += 1;
+            current = &node.next;
+        }
+        None
+    }
+}
+
+impl Searched for Vec<usize> {
+    fn from_values(values: &[usize]) -> Self {
+        values.to_vec()
+    }
+
+    fn find(&self, key: usize) -> Option<usize> {
+        self.iter().position(|&v| v == key)
+    }
+}
+
+impl Searched for VecDeque<usize> {
+    fn from_values(values: &[usize]) -> Self {
+        values.iter().copied().collect()
+    }
+
+    fn find(&self, key: usize) -> Option<usize> {
+        self.iter().position(|&v| v == key)
+    }
+}
+
+/// Where in the structure a search stops.
+#[derive(Clone, Copy)]
+enum Distribution {
+    /// Any position equally likely: on average half the structure.
+    Uniform,
+    /// Position `r` (from 0) with probability proportional to
+    /// `1 / (r + 1)^s`: a few hot elements near the front take most of the
+    /// searches, with a long tail behind them.
+    Zipf(f64),
+}
+
+impl Distribution {
+    fn parse(text: &str) -> Option<Self> {
+        match text.split_once(':') {
+            None if text == "uniform" => Some(Distribution::Uniform),
+            None if text == "zipf" => Some(Distribution::Zipf(1.0)),
+            Some(("zipf", s)) => s
+                .parse()
+                .ok()
+                .filter(|s: &f64| s.is_finite() && *s > 0.0)
+                .map(Distribution::Zipf),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Distribution::Uniform => "uniform".to_string(),
+            Distribution::Zipf(s) => format!("zipf:{}", s),
+        }
+    }
+
+    /// `count` positions in `0..nodes`.
+    fn draw(self, nodes: usize, count: usize, rng: &mut Rng) -> Vec<usize> {
+        match self {
+            Distribution::Uniform => (0..count).map(|_| rng.below(nodes)).collect(),
+            Distribution::Zipf(s) => {
+                let mut total = 0.0;
+                let cumulative: Vec<f64> = (1..=nodes)
+                    .map(|rank| {
+                        total += (rank as f64).powf(-s);
+                        total
+                    })
+                    .collect();
+                (0..count)
+                    .map(|_| {
+                        let u = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * total;
+                        cumulative.partition_point(|&c| c <= u).min(nodes - 1)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// `find [--nodes <n>] [--distributions <uniform,zipf:s,...>] [--searches <k>]
+/// [--iterations <i>] [--seed <s>]`: searches a list, a `Vec` and a
+/// `VecDeque` of the same distinct keys front to back, stopping at the
+/// first match, the "find the k-th element" loop. First the cost of a
+/// search against how deep it stops, then the expected cost of `k`
+/// searches whose stopping points follow each distribution.
+///
+/// Uniform searches walk half the structure on average, which is the
+/// streaming case the `Vec` wins outright. Under zipf most searches stop
+/// within the first few elements, where the list's one dependent load per
+/// step costs little next to the `Vec`'s, and the tail decides how far
+/// apart they end up.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 16;
+    let mut distributions = vec![
+        Distribution::Uniform,
+        Distribution::Zipf(0.8),
+        Distribution::Zipf(1.2),
+    ];
+    let mut searches: usize = 2000;
+    let mut iterations: usize = 5;
+    let mut seed: u64 = 42;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--distributions", Some(v)) => {
+                let parsed: Option<Vec<Distribution>> = v
+                    .split(',')
+                    .map(|d| Distribution::parse(d.trim()))
+                    .collect();
+                match parsed {
+                    Some(list) if !list.is_empty() => distributions = list,
+                    _ => {
+                        eprintln!(
+                            "Error: bad --distributions '{}' (expected e.g. uniform,zipf:1.1)",
+                            v
+                        );
+                        return;
+                    }
+                }
+            }
+            ("--searches", Some(v)) => searches = v.parse().unwrap_or(searches),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--seed", Some(v)) => seed = v.parse().unwrap_or(seed),
+            _ => {
+                eprintln!("Error: unknown or incomplete find option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+    let searches = searches.max(1);
+    let iterations = iterations.max(1);
+
+    // Distinct keys in shuffled order, so a key says nothing about where
+    // it is stored.
+    let mut rng = Rng::new(seed);
+    let mut values: Vec<usize> = (0..nodes).collect();
+    rng.shuffle(&mut values);
+    let list: LinkedList<usize> = Searched::from_values(&values);
+    let vec: Vec<usize> = Searched::from_values(&values);
+    let deque: VecDeque<usize> = Searched::from_values(&values);
+
+    println!("--- Early-Exit Search ---");
+    println!(
+        "{} distinct keys, {} iterations (seed {})",
+        nodes, iterations, seed
+    );
+
+    println!("\n[Cost by Depth]");
+    println!(
+        "{:>10} {:>14} {:>14} {:>14} {:>10}",
+        "Stops at", "list cyc", "vec cyc", "deque cyc", "list/vec"
+    );
+    let depths: Vec<usize> = std::iter::successors(Some(1), |&d| Some(d * 4))
+        .take_while(|&d| d < nodes)
+        .chain(std::iter::once(nodes))
+        .collect();
+    for &depth in &depths {
+        let key = values[depth - 1];
+        let repeats = (VISITS_PER_SAMPLE / depth).max(1);
+        let list_cycles = per_search(iterations, &list, &vec![key; repeats]);
+        let vec_cycles = per_search(iterations, &vec, &vec![key; repeats]);
+        let deque_cycles = per_search(iterations, &deque, &vec![key; repeats]);
+        println!(
+            "{:>10} {:>14} {:>14} {:>14} {:>10}",
+            depth,
+            bench::stat(&list_cycles.1, list_cycles.1.median, 1),
+            bench::stat(&vec_cycles.1, vec_cycles.1.median, 1),
+            bench::stat(&deque_cycles.1, deque_cycles.1.median, 1),
+            bench::ratio(&list_cycles.1, &vec_cycles.1, 2)
+        );
+    }
+    println!("Stops at: elements visited, the match included; cycles are medians per search");
+
+    println!("\n[Expected Cost by Distribution]");
+    println!(
+        "{} searches per iteration, stopping points drawn afresh for each distribution",
+        searches
+    );
+    println!(
+        "{:<12} {:<9} {:>11} {:>12} {:>12} {:>10} {:>9} {:>8}",
+        "Distrib.",
+        "Structure",
+        "Mean depth",
+        "ns/search",
+        "cyc/search",
+        "cyc/elem",
+        "vs vec",
+        "Flagged"
+    );
+    for distribution in &distributions {
+        let positions = distribution.draw(nodes, searches, &mut rng);
+        let keys: Vec<usize> = positions.iter().map(|&p| values[p]).collect();
+        let depth = positions.iter().map(|&p| p + 1).sum::<usize>() as f64 / searches as f64;
+        let expected: usize = positions.iter().sum();
+
+        let rows = [
+            ("list", per_search(iterations, &list, &keys)),
+            ("vec", per_search(iterations, &vec, &keys)),
+            ("deque", per_search(iterations, &deque, &keys)),
+        ];
+        let vec_cycles = rows[1].1 .1;
+        for (name, (ns, cycles, flagged, found)) in rows {
+            if found != expected {
+                eprintln!(
+                    "Error: {} found keys at the wrong positions (sum {} != {})",
+                    name, found, expected
+                );
+            }
+            println!(
+                "{:<12} {:<9} {:>11.1} {:>12} {:>12} {:>10} {:>9} {:>8}",
+                distribution.name(),
+                name,
+                depth,
+                bench::stat(&ns, ns.median, 1),
+                bench::stat(&cycles, cycles.median, 1),
+                bench::stat(&cycles, cycles.median / depth, 2),
+                bench::ratio(&cycles, &vec_cycles, 2),
+                flagged
+            );
+        }
+    }
+    println!("Mean depth: elements visited per search on average; cyc/elem: cyc/search over it");
+}
+
+/// Ns and cycles per search for `keys` in `structure`, the number of
+/// flagged samples, and the sum of the positions found.
+fn per_search<S: Searched>(
+    iterations: usize,
+    structure: &S,
+    keys: &[usize],
+) -> (Summary, Summary, usize, usize) {
+    let search_all = || {
+        keys.iter()
+            .map(|&key| structure.find(black_box(key)).unwrap_or(0))
+            .sum::<usize>()
+    };
+    let found = search_all();
+    let samples = bench::time_repeated(iterations, keys.len(), search_all);
+    let (ns, cycles, flagged) = bench::per_operation(&samples);
+    (ns, cycles, flagged, found)
+}
@@ -0,0 +1,187 @@
+use crate::bench::{self, BenchResult};
+use crate::cache;
+use crate::rng::Rng;
+use crate::stats::Summary;
+use crate::{LinkedList, Node};
+
+/// Smallest and largest survivor block, in bytes; sizes step by malloc's
+/// 16-byte granularity so each one lands in a different size class.
+const SURVIVOR_BYTES: (usize, usize) = (16, 256);
+
+/// `generations [--nodes <n>] [--generations <g>] [--iterations <i>]
+/// [--survivors <percent>] [--seed <s>]`: frees and rebuilds the list `g`
+/// times in one process and times every generation, next to one list built
+/// up front and reused throughout. The reused list is the control, drifting
+/// only with the machine; whatever the rebuilt list drifts beyond it is the
+/// allocator's state changing under it.
+///
+/// A real process doesn't free everything between runs, so while each
+/// generation is built it also allocates `--survivors` percent as many
+/// blocks of assorted sizes, interleaved with the nodes, which outlive it;
+/// a random half of the older survivors is freed before every build. The
+/// next list fills the holes they leave around themselves.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 18;
+    let mut generations: usize = 20;
+    let mut iterations: usize = 5;
+    let mut survivors: usize = 10;
+    let mut seed: u64 = 42;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--generations", Some(v)) => generations = v.parse().unwrap_or(generations),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--survivors", Some(v)) => survivors = v.parse().unwrap_or(survivors),
+            ("--seed", Some(v)) => seed = v.parse().unwrap_or(seed),
+            _ => {
+                eprintln!("Error: unknown or incomplete generations option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+    let generations = generations.max(1);
+    let iterations = iterations.max(1);
+
+    println!("--- Allocator Drift Across Generations ---");
+    println!(
+        "{} nodes, {} generations of {} traversals; {}% survivors (seed {})",
+        nodes, generations, iterations, survivors, seed
+    );
+    println!(
+        "{:>4} {:>14} {:>10} {:>10} {:>10} {:>14} {:>9} {:>8}",
+        "Gen",
+        "Rebuilt cyc/n",
+        "stddev",
+        "Adjacent",
+        "Span MiB",
+        "Reused cyc/n",
+        "Ratio",
+        "Flagged"
+    );
+
+    let mut rng = Rng::new(seed);
+    let reused = bench::build(nodes);
+    let mut kept: Vec<Vec<u8>> = Vec::new();
+    let mut rebuilt_medians = Vec::new();
+    let mut reused_medians = Vec::new();
+    for generation in 0..generations {
+        rng.shuffle(&mut kept);
+        kept.truncate(kept.len() / 2);
+        let list = build_among(nodes, nodes * survivors / 100, &mut rng, &mut kept);
+
+        // Alternate which list goes first, so warming one up for the other
+        // favours neither.
+        let time = |list: &LinkedList<usize>| BenchResult {
+            name: "traverse".to_string(),
+            nodes,
+            samples: bench::time_traversals(list, iterations),
+            layout: cache::node_layout(list),
+        };
+        let (rebuilt, control) = if generation % 2 == 0 {
+            let rebuilt = time(&list);
+            (rebuilt, time(&reused))
+        } else {
+            let control = time(&reused);
+            (time(&list), control)
+        };
+        let span = span_bytes(&list);
+        drop(list);
+
+        let cycles = rebuilt.cycles_per_node();
+        let control_cycles = control.cycles_per_node();
+        println!(
+            "{:>4} {:>14} {:>10} {:>9.0}% {:>10.1} {:>14} {:>9} {:>8}",
+            generation + 1,
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            rebuilt.layout.adjacent_fraction * 100.0,
+            span as f64 / (1 << 20) as f64,
+            bench::stat(&control_cycles, control_cycles.median, 2),
+            bench::ratio(&cycles, &control_cycles, 2),
+            rebuilt.flagged() + control.flagged()
+        );
+        rebuilt_medians.push(cycles.median);
+        reused_medians.push(control_cycles.median);
+    }
+
+    let rebuilt_trend = trend(&rebuilt_medians);
+    let reused_trend = trend(&reused_medians);
+    println!(
+        "\nRebuilt: generation 1 {:.2}, last {:.2} cycles/node; trend {:+.2}% of generation 1 per generation",
+        rebuilt_medians[0],
+        rebuilt_medians[generations - 1],
+        rebuilt_trend
+    );
+    println!(
+        "Reused:  generation 1 {:.2}, last {:.2} cycles/node; trend {:+.2}% of generation 1 per generation",
+        reused_medians[0],
+        reused_medians[generations - 1],
+        reused_trend
+    );
+    let noise = Summary::of(&reused_medians);
+    println!(
+        "Allocator drift (rebuilt trend less reused): {:+.2}% per generation, {:+.1}% over the run; reused spread {:.1}% of its median",
+        rebuilt_trend - reused_trend,
+        (rebuilt_trend - reused_trend) * (generations - 1) as f64,
+        noise.stddev * 100.0 / noise.median.max(f64::MIN_POSITIVE)
+    );
+    println!("Span: distance from the lowest node to the highest; Ratio: rebuilt over reused cycles/node in the same generation");
+    println!("Generation 1 already differs from the reused list by the survivors between its nodes; the trends are the comparison");
+}
+
+/// Builds a list of `nodes` like [`bench::build`], allocating `survivors`
+/// blocks of random sizes at even intervals between the nodes and adding
+/// them to `kept`.
+fn build_among(
+    nodes: usize,
+    survivors: usize,
+    rng: &mut Rng,
+    kept: &mut Vec<Vec<u8>>,
+) -> LinkedList<usize> {
+    let every = nodes
+        .checked_div(survivors)
+        .map_or(usize::MAX, |every| every.max(1));
+    let steps = (SURVIVOR_BYTES.1 - SURVIVOR_BYTES.0) / 16 + 1;
+    let mut list = LinkedList::new();
+    for i in 0..nodes {
+        list.push(i);
+        if i % every == every - 1 {
+            kept.push(vec![0u8; SURVIVOR_BYTES.0 + rng.below(steps) * 16]);
+        }
+    }
+    list
+}
+
+/// Bytes from the lowest node's address to the end of the highest one.
+fn span_bytes(list: &LinkedList<usize>) -> usize {
+    let (mut low, mut high) = (usize::MAX, 0);
+    let mut current = &list.head;
+    while let Some(node) = current {
+        let address = &**node as *const Node<usize> as usize;
+        low = low.min(address);
+        high = high.max(address + std::mem::size_of::<Node<usize>>());
+        current = &node.next;
+    }
+    high.saturating_sub(low)
+}
+
+/// Least-squares slope of `medians` against generation number, as a
+/// percentage of the first generation's value.
+fn trend(medians: &[f64]) -> f64 {
+    let n = medians.len() as f64;
+    if medians.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = medians.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, y) in medians.iter().enumerate() {
+        let dx = x as f64 - mean_x;
+        covariance += dx * (y - mean_y);
+        variance += dx * dx;
+    }
+    covariance / variance * 100.0 / medians[0].max(f64::MIN_POSITIVE)
+}
@@ -0,0 +1,87 @@
+//! Per-operation latencies for the mixed-workload and concurrent benchmarks,
+//! kept in an HDR histogram rather than averaged: a mean per operation
+//! hides the one seek in a thousand that walks half the list, or the
+//! allocation that drops into the allocator's slow path.
+
+use std::fs;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+use hdrhistogram::serialization::interval_log::{IntervalLogWriterBuilder, Tag};
+use hdrhistogram::serialization::V2DeflateSerializer;
+use hdrhistogram::Histogram;
+
+/// Significant decimal digits kept for every value: 0.1% resolution from a
+/// handful of cycles up to minutes.
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Cycles per operation, recorded with `SIGNIFICANT_DIGITS` of precision
+/// whatever the magnitude, so the tail costs no more memory than the
+/// median.
+pub struct Latencies(Histogram<u64>);
+
+impl Latencies {
+    pub fn new() -> Self {
+        // Auto-resizing from one cycle up: no ceiling to guess in advance.
+        Latencies(Histogram::new(SIGNIFICANT_DIGITS).expect("valid histogram precision"))
+    }
+
+    pub fn record(&mut self, cycles: u64) {
+        // Only a value past u64::MAX / 2 can fail, which no operation takes.
+        let _ = self.0.record(cycles.max(1));
+    }
+
+    pub fn add(&mut self, other: &Latencies) {
+        let _ = self.0.add(&other.0);
+    }
+
+    /// The header cells matching [`cells`](Latencies::cells).
+    pub fn columns() -> String {
+        format!(
+            "{:>10} {:>10} {:>10} {:>12}",
+            "p50 cyc", "p99 cyc", "p99.9 cyc", "max cyc"
+        )
+    }
+
+    /// p50, p99, p99.9 and max, laid out under [`columns`](Latencies::columns).
+    pub fn cells(&self) -> String {
+        format!(
+            "{:>10} {:>10} {:>10} {:>12}",
+            self.0.value_at_quantile(0.50),
+            self.0.value_at_quantile(0.99),
+            self.0.value_at_quantile(0.999),
+            self.0.max()
+        )
+    }
+}
+
+/// Writes `histograms` to `path` as an HdrHistogram interval log (the
+/// V2-compressed, base64 format that HistogramLogProcessor and the online
+/// plotters read), one interval per histogram laid end to end, tagged with
+/// its name. Values are cycles, which the log's comment header says.
+pub fn export(path: &str, histograms: &[(String, &Latencies, Duration)]) -> io::Result<()> {
+    let total: Duration = histograms.iter().map(|h| h.2).sum();
+    let began = SystemTime::now()
+        .checked_sub(total)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    let mut serializer = V2DeflateSerializer::new();
+    let mut log = IntervalLogWriterBuilder::new()
+        .add_comment("linked_list_bench per-operation latencies, in CPU cycles")
+        .with_start_time(began)
+        .with_base_time(began)
+        .begin_log_with(&mut out, &mut serializer)?;
+    let mut start = Duration::ZERO;
+    for (name, latencies, length) in histograms {
+        // Tags can't hold spaces or commas.
+        let tag: String = name
+            .chars()
+            .map(|c| if c == ' ' || c == ',' { '-' } else { c })
+            .collect();
+        log.write_histogram(&latencies.0, start, *length, Tag::new(&tag))
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        start += *length;
+    }
+    drop(log);
+    out.flush()
+}
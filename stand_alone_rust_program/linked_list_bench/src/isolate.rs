@@ -0,0 +1,29 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Runs this binary again with `args` and returns what it printed. Each
+/// child starts from a fresh address space: an untouched heap, cold page
+/// tables and allocator state no earlier benchmark has shaped. Affinity and
+/// priority set by `--pin` are inherited.
+pub fn child(args: &[String]) -> io::Result<String> {
+    let output = Command::new(std::env::current_exe()?).args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "child exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A scratch file for a child to write its results to, unique to this
+/// process and `tag`.
+pub fn scratch_path(tag: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "linked_list_bench-{}-{}.tsv",
+        std::process::id(),
+        tag
+    ))
+}
@@ -0,0 +1,631 @@
+//! The singly linked list every benchmark walks, and the operations the
+//! list benchmarks time on it. Only safe code, with no timing in it, so the
+//! fuzz target can build it on its own.
+
+pub struct Node<T> {
+    pub data: T,
+    pub next: Link<T>,
+}
+
+pub type Link<T> = Option<Box<Node<T>>>;
+
+pub struct LinkedList<T> {
+    pub head: Link<T>,
+    pub count: usize,
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        LinkedList::new()
+    }
+}
+
+impl<T> LinkedList<T> {
+    pub fn new() -> Self {
+        LinkedList {
+            head: None,
+            count: 0,
+        }
+    }
+
+    pub fn push(&mut self, data: T) {
+        let new_node = Box::new(Node {
+            data,
+            next: self.head.take(),
+        });
+        self.head = Some(new_node);
+        self.count += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_node().map(|node| node.data)
+    }
+
+    /// Links an already allocated node in at the head.
+    pub fn push_node(&mut self, mut node: Box<Node<T>>) {
+        node.next = self.head.take();
+        self.head = Some(node);
+        self.count += 1;
+    }
+
+    /// A list holding `values` front to back, allocated back to front.
+    pub fn from_vec(values: Vec<T>) -> Self {
+        let mut list = LinkedList::new();
+        for value in values.into_iter().rev() {
+            list.push(value);
+        }
+        list
+    }
+
+    /// Pops every value into a `Vec`, front to back.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.count);
+        while let Some(value) = self.pop() {
+            values.push(value);
+        }
+        values
+    }
+
+    /// Unlinks the head node without freeing it.
+    pub fn pop_node(&mut self) -> Option<Box<Node<T>>> {
+        let mut node = self.head.take()?;
+        self.head = node.next.take();
+        self.count -= 1;
+        Some(node)
+    }
+
+    /// Unlinks and frees every node whose value fails `keep`, in one pass,
+    /// keeping the survivors in order.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let mut link = &mut self.head;
+        while let Some(data) = link.as_ref().map(|node| &node.data) {
+            if keep(data) {
+                link = &mut link.as_mut().unwrap().next;
+            } else {
+                let node = *link.take().unwrap();
+                *link = node.next;
+                self.count -= 1;
+            }
+        }
+    }
+
+    /// The link `index` nodes in, or the final `None` if the list is
+    /// shorter: where a positional operation lands after its seek.
+    fn link_at(&mut self, index: usize) -> &mut Link<T> {
+        let mut link = &mut self.head;
+        for _ in 0..index {
+            match link {
+                Some(node) => link = &mut node.next,
+                None => break,
+            }
+        }
+        link
+    }
+
+    /// Inserts `data` so it ends up `index` nodes in, or at the end if the
+    /// list is shorter.
+    pub fn insert_at(&mut self, index: usize, data: T) {
+        let link = self.link_at(index);
+        let next = link.take();
+        *link = Some(Box::new(Node { data, next }));
+        self.count += 1;
+    }
+
+    /// Unlinks and returns the value `index` nodes in.
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let link = self.link_at(index);
+        let node = *link.take()?;
+        *link = node.next;
+        self.count -= 1;
+        Some(node.data)
+    }
+
+    /// Cuts the list after its first `index` nodes and returns the rest.
+    pub fn split_off(&mut self, index: usize) -> LinkedList<T> {
+        let index = index.min(self.count);
+        let tail = LinkedList {
+            head: self.link_at(index).take(),
+            count: self.count - index,
+        };
+        self.count = index;
+        tail
+    }
+
+    /// Links `other` on at the end: a walk of this whole list.
+    pub fn append(&mut self, mut other: LinkedList<T>) {
+        let count = std::mem::take(&mut other.count);
+        *self.link_at(self.count) = other.head.take();
+        self.count += count;
+    }
+
+    /// Interleaves `other` into this list, one node of each in turn, by
+    /// relinking; whichever list is longer supplies the tail.
+    pub fn zip(&mut self, mut other: LinkedList<T>) {
+        self.count += std::mem::take(&mut other.count);
+        let mut rest = other.head.take();
+        let mut link = &mut self.head;
+        while let Some(mut node) = rest.take() {
+            if link.is_none() {
+                *link = Some(node);
+                break;
+            }
+            link = &mut link.as_mut().unwrap().next;
+            rest = node.next.take();
+            node.next = link.take();
+            *link = Some(node);
+            link = &mut link.as_mut().unwrap().next;
+        }
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    /// Removes consecutive repeats, keeping the first of each run, like
+    /// `Vec::dedup`.
+    pub fn dedup(&mut self) {
+        let mut current = &mut self.head;
+        while let Some(node) = current {
+            while node
+                .next
+                .as_ref()
+                .is_some_and(|next| next.data == node.data)
+            {
+                let next = *node.next.take().unwrap();
+                node.next = next.next;
+                self.count -= 1;
+            }
+            current = &mut node.next;
+        }
+    }
+}
+
+impl<T: Ord> LinkedList<T> {
+    /// Inserts `data` before the first node holding a larger value, so a
+    /// list built only by this stays in ascending order. Equal values keep
+    /// their insertion order.
+    pub fn insert_sorted(&mut self, data: T) {
+        let mut link = &mut self.head;
+        while link.as_ref().is_some_and(|node| node.data <= data) {
+            link = &mut link.as_mut().unwrap().next;
+        }
+        let next = link.take();
+        *link = Some(Box::new(Node { data, next }));
+        self.count += 1;
+    }
+
+    /// Merges the sorted `other` into this sorted list by relinking its
+    /// nodes, allocating nothing. On equal values this list's come first.
+    pub fn merge(&mut self, mut other: LinkedList<T>) {
+        self.count += std::mem::take(&mut other.count);
+        let mut rest = other.head.take();
+        let mut link = &mut self.head;
+        while let Some(mut node) = rest.take() {
+            while link.as_ref().is_some_and(|here| here.data <= node.data) {
+                link = &mut link.as_mut().unwrap().next;
+            }
+            if link.is_none() {
+                // This list is used up: the rest of `other` goes on whole.
+                *link = Some(node);
+                break;
+            }
+            rest = node.next.take();
+            node.next = link.take();
+            *link = Some(node);
+            link = &mut link.as_mut().unwrap().next;
+        }
+    }
+}
+
+impl<T> Drop for LinkedList<T> {
+    /// The default drop recurses once per node and overflows the stack on
+    /// big lists, so unlink the nodes one at a time instead.
+    fn drop(&mut self) {
+        let mut current = self.head.take();
+        while let Some(mut node) = current {
+            current = node.next.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::rc::Rc;
+
+    fn list_of(values: &[i32]) -> LinkedList<i32> {
+        LinkedList::from_vec(values.to_vec())
+    }
+
+    /// Ordered by its key alone, so the tag tells equal keys apart.
+    #[derive(Debug)]
+    struct Keyed(i32, char);
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    fn tags(list: LinkedList<Keyed>) -> String {
+        list.into_vec().into_iter().map(|keyed| keyed.1).collect()
+    }
+
+    #[test]
+    fn push_and_pop_are_last_in_first_out() {
+        let mut list = LinkedList::new();
+        for value in 1..=3 {
+            list.push(value);
+        }
+        assert_eq!(list.count, 3);
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(2));
+        list.push(4);
+        assert_eq!(list.into_vec(), vec![4, 1]);
+    }
+
+    #[test]
+    fn split_off_keeps_the_front_and_returns_the_rest() {
+        let mut front = list_of(&[1, 2, 3, 4, 5]);
+        let back = front.split_off(2);
+        assert_eq!((front.count, back.count), (2, 3));
+        assert_eq!(front.into_vec(), vec![1, 2]);
+        assert_eq!(back.into_vec(), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn insert_at_the_end_or_past_it_appends() {
+        let mut list = LinkedList::new();
+        list.insert_at(0, 2);
+        list.insert_at(0, 1);
+        list.insert_at(2, 4);
+        list.insert_at(2, 3);
+        list.insert_at(100, 5);
+        assert_eq!(list.count, 5);
+        assert_eq!(list.into_vec(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn remove_at_the_end_or_past_it_returns_none() {
+        let mut list = list_of(&[1, 2, 3, 4]);
+        assert_eq!(list.remove_at(4), None);
+        assert_eq!(list.remove_at(10), None);
+        assert_eq!(list.count, 4);
+        assert_eq!(list.remove_at(3), Some(4));
+        assert_eq!(list.remove_at(1), Some(2));
+        assert_eq!(list.remove_at(0), Some(1));
+        assert_eq!(list.count, 1);
+        assert_eq!(list.into_vec(), vec![3]);
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.remove_at(0), None);
+        assert_eq!(list.count, 0);
+    }
+
+    #[test]
+    fn split_off_at_either_end() {
+        let mut list = list_of(&[1, 2, 3]);
+        let all = list.split_off(0);
+        assert_eq!((list.count, all.count), (0, 3));
+        assert!(list.head.is_none());
+        assert_eq!(all.into_vec(), vec![1, 2, 3]);
+
+        let mut list = list_of(&[1, 2, 3]);
+        let none = list.split_off(3);
+        assert_eq!((list.count, none.count), (3, 0));
+        assert!(none.head.is_none());
+        let none = list.split_off(10);
+        assert_eq!((list.count, none.count), (3, 0));
+        assert_eq!(list.into_vec(), vec![1, 2, 3]);
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.split_off(0).count, 0);
+    }
+
+    #[test]
+    fn append_to_or_of_an_empty_list() {
+        let mut list = LinkedList::new();
+        list.append(list_of(&[1, 2]));
+        list.append(LinkedList::new());
+        assert_eq!(list.count, 2);
+        assert_eq!(list.into_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn append_links_the_other_list_on_the_end() {
+        let mut list = list_of(&[1, 2]);
+        list.append(list_of(&[3, 4, 5]));
+        assert_eq!(list.count, 5);
+        assert_eq!(list.into_vec(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn merge_interleaves_two_sorted_lists() {
+        let mut list = list_of(&[1, 4, 6, 9]);
+        list.merge(list_of(&[2, 3, 7, 10, 11]));
+        assert_eq!(list.count, 9);
+        assert_eq!(list.into_vec(), vec![1, 2, 3, 4, 6, 7, 9, 10, 11]);
+    }
+
+    #[test]
+    fn insert_sorted_keeps_the_list_ascending() {
+        let mut list = LinkedList::new();
+        for value in [5, 1, 9, 3, 1, 7, 0, 9] {
+            list.insert_sorted(value);
+        }
+        assert_eq!(list.count, 8);
+        assert_eq!(list.into_vec(), vec![0, 1, 1, 3, 5, 7, 9, 9]);
+    }
+
+    #[test]
+    fn insert_sorted_keeps_equal_values_in_insertion_order() {
+        let mut list = LinkedList::new();
+        for (key, tag) in [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (2, 'e'), (3, 'f')] {
+            list.insert_sorted(Keyed(key, tag));
+        }
+        assert_eq!(tags(list), "bdacef");
+    }
+
+    #[test]
+    fn retain_unlinks_the_rejected_values_in_place() {
+        let mut list = list_of(&[1, 2, 3, 4, 5, 6, 7]);
+        list.retain(|value| value % 3 != 0);
+        assert_eq!(list.count, 5);
+        assert_eq!(list.into_vec(), vec![1, 2, 4, 5, 7]);
+
+        let mut list = list_of(&[1, 2, 3]);
+        list.retain(|_| false);
+        assert_eq!(list.count, 0);
+        assert!(list.head.is_none());
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.retain(|_| true);
+        assert_eq!(list.count, 0);
+    }
+
+    #[test]
+    fn dedup_keeps_the_first_of_each_run() {
+        let mut list = list_of(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        list.dedup();
+        assert_eq!(list.count, 5);
+        assert_eq!(list.into_vec(), vec![1, 2, 3, 1, 4]);
+
+        let mut list = LinkedList::new();
+        for tag in "abcd".chars() {
+            list.push(Keyed(7, tag));
+        }
+        list.dedup();
+        assert_eq!(list.count, 1);
+        assert_eq!(tags(list), "d");
+
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.dedup();
+        assert_eq!(list.count, 0);
+    }
+
+    #[test]
+    fn merge_puts_this_lists_equal_keys_first() {
+        let keyed = |keys: &[(i32, char)]| {
+            LinkedList::from_vec(keys.iter().map(|&(key, tag)| Keyed(key, tag)).collect())
+        };
+        let mut list = keyed(&[(1, 'a'), (2, 'b'), (2, 'c'), (4, 'd')]);
+        list.merge(keyed(&[(1, 'e'), (2, 'f'), (3, 'g'), (4, 'h'), (4, 'i')]));
+        assert_eq!(list.count, 9);
+        assert_eq!(tags(list), "aebcfgdhi");
+    }
+
+    #[test]
+    fn merge_with_an_empty_list() {
+        let mut list = list_of(&[1, 2]);
+        list.merge(LinkedList::new());
+        assert_eq!(list.into_vec(), vec![1, 2]);
+
+        let mut list = LinkedList::new();
+        list.merge(list_of(&[1, 2]));
+        assert_eq!(list.count, 2);
+        assert_eq!(list.into_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn zip_takes_the_tail_from_the_longer_list() {
+        let mut list = list_of(&[1, 3]);
+        list.zip(list_of(&[2, 4, 6, 8]));
+        assert_eq!(list.count, 6);
+        assert_eq!(list.into_vec(), vec![1, 2, 3, 4, 6, 8]);
+
+        let mut list = list_of(&[1, 3, 5, 7]);
+        list.zip(list_of(&[2]));
+        assert_eq!(list.count, 5);
+        assert_eq!(list.into_vec(), vec![1, 2, 3, 5, 7]);
+    }
+
+    #[test]
+    fn zip_with_an_empty_list() {
+        let mut list = list_of(&[1, 2]);
+        list.zip(LinkedList::new());
+        assert_eq!(list.into_vec(), vec![1, 2]);
+
+        let mut list = LinkedList::new();
+        list.zip(list_of(&[1, 2]));
+        assert_eq!(list.count, 2);
+        assert_eq!(list.into_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn drop_frees_every_node() {
+        let payload = Rc::new(());
+        let mut list = LinkedList::new();
+        for _ in 0..100 {
+            list.push(Rc::clone(&payload));
+        }
+        let tail = list.split_off(40);
+        drop(list);
+        assert_eq!(Rc::strong_count(&payload), 61);
+        drop(tail);
+        assert_eq!(Rc::strong_count(&payload), 1);
+    }
+
+    #[test]
+    fn drop_of_a_long_list_does_not_recurse() {
+        // Deep enough to overflow a test thread's stack with the recursive
+        // drop; Miri is too slow for that many, but still checks the walk.
+        let nodes = if cfg!(miri) { 1_000 } else { 1_000_000 };
+        let mut list = LinkedList::new();
+        for value in 0..nodes {
+            list.push(value);
+        }
+        drop(list);
+    }
+
+    /// One operation of a model test, applied to a list and to the `Vec`
+    /// that models it.
+    #[derive(Clone, Debug)]
+    enum Op {
+        Push(u8),
+        Pop,
+        InsertAt(usize, u8),
+        RemoveAt(usize),
+        SplitOff(usize),
+        Append(Vec<u8>),
+        Zip(Vec<u8>),
+        /// Keeps the values not divisible by this.
+        Retain(u8),
+        Dedup,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        // Small values repeat, so dedup and retain have something to do;
+        // indices reach past the end of the short lists.
+        let value = 0..4u8;
+        let values = prop::collection::vec(0..4u8, 0..6);
+        prop_oneof![
+            value.clone().prop_map(Op::Push),
+            Just(Op::Pop),
+            (0..12usize, value).prop_map(|(i, v)| Op::InsertAt(i, v)),
+            (0..12usize).prop_map(Op::RemoveAt),
+            (0..12usize).prop_map(Op::SplitOff),
+            values.clone().prop_map(Op::Append),
+            values.prop_map(Op::Zip),
+            (1..4u8).prop_map(Op::Retain),
+            Just(Op::Dedup),
+        ]
+    }
+
+    /// The list's values front to back, leaving it as it is.
+    fn values<T: Clone>(list: &LinkedList<T>) -> Vec<T> {
+        let mut values = Vec::new();
+        let mut current = &list.head;
+        while let Some(node) = current {
+            values.push(node.data.clone());
+            current = &node.next;
+        }
+        values
+    }
+
+    proptest! {
+        #![proptest_config(crate::testing::proptest_config())]
+
+        #[test]
+        fn operations_match_a_vec(ops in prop::collection::vec(op(), 0..40)) {
+            let mut list = LinkedList::new();
+            let mut model: Vec<u8> = Vec::new();
+            for op in ops {
+                match op {
+                    Op::Push(v) => {
+                        list.push(v);
+                        model.insert(0, v);
+                    }
+                    Op::Pop => {
+                        let expected = (!model.is_empty()).then(|| model.remove(0));
+                        prop_assert_eq!(list.pop(), expected);
+                    }
+                    Op::InsertAt(i, v) => {
+                        list.insert_at(i, v);
+                        model.insert(i.min(model.len()), v);
+                    }
+                    Op::RemoveAt(i) => {
+                        let expected = (i < model.len()).then(|| model.remove(i));
+                        prop_assert_eq!(list.remove_at(i), expected);
+                    }
+                    Op::SplitOff(i) => {
+                        let tail = list.split_off(i);
+                        let expected = model.split_off(i.min(model.len()));
+                        prop_assert_eq!(tail.count, expected.len());
+                        prop_assert_eq!(tail.into_vec(), expected);
+                    }
+                    Op::Append(values) => {
+                        list.append(LinkedList::from_vec(values.clone()));
+                        model.extend(values);
+                    }
+                    Op::Zip(values) => {
+                        list.zip(LinkedList::from_vec(values.clone()));
+                        let mut zipped = Vec::new();
+                        let (mut ours, mut theirs) = (model.drain(..), values.into_iter());
+                        loop {
+                            match (ours.next(), theirs.next()) {
+                                (None, None) => break,
+                                (a, b) => zipped.extend(a.into_iter().chain(b)),
+                            }
+                        }
+                        drop(ours);
+                        model = zipped;
+                    }
+                    Op::Retain(d) => {
+                        list.retain(|v| v % d != 0);
+                        model.retain(|v| v % d != 0);
+                    }
+                    Op::Dedup => {
+                        list.dedup();
+                        model.dedup();
+                    }
+                }
+                prop_assert_eq!(list.count, model.len());
+                prop_assert_eq!(values(&list), model.clone());
+            }
+        }
+
+        #[test]
+        fn sorted_operations_match_a_stable_sort(
+            ours in prop::collection::vec((0..4i32, any::<char>()), 0..20),
+            theirs in prop::collection::vec((0..4i32, any::<char>()), 0..20),
+        ) {
+            let sorted = |pairs: &[(i32, char)]| {
+                let mut pairs = pairs.to_vec();
+                pairs.sort_by_key(|&(key, _)| key);
+                pairs
+            };
+            let build = |pairs: &[(i32, char)]| {
+                let mut list = LinkedList::new();
+                for &(key, tag) in pairs {
+                    list.insert_sorted(Keyed(key, tag));
+                }
+                list
+            };
+            let mut list = build(&ours);
+            prop_assert_eq!(tags(build(&ours)), tags_of(&sorted(&ours)));
+
+            list.merge(build(&theirs));
+            let mut model = sorted(&ours);
+            model.extend(sorted(&theirs));
+            prop_assert_eq!(list.count, model.len());
+            prop_assert_eq!(tags(list), tags_of(&sorted(&model)));
+        }
+    }
+
+    fn tags_of(pairs: &[(i32, char)]) -> String {
+        pairs.iter().map(|&(_, tag)| tag).collect()
+    }
+}
@@ -0,0 +1,475 @@
+//! Lock-free (Treiber) stacks for the `reclaim` subcommand, identical but
+//! for what happens to a node once it has been popped. Another thread may
+//! still be reading a popped node (it loaded the old head just before the
+//! pop), so it can't simply be freed: [`LeakyStack`] keeps every popped
+//! node until the stack itself is dropped, [`EpochStack`] hands it to
+//! crossbeam-epoch to free once no thread can still see it.
+
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicPtr, Ordering};
+use std::sync::Mutex;
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+/// Live nodes of one structure and the most there have been at once. Each
+/// allocation and free is one shared atomic add, the same for every
+/// structure that counts itself, but a contended one: with counting
+/// [paused](Census::pause), timed runs only read a flag that never changes
+/// under them. Signed so that frees that land after a
+/// [`reset`](Census::reset) can't underflow it.
+pub struct Census {
+    live: AtomicIsize,
+    peak: AtomicIsize,
+    paused: AtomicBool,
+}
+
+impl Census {
+    pub const fn new() -> Self {
+        Census {
+            live: AtomicIsize::new(0),
+            peak: AtomicIsize::new(0),
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    pub fn allocated(&self) {
+        if self.paused() {
+            return;
+        }
+        let live = self.live.fetch_add(1, Ordering::Relaxed) + 1;
+        if live > self.peak.load(Ordering::Relaxed) {
+            self.peak.fetch_max(live, Ordering::Relaxed);
+        }
+    }
+
+    pub fn freed(&self) {
+        if self.paused() {
+            return;
+        }
+        self.live.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn live(&self) -> usize {
+        self.live.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    pub fn reset(&self) {
+        self.live.store(0, Ordering::Relaxed);
+        self.peak.store(0, Ordering::Relaxed);
+    }
+
+    /// Stops counting, or starts again. Nodes allocated or freed while
+    /// paused are never counted, so `live` only means something again
+    /// after a [`reset`](Census::reset).
+    pub fn pause(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+struct RawNode<T> {
+    data: T,
+    next: *mut RawNode<T>,
+}
+
+/// The nodes one thread has popped from a [`LeakyStack`]. Nothing is freed
+/// while the thread works; [`LeakyStack::retire`] hands them back to the
+/// stack, which frees them when it is dropped.
+pub struct Retired<T>(Vec<*mut RawNode<T>>);
+
+impl<T> Default for Retired<T> {
+    fn default() -> Self {
+        Retired(Vec::new())
+    }
+}
+
+// SAFETY: the pointers are only dereferenced by the stack that allocated
+// them, in `Drop`, once no thread uses it.
+unsafe impl<T: Send> Send for Retired<T> {}
+
+/// Treiber stack that never frees a node while it is in use. It needs no
+/// reclamation scheme and no ABA protection (an address can't come back
+/// while the stack lives), at the price of memory that only grows.
+pub struct LeakyStack<T> {
+    head: AtomicPtr<RawNode<T>>,
+    graveyard: Mutex<Vec<*mut RawNode<T>>>,
+    census: &'static Census,
+}
+
+// SAFETY: nodes move between threads only through the atomic head, and a
+// node's fields are never written once it has been published.
+unsafe impl<T: Send> Sync for LeakyStack<T> {}
+unsafe impl<T: Send> Send for LeakyStack<T> {}
+
+impl<T: Copy> LeakyStack<T> {
+    pub const NODE_BYTES: usize = mem::size_of::<RawNode<T>>();
+
+    pub fn new(census: &'static Census) -> Self {
+        LeakyStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            graveyard: Mutex::new(Vec::new()),
+            census,
+        }
+    }
+
+    pub fn push(&self, data: T) {
+        let node = Box::into_raw(Box::new(RawNode {
+            data,
+            next: ptr::null_mut(),
+        }));
+        self.census.allocated();
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: `node` isn't published yet, so this thread owns it.
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Pops the top value, adding its node to `retired`.
+    pub fn pop(&self, retired: &mut Retired<T>) -> Option<T> {
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // SAFETY: nodes are freed only in `Drop`, so even a node another
+            // thread has popped in the meantime is still readable.
+            let node = unsafe { head.as_ref() }?;
+            match self.head.compare_exchange_weak(
+                head,
+                node.next,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    retired.0.push(head);
+                    return Some(node.data);
+                }
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Takes back the nodes a thread popped, to be freed with the stack.
+    pub fn retire(&self, retired: Retired<T>) {
+        self.graveyard.lock().unwrap().extend(retired.0);
+    }
+}
+
+impl<T> Drop for LeakyStack<T> {
+    fn drop(&mut self) {
+        let mut current = *self.head.get_mut();
+        while !current.is_null() {
+            // SAFETY: `&mut self` means no other thread holds the stack, and
+            // every node still linked was made by `push`.
+            let node = unsafe { Box::from_raw(current) };
+            current = node.next;
+            self.census.freed();
+        }
+        for node in self.graveyard.get_mut().unwrap().drain(..) {
+            // SAFETY: as above; a popped node is unlinked, so was not freed
+            // by the walk.
+            drop(unsafe { Box::from_raw(node) });
+            self.census.freed();
+        }
+    }
+}
+
+struct EpochNode<T> {
+    data: T,
+    next: Atomic<EpochNode<T>>,
+}
+
+/// Treiber stack whose popped nodes are freed by crossbeam-epoch: every
+/// operation pins the thread, and a popped node is destroyed once every
+/// thread pinned at the time has unpinned.
+pub struct EpochStack<T> {
+    head: Atomic<EpochNode<T>>,
+    census: &'static Census,
+}
+
+impl<T: Copy + Send> EpochStack<T> {
+    pub const NODE_BYTES: usize = mem::size_of::<EpochNode<T>>();
+
+    pub fn new(census: &'static Census) -> Self {
+        EpochStack {
+            head: Atomic::null(),
+            census,
+        }
+    }
+
+    pub fn push(&self, data: T) {
+        let mut node = Owned::new(EpochNode {
+            data,
+            next: Atomic::null(),
+        });
+        self.census.allocated();
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Relaxed, &guard);
+        loop {
+            node.next.store(head, Ordering::Relaxed);
+            match self.head.compare_exchange_weak(
+                head,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+                &guard,
+            ) {
+                Ok(_) => return,
+                Err(e) => {
+                    head = e.current;
+                    node = e.new;
+                }
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Acquire, &guard);
+        loop {
+            // SAFETY: the guard keeps any node loaded under it alive.
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Ordering::Relaxed, &guard);
+            match self.head.compare_exchange_weak(
+                head,
+                next,
+                Ordering::Acquire,
+                Ordering::Acquire,
+                &guard,
+            ) {
+                Ok(_) => {
+                    let data = node.data;
+                    let census = self.census;
+                    // SAFETY: the node is unlinked, so no thread that pins
+                    // from now on can reach it, and it is freed only when
+                    // the ones pinned now have all unpinned.
+                    unsafe {
+                        guard.defer_unchecked(move || {
+                            drop(head.into_owned());
+                            census.freed();
+                        });
+                    }
+                    return Some(data);
+                }
+                Err(e) => head = e.current,
+            }
+        }
+    }
+}
+
+impl<T> Drop for EpochStack<T> {
+    fn drop(&mut self) {
+        // SAFETY: `&mut self` means no other thread holds the stack, so the
+        // nodes still linked can be freed without a guard.
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut current = self.head.load(Ordering::Relaxed, guard);
+            while !current.is_null() {
+                let node = current.into_owned();
+                current = node.next.load(Ordering::Relaxed, guard);
+                self.census.freed();
+            }
+        }
+    }
+}
+
+/// Runs deferred frees until none are left in this thread's or the global
+/// queue, or a bounded number of tries has passed. Meant to be called once
+/// every thread that used an [`EpochStack`] has exited. A paused census
+/// can't tell when they are done, so it gets every try.
+pub fn drain_epoch_garbage(census: &Census, expected_live: usize) {
+    for _ in 0..128 {
+        if !census.paused() && census.live() <= expected_live {
+            return;
+        }
+        epoch::pin().flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::thread;
+
+    // Under Miri every thread interleaving is simulated, so keep it small.
+    const PER_THREAD: usize = if cfg!(miri) { 20 } else { 10_000 };
+
+    #[test]
+    fn leaky_stack_is_last_in_first_out_and_frees_on_drop() {
+        static CENSUS: Census = Census::new();
+        let stack = LeakyStack::new(&CENSUS);
+        let mut retired = Retired::default();
+        assert_eq!(stack.pop(&mut retired), None);
+        for value in 0..3 {
+            stack.push(value);
+        }
+        assert_eq!(stack.pop(&mut retired), Some(2));
+        assert_eq!(stack.pop(&mut retired), Some(1));
+        stack.retire(retired);
+        assert_eq!((CENSUS.live(), CENSUS.peak()), (3, 3));
+        drop(stack);
+        assert_eq!(CENSUS.live(), 0);
+    }
+
+    #[test]
+    fn a_paused_census_counts_nothing() {
+        static CENSUS: Census = Census::new();
+        CENSUS.pause(true);
+        let stack = LeakyStack::new(&CENSUS);
+        let mut retired = Retired::default();
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(stack.pop(&mut retired), Some(2));
+        stack.retire(retired);
+        drop(stack);
+        assert_eq!((CENSUS.live(), CENSUS.peak()), (0, 0));
+
+        CENSUS.pause(false);
+        let stack = LeakyStack::new(&CENSUS);
+        stack.push(3);
+        assert_eq!((CENSUS.live(), CENSUS.peak()), (1, 1));
+        drop(stack);
+        assert_eq!(CENSUS.live(), 0);
+    }
+
+    #[test]
+    fn leaky_stack_loses_nothing_across_threads() {
+        static CENSUS: Census = Census::new();
+        let stack = LeakyStack::new(&CENSUS);
+        let popped: usize = thread::scope(|scope| {
+            let workers: Vec<_> = (0..2)
+                .map(|worker| {
+                    let stack = &stack;
+                    scope.spawn(move || {
+                        let mut retired = Retired::default();
+                        let mut sum = 0;
+                        for i in 0..PER_THREAD {
+                            stack.push(worker * PER_THREAD + i);
+                            sum += stack.pop(&mut retired).unwrap();
+                        }
+                        stack.retire(retired);
+                        sum
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).sum()
+        });
+        // Every value pushed was popped exactly once, by one thread or the
+        // other.
+        let pushed = 2 * PER_THREAD;
+        assert_eq!(popped, pushed * (pushed - 1) / 2);
+        drop(stack);
+        assert_eq!(CENSUS.live(), 0);
+    }
+
+    // crossbeam-epoch's own pinning breaks Stacked Borrows, and its global
+    // collector is never dropped, so Miri reports both against it; the
+    // census below still checks that every node of ours is freed.
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "run with MIRIFLAGS=\"-Zmiri-tree-borrows -Zmiri-ignore-leaks\" and --include-ignored"
+    )]
+    fn epoch_stack_loses_nothing_and_frees_everything() {
+        static CENSUS: Census = Census::new();
+        let stack = EpochStack::new(&CENSUS);
+        assert_eq!(stack.pop(), None);
+        let popped: usize = thread::scope(|scope| {
+            let workers: Vec<_> = (0..2)
+                .map(|worker| {
+                    let stack = &stack;
+                    scope.spawn(move || {
+                        let mut sum = 0;
+                        for i in 0..PER_THREAD {
+                            stack.push(worker * PER_THREAD + i);
+                            sum += stack.pop().unwrap();
+                        }
+                        sum
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).sum()
+        });
+        let pushed = 2 * PER_THREAD;
+        assert_eq!(popped, pushed * (pushed - 1) / 2);
+        stack.push(7);
+        drop(stack);
+        drain_epoch_garbage(&CENSUS, 0);
+        assert_eq!(CENSUS.live(), 0);
+    }
+
+    /// Pushes (`Some`) and pops (`None`) from one thread.
+    fn ops() -> impl Strategy<Value = Vec<Option<u8>>> {
+        prop::collection::vec(prop::option::of(any::<u8>()), 0..60)
+    }
+
+    proptest! {
+        #![proptest_config(crate::testing::proptest_config())]
+
+        #[test]
+        fn leaky_stack_matches_a_vec(ops in ops()) {
+            static CENSUS: Census = Census::new();
+            CENSUS.reset();
+            let stack = LeakyStack::new(&CENSUS);
+            let mut retired = Retired::default();
+            let mut model = Vec::new();
+            for op in ops {
+                match op {
+                    Some(value) => {
+                        stack.push(value);
+                        model.push(value);
+                    }
+                    None => prop_assert_eq!(stack.pop(&mut retired), model.pop()),
+                }
+            }
+            while let Some(value) = model.pop() {
+                prop_assert_eq!(stack.pop(&mut retired), Some(value));
+            }
+            prop_assert_eq!(stack.pop(&mut retired), None);
+            stack.retire(retired);
+            drop(stack);
+            prop_assert_eq!(CENSUS.live(), 0);
+        }
+
+        #[test]
+        #[cfg_attr(
+            miri,
+            ignore = "run with MIRIFLAGS=\"-Zmiri-tree-borrows -Zmiri-ignore-leaks\" and --include-ignored"
+        )]
+        fn epoch_stack_matches_a_vec(ops in ops()) {
+            static CENSUS: Census = Census::new();
+            let stack = EpochStack::new(&CENSUS);
+            let mut model = Vec::new();
+            for op in ops {
+                match op {
+                    Some(value) => {
+                        stack.push(value);
+                        model.push(value);
+                    }
+                    None => prop_assert_eq!(stack.pop(), model.pop()),
+                }
+            }
+            while let Some(value) = model.pop() {
+                prop_assert_eq!(stack.pop(), Some(value));
+            }
+            prop_assert_eq!(stack.pop(), None);
+            drop(stack);
+            drain_epoch_garbage(&CENSUS, 0);
+            prop_assert_eq!(CENSUS.live(), 0);
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::clock;
+use crate::histogram::{self, Latencies};
+use crate::rng::Rng;
+use crate::sync::{SpinLock, TicketLock};
+use crate::LinkedList;
+
+/// A list behind one kind of lock, as every thread of a run shares it.
+trait Guarded: Sync {
+    fn new(list: LinkedList<usize>) -> Self;
+    fn with<R>(&self, f: impl FnOnce(&mut LinkedList<usize>) -> R) -> R;
+}
+
+impl Guarded for Mutex<LinkedList<usize>> {
+    fn new(list: LinkedList<usize>) -> Self {
+        Mutex::new(list)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut LinkedList<usize>) -> R) -> R {
+        f(&mut self.lock().unwrap())
+    }
+}
+
+impl Guarded for parking_lot::Mutex<LinkedList<usize>> {
+    fn new(list: LinkedList<usize>) -> Self {
+        parking_lot::Mutex::new(list)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut LinkedList<usize>) -> R) -> R {
+        f(&mut self.lock())
+    }
+}
+
+impl Guarded for SpinLock<LinkedList<usize>> {
+    fn new(list: LinkedList<usize>) -> Self {
+        SpinLock::new(list)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut LinkedList<usize>) -> R) -> R {
+        f(&mut self.lock())
+    }
+}
+
+impl Guarded for TicketLock<LinkedList<usize>> {
+    fn new(list: LinkedList<usize>) -> Self {
+        TicketLock::new(list)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut LinkedList<usize>) -> R) -> R {
+        f(&mut self.lock())
+    }
+}
+
+/// What every thread of a run does: `ops` operations on a shared list of
+/// `initial` nodes, each a push, a pop or a read of the first `walk` nodes
+/// with equal odds, taking the lock once per operation.
+#[derive(Clone, Copy)]
+struct Workload {
+    threads: usize,
+    ops: usize,
+    initial: usize,
+    walk: usize,
+}
+
+/// One run of a workload under one lock.
+struct Run {
+    seconds: f64,
+    /// Cycles from asking for the lock to releasing it, one per operation.
+    latencies: Latencies,
+    /// Each thread's time from its first operation to its last, in cycles.
+    spans: Vec<u64>,
+}
+
+type Measure = fn(Workload) -> io::Result<Run>;
+
+const LOCKS: [(&str, Measure); 4] = [
+    ("std Mutex", measure::<Mutex<LinkedList<usize>>>),
+    (
+        "parking_lot",
+        measure::<parking_lot::Mutex<LinkedList<usize>>>,
+    ),
+    ("spinlock", measure::<SpinLock<LinkedList<usize>>>),
+    ("ticket", measure::<TicketLock<LinkedList<usize>>>),
+];
+
+/// `locks [--threads <a,b,...>] [--ops <per thread>] [--initial <n>]
+/// [--walk <nodes>] [--iterations <k>] [--hdr <file>]`: runs the same
+/// concurrent list workload under `std::sync::Mutex`, `parking_lot::Mutex`
+/// and the crate's spinlock and ticket lock, and reports throughput and the
+/// per-operation latency tail for every thread count. `--hdr` also writes
+/// every row's latency histogram to an HdrHistogram interval log.
+pub fn run(args: &[String]) {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    // Up to twice the cores, so the table shows what each lock does once
+    // waiters outnumber the CPUs and a holder can be preempted.
+    let mut threads: Vec<usize> = std::iter::successors(Some(1), |&t| Some(t * 2))
+        .take_while(|&t| t <= (2 * cores).max(4))
+        .collect();
+    let mut ops: usize = 100_000;
+    let mut initial: usize = 1024;
+    let mut walk: usize = 16;
+    let mut iterations: usize = 3;
+    let mut hdr_path: Option<String> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--threads", Some(v)) => {
+                let parsed: Option<Vec<usize>> =
+                    v.split(',').map(|t| t.trim().parse().ok()).collect();
+                match parsed {
+                    Some(list) if !list.is_empty() && !list.contains(&0) => threads = list,
+                    _ => {
+                        eprintln!("Error: bad --threads '{}' (expected e.g. 1,2,4,8)", v);
+                        return;
+                    }
+                }
+            }
+            ("--ops", Some(v)) => ops = v.parse().unwrap_or(ops),
+            ("--initial", Some(v)) => initial = v.parse().unwrap_or(initial),
+            ("--walk", Some(v)) => walk = v.parse().unwrap_or(walk),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--hdr", Some(v)) => hdr_path = Some(v.clone()),
+            _ => {
+                eprintln!("Error: unknown or incomplete locks option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let ops = ops.max(1);
+    let iterations = iterations.max(1);
+
+    println!("--- Lock-Guarded List ---");
+    println!(
+        "{} ops per thread on a shared list of {} nodes: 1/3 push, 1/3 pop, 1/3 read of the first {} nodes; {} iterations, available parallelism {}",
+        ops, initial, walk, iterations, cores
+    );
+    println!(
+        "{:>7} {:<12} {:>10} {} {:>8}",
+        "Threads",
+        "Lock",
+        "Mops/s",
+        Latencies::columns(),
+        "Spread"
+    );
+    let mut histograms = Vec::new();
+    for &count in &threads {
+        let workload = Workload {
+            threads: count,
+            ops,
+            initial,
+            walk,
+        };
+        // Locks take turns within each iteration, so drift over the run
+        // hits all of them alike.
+        let mut runs: Vec<Vec<Run>> = LOCKS.iter().map(|_| Vec::new()).collect();
+        for _ in 0..iterations {
+            for ((name, measure), runs) in LOCKS.iter().zip(&mut runs) {
+                match measure(workload) {
+                    Ok(run) => runs.push(run),
+                    Err(e) => {
+                        eprintln!(
+                            "Error: could not run {} with {} threads: {}",
+                            name, count, e
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+        for ((name, _), runs) in LOCKS.iter().zip(&runs) {
+            let (latencies, length) = row(count, name, ops, runs);
+            histograms.push((format!("{}/{}t", name, count), latencies, length));
+        }
+    }
+    println!(
+        "Latency: cycles per operation including the wait for the lock, pooled over the iterations"
+    );
+    println!("Spread: slowest thread's time over the fastest's, median over the iterations (1.00 = fair)");
+
+    if let Some(path) = hdr_path {
+        let histograms: Vec<(String, &Latencies, Duration)> = histograms
+            .iter()
+            .map(|(name, latencies, length)| (name.clone(), latencies, *length))
+            .collect();
+        match histogram::export(&path, &histograms) {
+            Ok(()) => eprintln!("Latency histograms written to {}", path),
+            Err(e) => eprintln!("Error: could not write histograms to {}: {}", path, e),
+        }
+    }
+}
+
+/// Prints the row of one lock at one thread count. Returns the latencies
+/// of all its runs and their total wall time.
+fn row(threads: usize, name: &str, ops: usize, runs: &[Run]) -> (Latencies, Duration) {
+    let mut throughput: Vec<f64> = runs
+        .iter()
+        .map(|r| (threads * ops) as f64 / r.seconds.max(f64::MIN_POSITIVE) / 1e6)
+        .collect();
+    let mut spread: Vec<f64> = runs
+        .iter()
+        .map(|r| {
+            let slowest = r.spans.iter().copied().max().unwrap_or(0);
+            let fastest = r.spans.iter().copied().min().unwrap_or(0);
+            slowest as f64 / fastest.max(1) as f64
+        })
+        .collect();
+    let mut latencies = Latencies::new();
+    for run in runs {
+        latencies.add(&run.latencies);
+    }
+    throughput.sort_by(f64::total_cmp);
+    spread.sort_by(f64::total_cmp);
+    println!(
+        "{:>7} {:<12} {:>10.2} {} {:>7.2}x",
+        threads,
+        name,
+        throughput[throughput.len() / 2],
+        latencies.cells(),
+        spread[spread.len() / 2]
+    );
+    let length = Duration::from_secs_f64(runs.iter().map(|r| r.seconds).sum());
+    (latencies, length)
+}
+
+/// Runs `workload` once with the list behind an `L`.
+fn measure<L: Guarded>(workload: Workload) -> io::Result<Run> {
+    let list = L::new(crate::bench::build(workload.initial));
+    let (results, seconds) = race(workload.threads, "locks", |index| {
+        worker(&list, workload, index as u64 + 1)
+    })?;
+    let mut latencies = Latencies::new();
+    let mut spans = Vec::with_capacity(workload.threads);
+    for (thread_latencies, span) in results {
+        latencies.add(&thread_latencies);
+        spans.push(span);
+    }
+    Ok(Run {
+        seconds,
+        latencies,
+        spans,
+    })
+}
+
+/// Runs `work(index)` on `threads` threads named `<name>-<index>` and
+/// returns their results in index order with the wall time in seconds. The
+/// threads are all spawned before any starts, and the wall clock covers the
+/// first start to the last finish.
+pub fn race<R: Send>(
+    threads: usize,
+    name: &str,
+    work: impl Fn(usize) -> R + Sync,
+) -> io::Result<(Vec<R>, f64)> {
+    let go = AtomicBool::new(false);
+    let (work, go) = (&work, &go);
+    thread::scope(|scope| {
+        let spawned: Vec<io::Result<_>> = (0..threads)
+            .map(|index| {
+                thread::Builder::new()
+                    .name(format!("{}-{}", name, index))
+                    .spawn_scoped(scope, move || {
+                        while !go.load(Ordering::Acquire) {
+                            thread::yield_now();
+                        }
+                        work(index)
+                    })
+            })
+            .collect();
+        // Released even when a spawn failed, so the threads that did start
+        // can finish and the scope can end.
+        let start = clock::start();
+        go.store(true, Ordering::Release);
+        let mut results = Vec::with_capacity(threads);
+        let mut failed = None;
+        for handle in spawned {
+            match handle {
+                Ok(handle) => results.push(handle.join().expect("worker thread panicked")),
+                Err(e) => failed = Some(e),
+            }
+        }
+        let seconds = clock::stop(&start).time.as_secs_f64();
+        match failed {
+            Some(e) => Err(e),
+            None => Ok((results, seconds)),
+        }
+    })
+}
+
+/// One thread's share of a run: its per-operation latencies and the span
+/// from its first operation to its last.
+fn worker<L: Guarded>(list: &L, workload: Workload, seed: u64) -> (Latencies, u64) {
+    let mut rng = Rng::new(seed);
+    let mut latencies = Latencies::new();
+    let first = clock::timestamp();
+    for _ in 0..workload.ops {
+        let pick = rng.below(3);
+        let value = rng.next_u64() as usize;
+        let before = clock::timestamp();
+        let result = list.with(|list| match pick {
+            0 => {
+                list.push(value);
+                0
+            }
+            1 => list.pop().unwrap_or(0),
+            _ => {
+                let mut sum = 0usize;
+                let mut current = &list.head;
+                for _ in 0..workload.walk {
+                    let Some(node) = current else { break };
+                    sum = sum.wrapping_add(node.data);
+                    current = &node.next;
+                }
+                sum
+            }
+        });
+        latencies.record(clock::timestamp().saturating_sub(before));
+        std::hint::black_box(result);
+    }
+    (latencies, clock::timestamp().saturating_sub(first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// One operation of the workload, as `worker` picks them.
+    #[derive(Clone, Debug)]
+    enum Op {
+        Push(usize),
+        Pop,
+        /// Sums the first this many values.
+        Walk(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            any::<usize>().prop_map(Op::Push),
+            Just(Op::Pop),
+            (0..8usize).prop_map(Op::Walk),
+        ]
+    }
+
+    /// Applies `ops` through `L` to a list that starts as `initial`, and to
+    /// the `Vec` that models it, front first.
+    fn check<L: Guarded>(initial: &[usize], ops: &[Op]) -> Result<(), TestCaseError> {
+        let list = L::new(LinkedList::from_vec(initial.to_vec()));
+        let mut model = initial.to_vec();
+        for op in ops {
+            match *op {
+                Op::Push(value) => {
+                    list.with(|list| list.push(value));
+                    model.insert(0, value);
+                }
+                Op::Pop => {
+                    let expected = (!model.is_empty()).then(|| model.remove(0));
+                    prop_assert_eq!(list.with(|list| list.pop()), expected);
+                }
+                Op::Walk(walk) => {
+                    let sum = list.with(|list| {
+                        let mut sum = 0usize;
+                        let mut current = &list.head;
+                        for _ in 0..walk {
+                            let Some(node) = current else { break };
+                            sum = sum.wrapping_add(node.data);
+                            current = &node.next;
+                        }
+                        sum
+                    });
+                    let expected = model
+                        .iter()
+                        .take(walk)
+                        .fold(0usize, |sum, &v| sum.wrapping_add(v));
+                    prop_assert_eq!(sum, expected);
+                }
+            }
+            prop_assert_eq!(list.with(|list| list.count), model.len());
+        }
+        prop_assert_eq!(list.with(|list| std::mem::take(list).into_vec()), model);
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(crate::testing::proptest_config())]
+
+        #[test]
+        fn every_lock_guards_a_list_that_matches_a_vec(
+            initial in prop::collection::vec(any::<usize>(), 0..8),
+            ops in prop::collection::vec(op(), 0..40),
+        ) {
+            check::<Mutex<LinkedList<usize>>>(&initial, &ops)?;
+            check::<parking_lot::Mutex<LinkedList<usize>>>(&initial, &ops)?;
+            check::<SpinLock<LinkedList<usize>>>(&initial, &ops)?;
+            check::<TicketLock<LinkedList<usize>>>(&initial, &ops)?;
+        }
+    }
+
+    #[test]
+    fn the_hand_rolled_locks_lose_no_pushes_across_threads() {
+        // Under Miri every thread interleaving is simulated, so keep it small.
+        let per_thread = if cfg!(miri) { 20 } else { 10_000 };
+        fn pushes<L: Guarded>(per_thread: usize) -> usize {
+            let list = L::new(LinkedList::new());
+            race(2, "test", |index| {
+                for i in 0..per_thread {
+                    list.with(|list| list.push(index * per_thread + i));
+                }
+            })
+            .unwrap();
+            list.with(|list| list.count)
+        }
+        assert_eq!(
+            pushes::<SpinLock<LinkedList<usize>>>(per_thread),
+            2 * per_thread
+        );
+        assert_eq!(
+            pushes::<TicketLock<LinkedList<usize>>>(per_thread),
+            2 * per_thread
+        );
+    }
+}
@@ -1,106 +1,462 @@
-use std::time::Instant;
-use std::mem;
+mod ab;
+mod affinity;
+mod arena;
+mod archive;
+mod bench;
+mod branch;
+mod cache;
+mod chase;
+mod clock;
+mod cold;
+mod counters;
+mod cputime;
+mod data;
+mod diff;
+mod energy;
+mod environment;
+mod ffi;
+mod filter;
+mod find;
+mod generations;
+mod histogram;
+mod isolate;
+mod list;
+mod lockfree;
+mod locks;
+mod matrix;
+mod merge;
+mod metadata;
+mod mlock;
+mod mlp;
+mod placement;
+#[cfg(not(target_arch = "wasm32"))]
+mod plot;
+mod pool;
+mod position;
+mod prefetch;
+mod profile;
+mod reclaim;
+mod report;
+mod results;
+mod rng;
+mod segments;
+mod sorted;
+mod simd;
+mod soa;
+mod stats;
+#[cfg(not(target_arch = "wasm32"))]
+mod store;
+mod stream;
+mod stride;
+mod sync;
+#[cfg(test)]
+mod testing;
+mod thermal;
+mod tlb;
+#[cfg(not(target_arch = "wasm32"))]
+mod tui;
+#[cfg(target_arch = "wasm32")]
+mod unavailable;
+#[cfg(target_arch = "wasm32")]
+use unavailable::{plot, store, tui};
+mod sweep;
+#[cfg(target_os = "macos")]
+mod sysctl;
+mod workload;
 
-// These are specific to x86_64 processors
-#[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::{_rdtsc, _mm_lfence};
+use list::{Link, LinkedList, Node};
 
-struct Node<T> {
-    data: T,
-    next: Link<T>,
-}
+impl<T> LinkedList<T> {
+    /// Performs traversal while measuring both wall-time and CPU cycles
+    fn benchmark_traversal(&self) -> (usize, clock::Reading) {
+        let (visited_count, reading) = measure(|| {
+            let mut current = &self.head;
+            let mut visited_count = 0;
 
-type Link<T> = Option<Box<Node<T>>>;
+            while let Some(node) = current {
+                visited_count += 1;
+                current = &node.next;
+            }
+            visited_count
+        });
 
-struct LinkedList<T> {
-    head: Link<T>,
-    count: usize,
+        (visited_count, reading)
+    }
 }
 
-impl<T> LinkedList<T> {
-    fn new() -> Self {
-        LinkedList { head: None, count: 0 }
+impl LinkedList<usize> {
+    /// Same walk as `benchmark_traversal`, but every payload is folded into a
+    /// position-weighted checksum so skipped or reordered nodes show up.
+    fn benchmark_checked_traversal(&self) -> (usize, u64, clock::Reading) {
+        let ((visited_count, checksum), reading) = measure(|| {
+            let mut current = &self.head;
+            let mut visited_count = 0;
+            let mut checksum: u64 = 0;
+
+            while let Some(node) = current {
+                visited_count += 1;
+                checksum = checksum.wrapping_add((node.data as u64).wrapping_mul(visited_count as u64));
+                current = &node.next;
+            }
+            (visited_count, checksum)
+        });
+
+        (visited_count, checksum, reading)
     }
 
-    fn push(&mut self, data: T) {
-        let new_node = Box::new(Node {
-            data,
-            next: mem::replace(&mut self.head, None),
+    /// Same walk as `benchmark_traversal`, but increments every payload, so
+    /// each node's line has to be owned (RFO) and is written back dirty.
+    fn benchmark_write_traversal(&mut self) -> (usize, clock::Reading) {
+        let (visited_count, reading) = measure(|| {
+            let mut current = &mut self.head;
+            let mut visited_count = 0;
+
+            while let Some(node) = current {
+                visited_count += 1;
+                node.data = node.data.wrapping_add(1);
+                current = &mut node.next;
+            }
+            visited_count
         });
-        self.head = Some(new_node);
-        self.count += 1;
+
+        (visited_count, reading)
     }
+}
 
-    /// Performs traversal while measuring both wall-time and CPU cycles
-    fn benchmark_traversal(&self) -> (usize, std::time::Duration, u64) {
-        let start_time = Instant::now();
-        let start_cycles: u64;
-        let end_cycles: u64;
-
-        let mut current = &self.head;
-        let mut visited_count = 0;
-
-        unsafe {
-            // Serializing fence: ensures all previous instructions 
-            // are finished before the first rdtsc.
-            _mm_lfence(); 
-            start_cycles = _rdtsc();
-        }
+/// Runs `f` between two serialized cycle counter reads and returns its result
+/// with the elapsed wall-time and CPU cycles.
+fn measure<R>(f: impl FnOnce() -> R) -> (R, clock::Reading) {
+    let start = clock::start();
+    let result = f();
+    let reading = clock::stop(&start);
 
-        while let Some(node) = current {
-            visited_count += 1;
-            current = &node.next;
-        }
+    (result, reading)
+}
 
-        unsafe {
-            // Serializing fence: ensures the loop is 100% finished
-            // before we read the final cycle count.
-            _mm_lfence();
-            end_cycles = _rdtsc();
-        }
+/// Prints the human-readable report for a single-size run, with the write
+/// traversal of the same list when one was timed.
+fn print_results(result: &bench::BenchResult, write: Option<&bench::BenchResult>) {
+    println!("--- {} Hardware Benchmark ---", std::env::consts::ARCH);
+    println!("List Size: {}", result.nodes);
+    // Half the size on 32-bit targets, where both fields are 4-byte words.
+    println!("Node Size: {} bytes ({}-bit pointers)", bench::NODE_BYTES, usize::BITS);
+
+    let bench::Sample { visited, time, cycles, anomaly } = result.samples[0];
+
+    // --- Statistics ---
+   let time_ns = time.as_nanos() as f64;
+   let cycles_f = cycles as f64;
+
+    println!("\n[Results{}]", if result.samples.len() > 1 { " (first iteration)" } else { "" });
+    println!("Total Nodes Visited:   {:?}", visited);
+    println!("Total Time:   {:?}", time);
+    println!("Total Cycles: {}", cycles);
+    if let Some(anomaly) = anomaly {
+        println!("Flagged:      {} (cycle count not trustworthy, excluded from statistics)", anomaly);
+    }
+    if visited > 0 {
+        println!("\n[Efficiency Metrics]");
+        println!("Time per Node:   {:.2} ns", time_ns / visited as f64);
+        println!("Cycles per Node: {:.2} ticks", cycles_f / visited as f64);
+        
+        // This calculates the effective frequency during the test
+        let ghz = cycles_f / time_ns;
+        let band = clock::frequency_band();
+        println!("Effective Speed: {:.2} GHz (plausible {:.2}-{:.2} GHz, {}; checked from {:?})", ghz, band.low_ghz, band.high_ghz, band.source, band.min_checked);
+
+        let sample = &result.samples[0];
+        println!("Throughput:      {:.2} Mnodes/s", sample.nodes_per_second() / 1e6);
+        println!("Bandwidth:       {:.2} GB/s ({} bytes of node data per node)", sample.bytes_per_second() / 1e9, bench::NODE_BYTES);
+    }
 
-        let elapsed_time = start_time.elapsed();
-        let elapsed_cycles = end_cycles - start_cycles;
+    let cycles = result.cycles_per_node();
+    cache::print_model::<usize>(result.nodes, &result.layout, (cycles.count > 0).then_some(cycles.median));
 
-        (visited_count, elapsed_time, elapsed_cycles)
+    if result.samples.len() > 1 {
+        let ns = result.ns_per_node();
+        let cycles = result.cycles_per_node();
+        println!("\n[Statistics over {} iterations]", cycles.count);
+        let flagged = result.flagged();
+        if flagged > 0 {
+            let (migrated, backwards, frequency) = result.flagged_by_kind();
+            println!("Flagged samples: {} of {} excluded ({} CPU migration, {} counter backwards, {} implausible frequency)", flagged, result.samples.len(), migrated, backwards, frequency);
+        }
+        if cycles.count == 0 {
+            println!("No statistics: every sample was flagged");
+        } else {
+            println!("                 {:>10} {:>10} {:>10} {:>10}", "min", "median", "mean", "stddev");
+            println!("Time per Node:   {:>10.2} {:>10.2} {:>10.2} {:>10.2} ns", ns.min, ns.median, ns.mean, ns.stddev);
+            println!("Cycles per Node: {:>10.2} {:>10.2} {:>10.2} {:>10.2} ticks", cycles.min, cycles.median, cycles.mean, cycles.stddev);
+            let rate = result.nodes_per_second();
+            let bandwidth = result.bytes_per_second();
+            println!("Throughput:      {:>10.2} {:>10.2} {:>10.2} {:>10.2} Mnodes/s", rate.min / 1e6, rate.median / 1e6, rate.mean / 1e6, rate.stddev / 1e6);
+            println!("Bandwidth:       {:>10.2} {:>10.2} {:>10.2} {:>10.2} GB/s", bandwidth.min / 1e9, bandwidth.median / 1e9, bandwidth.mean / 1e9, bandwidth.stddev / 1e9);
+        }
+    }
+
+    if let Some(write) = write {
+        let (read_cycles, write_cycles) = (result.cycles_per_node(), write.cycles_per_node());
+        println!("\n[Write Traversal (same layout)]");
+        println!("                 {:>10} {:>10} {:>10} {:>10}", "min", "median", "mean", "stddev");
+        println!("Cycles per Node: {:>10} {:>10} {:>10} {:>10} ticks", bench::stat(&write_cycles, write_cycles.min, 2), bench::stat(&write_cycles, write_cycles.median, 2), bench::stat(&write_cycles, write_cycles.mean, 2), bench::stat(&write_cycles, write_cycles.stddev, 2));
+        println!("Time per Node:   {:>10} ns (median)", bench::stat(&write_cycles, write.ns_per_node().median, 2));
+        println!("Write / Read:    {:>11} median cycles per node", bench::ratio(&write_cycles, &read_cycles, 2));
+        if write.flagged() > 0 {
+            println!("Flagged samples: {} of {} excluded", write.flagged(), write.samples.len());
+        }
     }
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    // Global option, accepted anywhere on the command line.
+    if let Some(position) = args.iter().position(|a| a == "--pin") {
+        args.remove(position);
+        for note in affinity::pin_current_thread() {
+            eprintln!("[pin] {}", note);
+        }
+    }
     if args.len() < 2 {
-        println!("Usage: cargo run -- <num_nodes>");
+        println!("Usage: cargo run -- <num_nodes> [--iterations <k> | --measure-for <5s>] [--format <text|html|markdown|ndjson|github-benchmark>] [--verify] [--write] [--energy] [--cold] [--mlock]");
+        println!("                    [--store <results.db>] [--save <results.tsv>]");
+        println!("                    [--profile-phase build|traverse] [--perf-record <perf.data>]");
+        println!("       cargo run -- sweep [--min <nodes>] [--max <nodes>] [--iterations <k> | --measure-for <per size>] [--format <text|html|markdown|ndjson|github-benchmark>] [--verify] [--write] [--cold] [--mlock] [--isolate]");
+        println!("                    [--plot <file.svg|file.png>] [--store <results.db>] [--save <results.tsv>]");
+        println!("       cargo run -- history <results.db> [--benchmark <name>] [--nodes <n>]");
+        println!("       cargo run -- diff <a.tsv> <b.tsv>");
+        println!("       cargo run -- ab <binary-a> <binary-b> [--rounds <r>] [-- <sweep options>]");
+        println!("       cargo run -- chase [--min <bytes>] [--max <bytes>] [--steps <k>] [--iterations <n>]");
+        println!("       cargo run -- branch [--nodes <n>] [--iterations <k>]");
+        println!("       cargo run -- simd [--min <bytes>] [--max <bytes>] [--iterations <n>]");
+        println!("       cargo run -- stream [--min <bytes>] [--max <bytes>] [--iterations <n>]");
+        println!("       cargo run -- tlb [--nodes <n>] [--iterations <k>] [--huge]");
+        println!("       cargo run -- churn [--nodes <n>] [--ops <k>] [--iterations <n>]");
+        println!("       cargo run -- mlp [--nodes <per list>] [--iterations <k>]");
+        println!("       cargo run -- prefetch [--nodes <n>] [--iterations <k>]");
+        println!("       cargo run -- soa [--nodes <n>] [--iterations <k>]");
+        println!("       cargo run -- workload [--structure list|vec|deque|all] [--initial <n>] [--ops <k>] [--mix <push,pop,get,insert,remove>]");
+        println!("                    [--seed <s>] [--record <trace.tsv>] [--replay <trace.tsv>] [--iterations <n>] [--isolate] [--hdr <latency.hlog>]");
+        println!("       cargo run -- tui [--nodes <a,b,...>] [--iterations <k>]");
+        println!("       cargo run -- segments [--nodes <n>] [--every <k>] [--layout sequential|shuffled|fragmented] [--iterations <i>]");
+        println!("       cargo run --features c-list -- ffi [--nodes <n>] [--iterations <k>]");
+        println!("       cargo run -- sorted [--keys <a,b,...>] [--iterations <k>] [--seed <s>]");
+        println!("       cargo run -- filter [--nodes <n>] [--keep <percent>] [--run <length>] [--iterations <k>]");
+        println!("       cargo run -- merge [--nodes <a,b,...>] [--iterations <k>]");
+        println!("       cargo run -- position [--nodes <n>] [--ops <k>] [--regions <r>]");
+        println!("       cargo run -- placement [--nodes <n>] [--trials <k>] [--iterations <i>] [--max-offset <bytes>] [--seed <s>]");
+        println!("                    [--offsets <a,b,...>] [--no-aslr]");
+        println!("       cargo run -- payload --data <file.csv|file.json|file.ndjson> [--key <column>] [--searches <k>] [--iterations <n>]");
+        println!("       cargo run -- locks [--threads <a,b,...>] [--ops <per thread>] [--initial <n>] [--walk <nodes>] [--iterations <k>] [--hdr <latency.hlog>]");
+        println!("       cargo run -- reclaim [--threads <a,b,...>] [--ops <per thread>] [--initial <n>] [--iterations <k>]");
+        println!("       cargo run -- generations [--nodes <n>] [--generations <g>] [--iterations <i>] [--survivors <percent>] [--seed <s>]");
+        println!("       cargo run -- find [--nodes <n>] [--distributions <uniform,zipf:s,...>] [--searches <k>] [--iterations <i>] [--seed <s>]");
+        println!("       cargo run -- matrix [--variant <name=rustflags>]... [--rounds <r>] [--manifest <Cargo.toml>] [-- <sweep options>]");
+        println!("       cargo run -- export --out <bundle> [--title <t>] <results.tsv>...");
+        println!("       cargo run -- render <bundle> [--format <text|html|markdown|ndjson|github-benchmark>] [--title <t>] [--plot <file.svg|file.png>]");
+        println!("       cargo run -- stride [--bytes <buffer>] [--strides <a,b,...>] [--accesses <k>] [--iterations <n>]");
+        println!("Global options: --pin (pin to the current CPU and raise priority, best effort)");
         return;
     }
 
+    match args[1].as_str() {
+        "sweep" => return sweep::run(&args[2..]),
+        "history" => return store::history(&args[2..]),
+        "ab" => return ab::run(&args[2..]),
+        "diff" => return diff::run(&args[2..]),
+        "chase" => return chase::run(&args[2..]),
+        "stride" => return stride::run(&args[2..]),
+        "branch" => return branch::run(&args[2..]),
+        "simd" => return simd::run(&args[2..]),
+        "stream" => return stream::run(&args[2..]),
+        "tlb" => return tlb::run(&args[2..]),
+        "churn" => return pool::run(&args[2..]),
+        "mlp" => return mlp::run(&args[2..]),
+        "prefetch" => return prefetch::run(&args[2..]),
+        "soa" => return soa::run(&args[2..]),
+        "workload" => return workload::run(&args[2..]),
+        "tui" => return tui::run(&args[2..]),
+        "segments" => return segments::run(&args[2..]),
+        "ffi" => return ffi::run(&args[2..]),
+        "sorted" => return sorted::run(&args[2..]),
+        "filter" => return filter::run(&args[2..]),
+        "merge" => return merge::run(&args[2..]),
+        "position" => return position::run(&args[2..]),
+        "placement" => return placement::run(&args[2..]),
+        "payload" => return data::run(&args[2..]),
+        "locks" => return locks::run(&args[2..]),
+        "reclaim" => return reclaim::run(&args[2..]),
+        "generations" => return generations::run(&args[2..]),
+        "find" => return find::run(&args[2..]),
+        "matrix" => return matrix::run(&args[2..]),
+        "export" => return archive::export(&args[2..]),
+        "render" => return archive::render(&args[2..]),
+        _ => {}
+    }
+
     let num_nodes: usize = args[1].parse().unwrap_or(100_000);
 
+    let mut iterations: usize = 1;
+    let mut budget = None;
+    let mut profile_phase: Option<String> = None;
+    let mut perf_output: Option<String> = None;
+    let mut store_path: Option<String> = None;
+    let mut save_path: Option<String> = None;
+    let mut format = report::Format::Text;
+    let mut verify = false;
+    let mut write = false;
+    let mut energy = false;
+    let mut cold = false;
+    let mut mlock = false;
+    let mut options = args[2..].iter();
+    while let Some(arg) = options.next() {
+        if arg == "--verify" {
+            verify = true;
+            continue;
+        }
+        if arg == "--write" {
+            write = true;
+            continue;
+        }
+        if arg == "--energy" {
+            energy = true;
+            continue;
+        }
+        if arg == "--cold" {
+            cold = true;
+            continue;
+        }
+        if arg == "--mlock" {
+            mlock = true;
+            continue;
+        }
+        match (arg.as_str(), options.next()) {
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--measure-for", Some(v)) => match bench::parse_budget(v) {
+                Some(b) => budget = Some(b),
+                None => {
+                    eprintln!("Error: bad --measure-for '{}' (expected e.g. 500ms, 5s or 1m)", v);
+                    return;
+                }
+            },
+            ("--profile-phase", Some(phase)) if phase == "build" || phase == "traverse" => {
+                profile_phase = Some(phase.clone())
+            }
+            ("--perf-record", Some(output)) => perf_output = Some(output.clone()),
+            ("--store", Some(path)) => store_path = Some(path.clone()),
+            ("--save", Some(path)) => save_path = Some(path.clone()),
+            ("--format", Some(v)) => match report::Format::parse(v) {
+                Some(f) => format = f,
+                None => {
+                    eprintln!("Error: unknown format '{}' (expected text, html, markdown, ndjson or github-benchmark)", v);
+                    return;
+                }
+            },
+            _ => {
+                eprintln!("Error: unknown or incomplete option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    if let Some(output) = perf_output {
+        profile::record(&output, &args[1..]);
+    }
+    if cold {
+        eprintln!("[cold] {}", cold::enable());
+    }
+    if mlock {
+        eprintln!("[mlock] {}", mlock::enable());
+    }
+    let mut marker = profile::PhaseMarker::new(profile_phase);
+    let environment = environment::snapshot();
+    let mut energy = energy::Phases::new(energy);
+    let mut cpu_time = cputime::Phases::new();
+    thermal::start();
+
+    marker.begin("build");
+    thermal::mark("build");
+    energy.begin("build");
+    cpu_time.begin("build");
     let mut list = LinkedList::new();
     for i in 0..num_nodes {
         list.push(i);
     }
+    cpu_time.end();
+    energy.end(num_nodes);
+    marker.end("build");
+    let locked = mlock::before(&list);
 
-    println!("--- x86_64 Hardware Benchmark ---");
-    println!("List Size: {}", num_nodes);
+    if let Some(budget) = budget {
+        iterations = bench::Iterations::Budget(budget).resolve(&list);
+        eprintln!("[measure-for] {:?} budget: {} iterations", budget, iterations);
+    }
 
-    let (visited, time, cycles) = list.benchmark_traversal();
+    marker.begin("traverse");
+    thermal::mark("traverse");
+    energy.begin("traverse");
+    cpu_time.begin("traverse");
+    let samples = if verify {
+        let samples = bench::time_verified_traversals(&list, iterations);
+        eprintln!("Verification passed: {} iteration(s) visited all {} nodes with the expected checksum", samples.len(), num_nodes);
+        samples
+    } else {
+        bench::time_traversals(&list, iterations)
+    };
+    cpu_time.end();
+    energy.end(samples.iter().map(|s| s.visited).sum());
+    // Writes change the payloads, so they run after the (verified) reads.
+    let write_samples = write.then(|| {
+        thermal::mark("traverse-write");
+        energy.begin("traverse-write");
+        cpu_time.begin("traverse-write");
+        let samples = bench::time_write_traversals(&mut list, iterations);
+        cpu_time.end();
+        energy.end(samples.iter().map(|s| s.visited).sum());
+        samples
+    });
+    marker.end("traverse");
+    drop(locked);
+    let layout = cache::node_layout(&list);
+    let mut results = vec![bench::BenchResult { name: "traverse".to_string(), nodes: num_nodes, samples, layout }];
+    if let Some(samples) = write_samples {
+        results.push(bench::BenchResult { name: "traverse-write".to_string(), nodes: num_nodes, samples, layout });
+    }
+    let metadata = metadata::Metadata::collect();
 
-    // --- Statistics ---
-   let time_ns = time.as_nanos() as f64;
-   let cycles_f = cycles as f64;
+    match format {
+        report::Format::Text => {
+            print_results(&results[0], results.get(1));
+            cpu_time.print();
+        }
+        report::Format::Html => print!(
+            "{}",
+            report::html("Linked List Traversal", &metadata, &results)
+        ),
+        report::Format::Markdown => print!("{}", report::markdown("Linked List Traversal", &metadata, &results)),
+        report::Format::GithubBenchmark => print!("{}", report::github_benchmark(&metadata, &results)),
+        report::Format::Ndjson => {
+            for result in &results {
+                println!("{}", report::ndjson("traverse", &metadata, result));
+            }
+        }
+    }
+    energy.print(format == report::Format::Text);
+    thermal::report(format == report::Format::Text);
+    environment::report(&environment, format == report::Format::Text);
 
-    println!("\n[Results]");
-    println!("Total Nodes Visited:   {:?}", visited);
-    println!("Total Time:   {:?}", time);
-    println!("Total Cycles: {}", cycles);
-    if visited > 0 {
-        println!("\n[Efficiency Metrics]");
-        println!("Time per Node:   {:.2} ns", time_ns / visited as f64);
-        println!("Cycles per Node: {:.2} ticks", cycles_f / visited as f64);
-        
-        // This calculates the effective frequency during the test
-        let ghz = (cycles_f / time_ns); 
-        println!("Effective Speed: {:.2} GHz", ghz);
+    if let Some(path) = store_path {
+        let stored = store::Store::open(&path).and_then(|store| results.iter().try_for_each(|result| store.append(&metadata, result)));
+        match stored {
+            Ok(()) => eprintln!("\nResult appended to {}", path),
+            Err(e) => eprintln!("Error: could not store result in {}: {}", path, e),
+        }
+    }
+
+    if let Some(path) = save_path {
+        match results::save(&path, &metadata, &results) {
+            Ok(()) => eprintln!("Samples saved to {}", path),
+            Err(e) => eprintln!("Error: could not save samples to {}: {}", path, e),
+        }
     }
 
     // As suspected the hidden boss [of Rust's strict ownership notions and its ramifications [due
@@ -109,6 +465,11 @@ fn main() {
     //std::process::exit(-1); //let's escape from Rust's ownership notions and let OS take care
 
     // Rust's mechanism to overcome dropping references -- aka legal way to leak memory
-    std::mem::forget(list);
+    // (except under Miri, which would report the leak; there the list is small
+    // and dropping it exercises Drop as well)
+    if cfg!(miri) {
+        drop(list);
+    } else {
+        std::mem::forget(list);
+    }
 }
-
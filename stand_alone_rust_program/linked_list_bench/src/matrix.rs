@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ab;
+use crate::stats::{self, Summary};
+
+/// Built when no `--variant` is given: the release profile as is, tuned
+/// for this machine's CPU, and one optimisation level down.
+const DEFAULT_VARIANTS: [(&str, &str); 3] = [
+    ("default", ""),
+    ("native", "-C target-cpu=native"),
+    ("opt-level=2", "-C opt-level=2"),
+];
+
+/// One build of the benchmark: its name and the RUSTFLAGS it is built with.
+struct Variant {
+    name: String,
+    rustflags: String,
+}
+
+/// `matrix [--variant <name=rustflags>]... [--rounds <r>] [--manifest
+/// <Cargo.toml>] [-- <sweep options>]`: builds this benchmark once per
+/// variant, each into its own target directory, then runs every build's
+/// sweep suite in interleaved rounds and reports cycles/node per size side
+/// by side, each against the first variant with a Welch t-test. Codegen
+/// flags move these loops as far as the choice of data structure does, so
+/// a flag worth keeping should show up here first.
+///
+/// RUSTFLAGS come after the profile's own flags, so `-C opt-level=2`
+/// overrides the release profile's 3. Variants replace any RUSTFLAGS in
+/// the environment; rounds rotate the order the builds run in, as `ab`
+/// alternates its two.
+pub fn run(args: &[String]) {
+    let mut variants: Vec<Variant> = Vec::new();
+    let mut rounds: usize = 5;
+    let mut manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let mut sweep_args: Vec<String> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--variant", Some(v)) => match v.split_once('=') {
+                Some((name, rustflags)) if !name.trim().is_empty() => variants.push(Variant {
+                    name: name.trim().to_string(),
+                    rustflags: rustflags.trim().to_string(),
+                }),
+                _ => {
+                    eprintln!(
+                        "Error: bad --variant '{}' (expected e.g. native='-C target-cpu=native')",
+                        v
+                    );
+                    return;
+                }
+            },
+            ("--rounds", Some(v)) => rounds = v.parse().unwrap_or(rounds),
+            ("--manifest", Some(v)) => manifest = PathBuf::from(v),
+            ("--", first) => {
+                sweep_args.extend(first.cloned());
+                sweep_args.extend(iter.by_ref().cloned());
+            }
+            _ => {
+                eprintln!("Error: unknown or incomplete matrix option '{}'", arg);
+                return;
+            }
+        }
+    }
+    if variants.is_empty() {
+        variants = DEFAULT_VARIANTS
+            .iter()
+            .map(|(name, rustflags)| Variant {
+                name: name.to_string(),
+                rustflags: rustflags.to_string(),
+            })
+            .collect();
+    }
+    let rounds = rounds.max(1);
+
+    let mut binaries = Vec::new();
+    for variant in &variants {
+        eprintln!(
+            "[matrix] building {} (RUSTFLAGS='{}')",
+            variant.name, variant.rustflags
+        );
+        match build(&manifest, variant) {
+            Ok(binary) => binaries.push(binary),
+            Err(e) => {
+                eprintln!("Error: could not build variant {}: {}", variant.name, e);
+                return;
+            }
+        }
+    }
+
+    // nodes -> median cycles/node of each round, per variant.
+    let mut results: Vec<BTreeMap<usize, Vec<f64>>> =
+        variants.iter().map(|_| BTreeMap::new()).collect();
+    for round in 0..rounds {
+        eprintln!("[matrix] round {}/{}", round + 1, rounds);
+        for offset in 0..variants.len() {
+            let index = (round + offset) % variants.len();
+            match ab::run_sweep(&binaries[index], &sweep_args) {
+                Ok(rows) => {
+                    for (nodes, cycles) in rows {
+                        results[index].entry(nodes).or_default().push(cycles);
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Error: could not run variant {}: {}",
+                        variants[index].name, e
+                    );
+                    return;
+                }
+            }
+        }
+    }
+
+    println!("--- Compiler Variant Matrix ({} rounds) ---", rounds);
+    for (variant, binary) in variants.iter().zip(&binaries) {
+        println!(
+            "{}: RUSTFLAGS='{}' ({})",
+            variant.name, variant.rustflags, binary
+        );
+    }
+    print!("\n{:>12}", "Nodes");
+    for (index, variant) in variants.iter().enumerate() {
+        if index == 0 {
+            print!(" {:>14}", truncate(&variant.name, 14));
+        } else {
+            print!(" {:>20}", truncate(&variant.name, 20));
+        }
+    }
+    println!();
+    for (nodes, baseline) in &results[0] {
+        let baseline_mean = Summary::of(baseline).mean;
+        print!("{:>12} {:>14.2}", nodes, baseline_mean);
+        for other in &results[1..] {
+            let Some(cycles) = other.get(nodes) else {
+                print!(" {:>20}", "-");
+                continue;
+            };
+            let mean = Summary::of(cycles).mean;
+            let marker = match stats::welch_t_test(baseline, cycles) {
+                Some(test) if test.p_value < 0.05 => "*",
+                _ => " ",
+            };
+            print!(
+                " {:>10.2} {:>+7.1}%{}",
+                mean,
+                (mean / baseline_mean - 1.0) * 100.0,
+                marker
+            );
+        }
+        println!();
+    }
+    println!(
+        "\nCells: mean over rounds of each run's median cycles/node, then the change against {}",
+        variants[0].name
+    );
+    println!("* significant at p < 0.05 (Welch's t-test over per-round medians)");
+}
+
+/// Builds the release binary of `manifest` with `variant`'s RUSTFLAGS into
+/// a target directory of its own, so variants don't rebuild over each
+/// other, and returns the binary's path.
+fn build(manifest: &Path, variant: &Variant) -> std::io::Result<String> {
+    let root = manifest
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+    let directory: String = variant
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let target = root.join("target").join("matrix").join(directory);
+    // Under `cargo run`, CARGO is the cargo that started us.
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["build", "--release", "--quiet", "--manifest-path"])
+        .arg(manifest)
+        .arg("--target-dir")
+        .arg(&target)
+        .env("RUSTFLAGS", &variant.rustflags)
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "cargo exited with {}",
+            status
+        )));
+    }
+    let binary = target
+        .join("release")
+        .join(format!("linked_list_bench{}", std::env::consts::EXE_SUFFIX));
+    Ok(binary.to_string_lossy().into_owned())
+}
+
+/// `text` cut to at most `width` characters, to keep the columns aligned.
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,191 @@
+use crate::bench::{self, Sample};
+use crate::rng::Rng;
+use crate::LinkedList;
+
+/// `merge [--nodes <a,b,...>] [--iterations <k>]`: combines two lists of N
+/// values each, at each N, two ways:
+///
+/// - merge: two sorted lists into one sorted list, by relinking nodes (no
+///   allocation), against a `Vec` merged with two cursors into a new `Vec`
+///   and against concatenating and sorting;
+/// - zip: interleaving the two, one value of each in turn, by relinking
+///   against collecting into a new `Vec`.
+///
+/// Splicing is the operation lists are said to be good at. The `Vec`
+/// versions allocate and copy every value, but read their inputs in order,
+/// while the list chases a pointer per node of both inputs.
+pub fn run(args: &[String]) {
+    let mut sizes: Vec<usize> = vec![1 << 10, 1 << 14, 1 << 18, 1 << 20];
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => {
+                sizes = v
+                    .split(',')
+                    .filter_map(|n| n.trim().parse().ok())
+                    .filter(|&n| n > 0)
+                    .collect()
+            }
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete merge option '{}'", arg);
+                return;
+            }
+        }
+    }
+    if sizes.is_empty() {
+        eprintln!("Error: --nodes needs at least one positive size");
+        return;
+    }
+
+    println!("--- Merge and Zip ---");
+    println!("Two inputs of N random values each; per-node figures count both inputs");
+    println!(
+        "{:>10} {:<6} {:<12} {:>6} {:>12} {:>14} {:>9} {:>8}",
+        "Nodes", "Op", "Method", "Iters", "ns/node", "cycles/node", "vs list", "Flagged"
+    );
+    for &nodes in &sizes {
+        let mut rng = Rng::new(nodes as u64);
+        let mut a: Vec<u64> = (0..nodes).map(|_| rng.next_u64()).collect();
+        let mut b: Vec<u64> = (0..nodes).map(|_| rng.next_u64()).collect();
+        a.sort_unstable();
+        b.sort_unstable();
+
+        let mut merged = [a.as_slice(), b.as_slice()].concat();
+        merged.sort();
+        let Some(rows) = compare(
+            &a,
+            &b,
+            iterations,
+            &merged,
+            |mut x, y| {
+                x.merge(y);
+                x
+            },
+            &[("vec merge", vec_merge), ("concat+sort", concat_sort)],
+        ) else {
+            eprintln!("Error: the merges of {} nodes disagree", nodes);
+            return;
+        };
+        print_rows(nodes, "merge", &rows);
+
+        let zipped: Vec<u64> = a.iter().zip(&b).flat_map(|(&x, &y)| [x, y]).collect();
+        let Some(rows) = compare(
+            &a,
+            &b,
+            iterations,
+            &zipped,
+            |mut x, y| {
+                x.zip(y);
+                x
+            },
+            &[("vec zip", vec_zip)],
+        ) else {
+            eprintln!("Error: the zips of {} nodes disagree", nodes);
+            return;
+        };
+        print_rows(nodes, "zip", &rows);
+    }
+    println!("\nvs list: how many times faster than relinking the lists");
+}
+
+type VecMethod = fn(Vec<u64>, Vec<u64>) -> Vec<u64>;
+
+/// Times the list operation and every `Vec` method on fresh copies of the
+/// inputs, built outside the timed region. None if any result differs from
+/// `expected`.
+fn compare(
+    a: &[u64],
+    b: &[u64],
+    iterations: usize,
+    expected: &[u64],
+    on_lists: impl Fn(LinkedList<u64>, LinkedList<u64>) -> LinkedList<u64>,
+    methods: &[(&'static str, VecMethod)],
+) -> Option<Vec<(&'static str, Vec<Sample>)>> {
+    let operations = a.len() + b.len();
+    let mut rows = vec![("list", Vec::new())];
+    rows.extend(methods.iter().map(|&(name, _)| (name, Vec::new())));
+    for _ in 0..iterations.max(1) {
+        let mut inputs = Some((
+            LinkedList::from_vec(a.to_vec()),
+            LinkedList::from_vec(b.to_vec()),
+        ));
+        let mut output = None;
+        rows[0].1.extend(bench::time_repeated(1, operations, || {
+            let (x, y) = inputs.take().unwrap();
+            output = Some(on_lists(x, y));
+        }));
+        if output?.into_vec() != expected {
+            return None;
+        }
+
+        for (row, &(_, method)) in rows[1..].iter_mut().zip(methods) {
+            let mut inputs = Some((a.to_vec(), b.to_vec()));
+            let mut output = None;
+            row.1.extend(bench::time_repeated(1, operations, || {
+                let (x, y) = inputs.take().unwrap();
+                output = Some(method(x, y));
+            }));
+            if output? != expected {
+                return None;
+            }
+        }
+    }
+    Some(rows)
+}
+
+fn print_rows(nodes: usize, op: &str, rows: &[(&str, Vec<Sample>)]) {
+    let (_, list_cycles, _) = bench::per_operation(&rows[0].1);
+    for (method, samples) in rows {
+        let (ns, cycles, flagged) = bench::per_operation(samples);
+        println!(
+            "{:>10} {:<6} {:<12} {:>6} {:>12} {:>14} {:>9} {:>8}",
+            nodes,
+            op,
+            method,
+            samples.len(),
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::ratio(&list_cycles, &cycles, 1),
+            flagged
+        );
+    }
+}
+
+fn vec_merge(a: Vec<u64>, b: Vec<u64>) -> Vec<u64> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] <= b[j] {
+            out.push(a[i]);
+            i += 1;
+        } else {
+            out.push(b[j]);
+            j += 1;
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
+
+fn concat_sort(mut a: Vec<u64>, b: Vec<u64>) -> Vec<u64> {
+    a.extend(b);
+    // The stable sort finds the two runs and merges them.
+    a.sort();
+    a
+}
+
+fn vec_zip(a: Vec<u64>, b: Vec<u64>) -> Vec<u64> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    for (x, y) in a.iter().zip(&b) {
+        out.push(*x);
+        out.push(*y);
+    }
+    let shorter = a.len().min(b.len());
+    out.extend_from_slice(&a[shorter..]);
+    out.extend_from_slice(&b[shorter..]);
+    out
+}
@@ -0,0 +1,71 @@
+/// Describes the machine and source revision a result was measured on, so
+/// stored results from different hosts or commits can be told apart.
+pub struct Metadata {
+    pub host: String,
+    pub cpu: String,
+    pub git_commit: String,
+}
+
+impl Metadata {
+    /// Miri runs isolated from the host: no /proc, no child processes.
+    #[cfg(miri)]
+    pub fn collect() -> Self {
+        let unknown = || "unknown".to_string();
+        Metadata {
+            host: unknown(),
+            cpu: unknown(),
+            git_commit: unknown(),
+        }
+    }
+
+    #[cfg(not(miri))]
+    pub fn collect() -> Self {
+        use std::fs;
+        use std::process::Command;
+
+        // /proc on Linux; Windows sets COMPUTERNAME and PROCESSOR_IDENTIFIER
+        // for every process instead, and macOS has sysctl.
+        let host = fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|h| h.trim().to_string())
+            .ok()
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .or_else(|| sysctl_string("kern.hostname"))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let cpu = fs::read_to_string("/proc/cpuinfo")
+            .ok()
+            .and_then(|info| {
+                info.lines()
+                    .find(|l| l.starts_with("model name"))
+                    .and_then(|l| l.split(':').nth(1))
+                    .map(|m| m.trim().to_string())
+            })
+            .or_else(|| std::env::var("PROCESSOR_IDENTIFIER").ok())
+            .or_else(|| sysctl_string("machdep.cpu.brand_string"))
+            .unwrap_or_else(|| std::env::consts::ARCH.to_string());
+
+        let git_commit = Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Metadata {
+            host,
+            cpu,
+            git_commit,
+        }
+    }
+}
+
+#[cfg(all(target_os = "macos", not(miri)))]
+fn sysctl_string(name: &str) -> Option<String> {
+    crate::sysctl::string(name)
+}
+
+#[cfg(all(not(target_os = "macos"), not(miri)))]
+fn sysctl_string(_name: &str) -> Option<String> {
+    None
+}
@@ -0,0 +1,190 @@
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{LinkedList, Node};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Makes every list the timing helpers measure from now on locked into
+/// memory and pre-touched before its timed phase. Returns how, for the
+/// run's notes.
+pub fn enable() -> String {
+    ENABLED.store(true, Ordering::Relaxed);
+    if !imp::SUPPORTED {
+        return "mlock isn't available here: pages are pre-touched only".to_string();
+    }
+    match imp::memlock_limit() {
+        Some(limit) => format!(
+            "locking each list's pages (RLIMIT_MEMLOCK {} KiB) and pre-touching them before it is timed",
+            limit >> 10
+        ),
+        None => "locking each list's pages and pre-touching them before it is timed".to_string(),
+    }
+}
+
+/// The pages holding a list's nodes, locked for as long as this lives.
+pub struct Locked {
+    /// (first address, bytes) of each run of consecutive pages locked.
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Drop for Locked {
+    fn drop(&mut self) {
+        for &(start, bytes) in &self.ranges {
+            imp::unlock(start, bytes);
+        }
+    }
+}
+
+/// When `--mlock` is on, locks the pages of `list` into memory and touches
+/// every node, so the timed phase that follows takes no page faults on
+/// it, and notes how many pages that covered. Where locking fails (most
+/// often an RLIMIT_MEMLOCK smaller than the list) the pages that did lock
+/// stay locked and the rest are only pre-touched. The timing helpers call
+/// this once per list; the pages unlock when the result is dropped.
+pub fn before(list: &LinkedList<usize>) -> Option<Locked> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let page = imp::page_size();
+    let mut pages: Vec<usize> = Vec::new();
+    let mut nodes = 0;
+    let mut current = &list.head;
+    while let Some(node) = current {
+        nodes += 1;
+        let start = &**node as *const Node<usize> as usize;
+        let end = start + std::mem::size_of::<Node<usize>>() - 1;
+        pages.extend(start / page..=end / page);
+        current = &node.next;
+    }
+    pages.sort_unstable();
+    pages.dedup();
+
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &p in &pages {
+        match runs.last_mut() {
+            Some((first, count)) if *first + *count == p => *count += 1,
+            _ => runs.push((p, 1)),
+        }
+    }
+    let mut locked = Locked { ranges: Vec::new() };
+    let mut locked_pages = 0;
+    let mut failure = None;
+    for &(first, count) in runs.iter().filter(|_| imp::SUPPORTED) {
+        match imp::lock(first * page, count * page) {
+            Ok(()) => {
+                locked.ranges.push((first * page, count * page));
+                locked_pages += count;
+            }
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+
+    // Locking faults the locked pages in; touching every node also covers
+    // the ones that didn't lock, and fills the TLB-missable page tables.
+    let mut current = &list.head;
+    while let Some(node) = current {
+        // SAFETY: both reads are of initialised fields of a live node.
+        unsafe {
+            ptr::read_volatile(&node.data);
+            ptr::read_volatile(&node.next as *const _ as *const u8);
+        }
+        current = &node.next;
+    }
+
+    match failure {
+        _ if !imp::SUPPORTED => eprintln!(
+            "[mlock] {} nodes: pre-touched {} pages (mlock isn't available here)",
+            nodes,
+            pages.len()
+        ),
+        None => eprintln!(
+            "[mlock] {} nodes: locked {} pages ({} KiB) in {} ranges",
+            nodes,
+            locked_pages,
+            (locked_pages * page) >> 10,
+            locked.ranges.len()
+        ),
+        Some(e) => eprintln!(
+            "[mlock] {} nodes: locked {} of {} pages, then {}{}; the rest are pre-touched only",
+            nodes,
+            locked_pages,
+            pages.len(),
+            e,
+            imp::memlock_limit().map_or(String::new(), |limit| format!(
+                " (RLIMIT_MEMLOCK is {} KiB)",
+                limit >> 10
+            ))
+        ),
+    }
+    Some(locked)
+}
+
+#[cfg(all(any(target_os = "linux", target_os = "macos"), not(miri)))]
+mod imp {
+    use std::io;
+
+    pub const SUPPORTED: bool = true;
+
+    pub fn page_size() -> usize {
+        // SAFETY: sysconf only reads a configuration value.
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            size as usize
+        } else {
+            4096
+        }
+    }
+
+    pub fn lock(start: usize, bytes: usize) -> Result<(), String> {
+        // SAFETY: mlock changes no memory, only whether it may be paged out;
+        // the range covers pages this process has mapped.
+        if unsafe { libc::mlock(start as *const libc::c_void, bytes) } == 0 {
+            Ok(())
+        } else {
+            Err(format!("mlock failed: {}", io::Error::last_os_error()))
+        }
+    }
+
+    pub fn unlock(start: usize, bytes: usize) {
+        // SAFETY: as for `lock`.
+        unsafe { libc::munlock(start as *const libc::c_void, bytes) };
+    }
+
+    /// The soft limit in bytes; `None` when it is unlimited or unknown.
+    // rlim_t is only 32 bits on some 32-bit targets.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn memlock_limit() -> Option<u64> {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit fills in the struct it is given and nothing else.
+        if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } != 0 {
+            return None;
+        }
+        (limit.rlim_cur != libc::RLIM_INFINITY).then_some(limit.rlim_cur as u64)
+    }
+}
+
+#[cfg(not(all(any(target_os = "linux", target_os = "macos"), not(miri))))]
+mod imp {
+    pub const SUPPORTED: bool = false;
+
+    pub fn page_size() -> usize {
+        4096
+    }
+
+    pub fn lock(_start: usize, _bytes: usize) -> Result<(), String> {
+        Err("mlock isn't available here".to_string())
+    }
+
+    pub fn unlock(_start: usize, _bytes: usize) {}
+
+    pub fn memlock_limit() -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,96 @@
+use crate::bench::{self, Sample};
+use crate::stats::Summary;
+use crate::{Link, LinkedList};
+
+const MAX_LISTS: usize = 16;
+
+/// `mlp [--nodes <per list>] [--iterations <k>]`: traverses K independent,
+/// randomly linked lists at once from one thread, advancing the K cursors
+/// round-robin, for K = 1..16. Each list on its own is one chain of
+/// dependent loads; K chains give the core K misses it can keep in flight.
+/// Aggregate nodes per cycle rises until the miss handling resources (line
+/// fill buffers) run out, and the speedup at that point is how much of a
+/// single list's slowness is latency that memory-level parallelism could
+/// hide.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 17;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete mlp option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+
+    let lists: Vec<LinkedList<usize>> = (0..MAX_LISTS)
+        .map(|seed| bench::build_shuffled(nodes, seed as u64 + 1))
+        .collect();
+
+    println!("--- Memory-Level Parallelism ---");
+    println!("{} randomly linked nodes per list", nodes);
+    println!(
+        "{:>6} {:>14} {:>12} {:>10} {:>8}",
+        "Lists", "cycles/node", "nodes/cycle", "Speedup", "Flagged"
+    );
+
+    let mut single = None;
+    // Among list counts with unflagged samples, next to a single list with
+    // them too.
+    let mut best: Option<(usize, f64)> = None;
+    for k in 1..=MAX_LISTS {
+        let samples: Vec<Sample> =
+            bench::time_repeated(iterations, k * nodes, || interleaved(&lists[..k]));
+        let (_, cycles, flagged) = bench::per_operation(&samples);
+        let rate = 1.0 / cycles.median.max(f64::MIN_POSITIVE);
+        let single: Summary = *single.get_or_insert(cycles);
+        let speedup = single.median / cycles.median.max(f64::MIN_POSITIVE);
+        if single.count > 0 && cycles.count > 0 && best.is_none_or(|(_, b)| speedup > b) {
+            best = Some((k, speedup));
+        }
+        println!(
+            "{:>6} {:>14} {:>12} {:>10} {:>8}",
+            k,
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, rate, 4),
+            bench::ratio(&single, &cycles, 2),
+            flagged
+        );
+    }
+
+    match best {
+        None => println!("\nPeak: unknown, every single-list sample was flagged"),
+        Some((k, speedup)) => println!(
+            "\nPeak {:.2}x single-list throughput at {} lists: about {:.0}% of a lone \
+             traversal's time is load latency that parallel misses can overlap",
+            speedup,
+            k,
+            (1.0 - 1.0 / speedup) * 100.0
+        ),
+    }
+}
+
+/// Advances one cursor per list in turn until every list is exhausted.
+/// Returns the number of nodes visited.
+fn interleaved(lists: &[LinkedList<usize>]) -> usize {
+    let mut cursors: Vec<&Link<usize>> = lists.iter().map(|list| &list.head).collect();
+    let mut visited = 0;
+    let mut live = cursors.len();
+    while live > 0 {
+        live = 0;
+        for cursor in cursors.iter_mut() {
+            if let Some(node) = cursor {
+                visited += 1;
+                live += 1;
+                *cursor = &node.next;
+            }
+        }
+    }
+    visited
+}
@@ -0,0 +1,202 @@
+use std::fs;
+
+use crate::bench::{self, BenchResult};
+use crate::cache;
+use crate::rng::Rng;
+use crate::stats::Summary;
+
+/// Always allocated ahead of the list, plus the trial's offset: a request
+/// this big makes glibc fold the previous trial's freed nodes back into the
+/// heap top, so every trial's list starts just past its padding.
+const BASE_PADDING: usize = 4096;
+
+/// Set in the re-executed child of `--no-aslr`, which must not re-exec again.
+const NO_ASLR_ENV: &str = "LINKED_LIST_BENCH_NO_ASLR";
+
+/// `placement [--nodes <n>] [--trials <k>] [--iterations <i>] [--max-offset <bytes>]
+/// [--seed <s>] [--offsets <a,b,...>] [--no-aslr]`: builds and times the
+/// list `k` times, each time behind a leading allocation of a different
+/// size, so its nodes land at a different heap offset. How far the trial
+/// medians spread, against the noise within a trial, is how much of a
+/// result is layout luck.
+///
+/// The offsets are printed so a run can be repeated with `--offsets`.
+/// `--no-aslr` re-executes the benchmark with address space randomisation
+/// turned off (Linux), so the heap base is the same from run to run too.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 18;
+    let mut trials: usize = 10;
+    let mut iterations: usize = 10;
+    let mut max_offset: usize = 64 << 10;
+    let mut seed: u64 = 42;
+    let mut offsets: Option<Vec<usize>> = None;
+    let mut no_aslr = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--no-aslr" {
+            no_aslr = true;
+            continue;
+        }
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--trials", Some(v)) => trials = v.parse().unwrap_or(trials),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--max-offset", Some(v)) => max_offset = v.parse().unwrap_or(max_offset),
+            ("--seed", Some(v)) => seed = v.parse().unwrap_or(seed),
+            ("--offsets", Some(v)) => {
+                offsets = Some(v.split(',').filter_map(|o| o.trim().parse().ok()).collect())
+            }
+            _ => {
+                eprintln!("Error: unknown or incomplete placement option '{}'", arg);
+                return;
+            }
+        }
+    }
+    if no_aslr && std::env::var_os(NO_ASLR_ENV).is_none() {
+        return without_aslr(args);
+    }
+    let nodes = nodes.max(1);
+
+    let offsets = offsets.unwrap_or_else(|| {
+        let mut rng = Rng::new(seed);
+        // malloc hands out 16-byte aligned chunks, so finer steps are
+        // rounded away.
+        let steps = (max_offset / 16).max(1);
+        (0..trials.max(1)).map(|_| rng.below(steps) * 16).collect()
+    });
+    if offsets.is_empty() {
+        eprintln!("Error: --offsets needs at least one offset in bytes");
+        return;
+    }
+
+    println!("--- Heap Placement Sensitivity ---");
+    println!(
+        "{} nodes, {} trials of {} traversals; ASLR {}",
+        nodes,
+        offsets.len(),
+        iterations,
+        aslr_state()
+    );
+    println!(
+        "{:>6} {:>10} {:>12} {:>10} {:>14} {:>10} {:>8}",
+        "Trial", "Offset", "Head in page", "Adjacent", "cycles/node", "stddev", "Flagged"
+    );
+
+    let mut medians = Vec::new();
+    let mut noise = Vec::new();
+    for (trial, &offset) in offsets.iter().enumerate() {
+        let padding = std::hint::black_box(vec![0u8; BASE_PADDING + offset]);
+        let list = bench::build(nodes);
+        let head = list.head.as_deref().map(|node| node as *const _ as usize);
+        let result = BenchResult {
+            name: "traverse".to_string(),
+            nodes,
+            samples: bench::time_traversals(&list, iterations),
+            layout: cache::node_layout(&list),
+        };
+        drop(list);
+        drop(padding);
+
+        let cycles = result.cycles_per_node();
+        println!(
+            "{:>6} {:>10} {:>12} {:>9.0}% {:>14} {:>10} {:>8}",
+            trial + 1,
+            offset,
+            head.map_or_else(|| "-".to_string(), |a| format!("{:#05x}", a % 4096)),
+            result.layout.adjacent_fraction * 100.0,
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            result.flagged()
+        );
+        if cycles.count > 0 {
+            medians.push(cycles.median);
+            noise.push(cycles.stddev);
+        }
+    }
+
+    let across = Summary::of(&medians);
+    let max = medians.iter().cloned().fold(0.0, f64::max);
+    let within = Summary::of(&noise).median;
+    println!(
+        "\nAcross trials: {:.2} to {:.2} cycles/node, median {:.2}, spread {:.1}% of the median",
+        across.min,
+        max,
+        across.median,
+        (max - across.min) * 100.0 / across.median.max(f64::MIN_POSITIVE)
+    );
+    println!(
+        "Between-trial stddev {:.2} vs median within-trial stddev {:.2} cycles/node: {}",
+        across.stddev,
+        within,
+        if across.stddev > 2.0 * within {
+            "placement matters; compare runs at the same offsets"
+        } else {
+            "placement is lost in the noise"
+        }
+    );
+    println!(
+        "Offsets: {}",
+        offsets
+            .iter()
+            .map(|o| o.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+}
+
+/// `/proc/sys/kernel/randomize_va_space` as words, and whether this
+/// process runs without it.
+fn aslr_state() -> String {
+    if std::env::var_os(NO_ASLR_ENV).is_some() {
+        return "disabled for this run".to_string();
+    }
+    if cfg!(miri) {
+        return "state unknown under Miri".to_string();
+    }
+    match fs::read_to_string("/proc/sys/kernel/randomize_va_space")
+        .ok()
+        .as_deref()
+        .map(str::trim)
+    {
+        Some("0") => "off system-wide".to_string(),
+        Some("1") => "on (stack, mmap and vDSO; heap base fixed)".to_string(),
+        Some("2") => "on (including the heap)".to_string(),
+        _ => "state unknown".to_string(),
+    }
+}
+
+/// Re-runs `placement` with ADDR_NO_RANDOMIZE set, which takes effect at
+/// the next exec.
+#[cfg(all(target_os = "linux", not(miri)))]
+fn without_aslr(args: &[String]) {
+    // SAFETY: personality only changes flags applied at the next exec;
+    // 0xffffffff queries the current ones.
+    let current = unsafe { libc::personality(0xffff_ffff) };
+    if current == -1
+        || unsafe { libc::personality((current | libc::ADDR_NO_RANDOMIZE) as libc::c_ulong) } == -1
+    {
+        eprintln!(
+            "Error: could not disable ASLR: {}",
+            std::io::Error::last_os_error()
+        );
+        return;
+    }
+    let status = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .arg("placement")
+            .args(args)
+            .env(NO_ASLR_ENV, "1")
+            .status()
+    });
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("Error: the run without ASLR exited with {}", status),
+        Err(e) => eprintln!("Error: could not re-run without ASLR: {}", e),
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(miri))))]
+fn without_aslr(_args: &[String]) {
+    eprintln!("Error: --no-aslr needs Linux's personality(2)");
+}
@@ -0,0 +1,151 @@
+use std::error::Error;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::bench::BenchResult;
+
+const SIZE: (u32, u32) = (1024, 640);
+
+/// Renders median cycles-per-node against list size (log2-x), one curve per
+/// structure. The backend is picked from the file extension (.svg or .png).
+pub fn cycles_per_node(
+    path: &str,
+    series: &[(&str, &[BenchResult])],
+) -> Result<(), Box<dyn Error>> {
+    if path.ends_with(".svg") {
+        draw(SVGBackend::new(path, SIZE).into_drawing_area(), series)
+    } else if path.ends_with(".png") {
+        draw(BitMapBackend::new(path, SIZE).into_drawing_area(), series)
+    } else {
+        Err("plot file must end in .svg or .png".into())
+    }
+}
+
+/// Same chart as [`cycles_per_node`], rendered to an SVG string for embedding.
+pub fn cycles_per_node_svg(series: &[(&str, &[BenchResult])]) -> Result<String, Box<dyn Error>> {
+    let mut svg = String::new();
+    draw(
+        SVGBackend::with_string(&mut svg, SIZE).into_drawing_area(),
+        series,
+    )?;
+    Ok(svg)
+}
+
+/// Histogram of the per-iteration cycles-per-node samples of one result, as
+/// an SVG string.
+pub fn histogram_svg(result: &BenchResult) -> Result<String, Box<dyn Error>> {
+    const BINS: usize = 20;
+
+    let values: Vec<f64> = result
+        .valid_samples()
+        .map(|s| s.cycles_per_node())
+        .collect();
+    if values.is_empty() {
+        return Err("no samples to plot".into());
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    // Keep a non-zero bin width when every sample is identical.
+    let width = ((max - min) / BINS as f64).max(f64::EPSILON.max(min.abs() * 1e-6));
+
+    let mut counts = [0u32; BINS];
+    for v in &values {
+        counts[(((v - min) / width) as usize).min(BINS - 1)] += 1;
+    }
+    let max_count = counts.iter().copied().max().unwrap_or(1);
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (640, 320)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                format!("{} @ {} nodes", result.name, result.nodes),
+                ("sans-serif", 18),
+            )
+            .margin(12)
+            .x_label_area_size(36)
+            .y_label_area_size(40)
+            .build_cartesian_2d(min..min + width * BINS as f64, 0u32..max_count + 1)?;
+        chart
+            .configure_mesh()
+            .x_desc("Cycles per node")
+            .y_desc("Samples")
+            .draw()?;
+        chart.draw_series(counts.iter().enumerate().map(|(i, &count)| {
+            let x0 = min + width * i as f64;
+            Rectangle::new([(x0, 0), (x0 + width, count)], BLUE.mix(0.6).filled())
+        }))?;
+        root.present()?;
+    }
+    Ok(svg)
+}
+
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    series: &[(&str, &[BenchResult])],
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let points = || series.iter().flat_map(|(_, p)| p.iter());
+    // Plot against log2(N) so every power-of-two size gets its own tick.
+    let min_log = (points().map(|p| p.nodes).min().ok_or("nothing to plot")? as f64)
+        .log2()
+        .floor();
+    let max_log = (points().map(|p| p.nodes).max().unwrap_or(1) as f64)
+        .log2()
+        .ceil();
+    // Fully flagged sizes have no median: a point at 0 would read as a drop.
+    let plotted = |points: &[BenchResult]| -> Vec<(f64, f64)> {
+        points
+            .iter()
+            .filter_map(|p| {
+                let cycles = p.cycles_per_node();
+                (cycles.count > 0).then(|| ((p.nodes as f64).log2(), cycles.median))
+            })
+            .collect()
+    };
+    let max_cycles = series
+        .iter()
+        .flat_map(|(_, points)| plotted(points))
+        .map(|(_, cycles)| cycles)
+        .reduce(f64::max)
+        .ok_or("nothing to plot: every sample was flagged")?;
+
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Traversal cost vs list size", ("sans-serif", 24))
+        .margin(16)
+        .margin_right(48)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(min_log..max_log.max(min_log + 1.0), 0.0..max_cycles * 1.1)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Nodes (N)")
+        .y_desc("Cycles per node")
+        .x_labels((max_log - min_log) as usize + 1)
+        .x_label_formatter(&|x| format!("{:.0}", x.exp2()))
+        .draw()?;
+
+    for (i, (name, points)) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        let data = plotted(points);
+        chart
+            .draw_series(LineSeries::new(data.iter().copied(), color.stroke_width(2)))?
+            .label(*name)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        chart.draw_series(data.iter().map(|&p| Circle::new(p, 3, color.filled())))?;
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::hint::black_box;
+
+use crate::bench;
+use crate::cache;
+use crate::rng::Rng;
+use crate::{Link, LinkedList, Node};
+
+/// Recycles list nodes through a free list instead of returning them to the
+/// allocator, so a push after a pop reuses the memory the pop released.
+pub struct NodePool<T> {
+    free: Link<T>,
+}
+
+impl<T> NodePool<T> {
+    pub fn new() -> Self {
+        NodePool { free: None }
+    }
+
+    /// A node holding `data`, from the free list when it has one.
+    pub fn alloc(&mut self, data: T) -> Box<Node<T>> {
+        match self.free.take() {
+            Some(mut node) => {
+                self.free = node.next.take();
+                node.data = data;
+                node
+            }
+            None => Box::new(Node { data, next: None }),
+        }
+    }
+
+    /// Returns a node to the free list. Its payload is dropped when the node
+    /// is reused or the pool is.
+    pub fn release(&mut self, mut node: Box<Node<T>>) {
+        node.next = self.free.take();
+        self.free = Some(node);
+    }
+}
+
+impl<T> Drop for NodePool<T> {
+    /// Same iterative unlinking as `LinkedList`'s drop.
+    fn drop(&mut self) {
+        let mut current = self.free.take();
+        while let Some(mut node) = current {
+            current = node.next.take();
+        }
+    }
+}
+
+/// Where churned nodes come from and go to.
+trait Source {
+    fn push(&mut self, list: &mut LinkedList<usize>, data: usize);
+    fn pop(&mut self, list: &mut LinkedList<usize>);
+}
+
+/// Every push is a fresh `Box`, every pop frees it.
+struct Fresh;
+
+impl Source for Fresh {
+    fn push(&mut self, list: &mut LinkedList<usize>, data: usize) {
+        list.push(data);
+    }
+
+    fn pop(&mut self, list: &mut LinkedList<usize>) {
+        black_box(list.pop());
+    }
+}
+
+impl Source for NodePool<usize> {
+    fn push(&mut self, list: &mut LinkedList<usize>, data: usize) {
+        list.push_node(self.alloc(data));
+    }
+
+    fn pop(&mut self, list: &mut LinkedList<usize>) {
+        if let Some(node) = list.pop_node() {
+            self.release(node);
+        }
+    }
+}
+
+/// `churn [--nodes <n>] [--ops <k>] [--iterations <n>]`: keeps a list of
+/// about `n` nodes and applies `k` random pushes and pops per sample, once
+/// with fresh `Box` allocations and once through a [`NodePool`]. Reports the
+/// cost per operation, then how the churned list ended up laid out and what
+/// that does to a traversal of it.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 16;
+    let mut ops: usize = 1 << 20;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--ops", Some(v)) => ops = v.parse().unwrap_or(ops),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete churn option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(2);
+
+    println!("--- Node Churn: fresh Box vs free-list pool ---");
+    println!(
+        "{} resident nodes, {} push/pop operations per sample",
+        nodes, ops
+    );
+    println!(
+        "{:<8} {:>6} {:>10} {:>10} {:>10} {:>10} {:>12} {:>16}",
+        "Source", "Iters", "ns/op", "cycles/op", "Mops/s", "Adjacent", "Stride", "traverse cyc/n"
+    );
+    report("fresh", nodes, ops, iterations, &mut Fresh);
+    report("pool", nodes, ops, iterations, &mut NodePool::new());
+}
+
+fn report(name: &str, nodes: usize, ops: usize, iterations: usize, source: &mut impl Source) {
+    let mut list = LinkedList::new();
+    for i in 0..nodes {
+        source.push(&mut list, i);
+    }
+    // Same seed for both sources: identical operation sequences.
+    let mut rng = Rng::new(nodes as u64);
+    let samples = bench::time_repeated(iterations, ops, || {
+        churn(&mut list, source, &mut rng, nodes, ops)
+    });
+    let (ns, cycles, _) = bench::per_operation(&samples);
+
+    let layout = cache::node_layout(&list);
+    let traversal = bench::time_traversals(&list, iterations);
+    let (_, traverse_cycles, _) = bench::per_operation(&traversal);
+    println!(
+        "{:<8} {:>6} {:>10} {:>10} {:>10} {:>9.0}% {:>10} B {:>16}",
+        name,
+        samples.len(),
+        bench::stat(&ns, ns.median, 2),
+        bench::stat(&cycles, cycles.median, 2),
+        bench::stat(&ns, 1e3 / ns.median.max(f64::MIN_POSITIVE), 1),
+        layout.adjacent_fraction * 100.0,
+        layout.median_stride,
+        bench::stat(&traverse_cycles, traverse_cycles.median, 2)
+    );
+}
+
+/// Applies `ops` pushes and pops, random but keeping the list between half
+/// and one and a half times `target` nodes.
+fn churn(
+    list: &mut LinkedList<usize>,
+    source: &mut impl Source,
+    rng: &mut Rng,
+    target: usize,
+    ops: usize,
+) -> usize {
+    for op in 0..ops {
+        let push = if list.count <= target / 2 {
+            true
+        } else if list.count >= target + target / 2 {
+            false
+        } else {
+            rng.next_u64() & 1 == 0
+        };
+        if push {
+            source.push(list, op);
+        } else {
+            source.pop(list);
+        }
+    }
+    list.count
+}
@@ -0,0 +1,134 @@
+use crate::bench::{self, Sample};
+use crate::rng::Rng;
+use crate::stats::Summary;
+
+/// One timed call: its index, the list's length at the time and the sample.
+type Call = (usize, usize, Sample);
+
+/// `position [--nodes <n>] [--ops <k>] [--regions <r>]`: times each
+/// positional operation (`insert_at`, `remove_at`, `split_off`) `k` times
+/// at uniformly random positions of an N-node list, one sample per call,
+/// and reports them bucketed by where in the list they landed. Every call
+/// first walks to its position, so a single average is the cost of an
+/// operation halfway down; the buckets show the line it sits on, and cycles
+/// per node walked says how steep that line is.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 16;
+    let mut ops: usize = 2000;
+    let mut regions: usize = 3;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--ops", Some(v)) => ops = v.parse().unwrap_or(ops),
+            ("--regions", Some(v)) => regions = v.parse().unwrap_or(regions),
+            _ => {
+                eprintln!("Error: unknown or incomplete position option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+    let ops = ops.max(1);
+    let regions = regions.clamp(1, 100);
+
+    let mut rng = Rng::new(nodes as u64);
+    let mut list = bench::build(nodes);
+    let mut timed: Vec<(&str, Vec<Call>)> = Vec::new();
+
+    let mut calls = Vec::with_capacity(ops);
+    for value in 0..ops {
+        let index = rng.below(list.count + 1);
+        let length = list.count;
+        let sample = bench::time_repeated(1, 1, || list.insert_at(index, value)).remove(0);
+        calls.push((index, length, sample));
+    }
+    timed.push(("insert_at", calls));
+
+    let mut calls = Vec::with_capacity(ops);
+    for _ in 0..ops {
+        let index = rng.below(list.count);
+        let length = list.count;
+        let sample = bench::time_repeated(1, 1, || list.remove_at(index)).remove(0);
+        calls.push((index, length, sample));
+    }
+    timed.push(("remove_at", calls));
+
+    let mut calls = Vec::with_capacity(ops);
+    for _ in 0..ops {
+        let index = rng.below(list.count + 1);
+        let length = list.count;
+        let mut tail = None;
+        let sample = bench::time_repeated(1, 1, || tail = Some(list.split_off(index))).remove(0);
+        // Put the tail back outside the timed region, so the list keeps its
+        // length and no node is freed.
+        if let Some(tail) = tail {
+            list.append(tail);
+        }
+        calls.push((index, length, sample));
+    }
+    timed.push(("split_off", calls));
+
+    println!("--- Cost by Position ---");
+    println!(
+        "{} nodes, {} calls of each operation at random positions",
+        nodes, ops
+    );
+    println!(
+        "{:<10} {:<12} {:>6} {:>12} {:>12} {:>12} {:>12} {:>8}",
+        "Op", "Region", "Calls", "ns/op", "cycles/op", "stddev", "cyc/walked", "Flagged"
+    );
+    for (op, calls) in &timed {
+        for region in 0..regions {
+            let in_region: Vec<&Call> = calls
+                .iter()
+                .filter(|(index, length, _)| region_of(*index, *length, regions) == region)
+                .collect();
+            print_row(op, &region_name(region, regions), &in_region);
+        }
+        print_row(op, "all", &calls.iter().collect::<Vec<_>>());
+    }
+    println!("\ncyc/walked: median cycles per node walked to reach the position");
+}
+
+/// Which of `regions` equal slices of the list `index` falls in.
+fn region_of(index: usize, length: usize, regions: usize) -> usize {
+    (index * regions / (length + 1)).min(regions - 1)
+}
+
+fn region_name(region: usize, regions: usize) -> String {
+    match (regions, region) {
+        (3, 0) => "head".to_string(),
+        (3, 1) => "middle".to_string(),
+        (3, _) => "tail".to_string(),
+        _ => format!(
+            "{}-{}%",
+            region * 100 / regions,
+            (region + 1) * 100 / regions
+        ),
+    }
+}
+
+fn print_row(op: &str, region: &str, calls: &[&Call]) {
+    let samples: Vec<Sample> = calls.iter().map(|(_, _, s)| *s).collect();
+    let (ns, cycles, flagged) = bench::per_operation(&samples);
+    let walked = Summary::of(
+        &calls
+            .iter()
+            .filter(|(_, _, s)| s.anomaly.is_none())
+            .map(|(index, _, s)| s.cycles as f64 / (*index).max(1) as f64)
+            .collect::<Vec<_>>(),
+    );
+    println!(
+        "{:<10} {:<12} {:>6} {:>12} {:>12} {:>12} {:>12} {:>8}",
+        op,
+        region,
+        calls.len(),
+        bench::stat(&ns, ns.median, 1),
+        bench::stat(&cycles, cycles.median, 1),
+        bench::stat(&cycles, cycles.stddev, 1),
+        bench::stat(&walked, walked.median, 2),
+        flagged
+    );
+}
@@ -0,0 +1,150 @@
+use crate::bench::{self, Sample};
+use crate::{LinkedList, Node};
+
+const DISTANCES: [usize; 6] = [0, 1, 2, 4, 8, 16];
+
+/// `prefetch [--nodes <n>] [--iterations <k>]`: software prefetching for a
+/// randomly linked list. The links alone can't be prefetched ahead (the next
+/// address is only known once the current node arrives), so an auxiliary
+/// array holds node addresses in list order and the traversal prefetches the
+/// node `distance` steps ahead while it follows the links as usual. Too short
+/// a distance leaves the miss exposed, too long evicts lines before they are
+/// used; the sweep finds the best distance for this machine.
+///
+/// The prefetch addresses don't depend on the chain, so out-of-order
+/// execution also issues the prefetches of later iterations early: even a
+/// distance of one overlaps many misses. The sweep measures that combined
+/// effect, which is what a real prefetching traversal would get.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 20;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete prefetch option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    let list = bench::build_shuffled(nodes.max(1), 1);
+    let addresses = node_addresses(&list);
+
+    println!("--- Prefetch Distance Sweep ---");
+    println!("{} randomly linked nodes", list.count);
+    println!(
+        "{:>10} {:>12} {:>10} {:>10} {:>8}",
+        "Distance", "cycles/node", "stddev", "Speedup", "Flagged"
+    );
+
+    let mut baseline = None;
+    let mut best = (0, f64::INFINITY);
+    for distance in DISTANCES {
+        let samples: Vec<Sample> = bench::time_repeated(iterations, list.count, || {
+            traverse_prefetching(&list, &addresses, distance)
+        });
+        let (_, cycles, flagged) = bench::per_operation(&samples);
+        let none = *baseline.get_or_insert(cycles);
+        if cycles.count > 0 && cycles.median < best.1 {
+            best = (distance, cycles.median);
+        }
+        println!(
+            "{:>10} {:>12} {:>10} {:>10} {:>8}",
+            if distance == 0 {
+                "none".to_string()
+            } else {
+                distance.to_string()
+            },
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            bench::ratio(&none, &cycles, 2),
+            flagged
+        );
+    }
+
+    match best {
+        (_, cycles) if cycles.is_infinite() => {
+            println!("\nBest: unknown, every sample was flagged")
+        }
+        (0, _) => println!("\nBest: no prefetching (nothing to hide at this size)"),
+        (distance, cycles) => println!(
+            "\nBest distance: {} nodes ahead, {:.2} cycles/node ({} faster than no prefetch)",
+            distance,
+            cycles,
+            baseline.map_or_else(
+                || bench::ALL_FLAGGED.to_string(),
+                |none| bench::stat(&none, none.median / cycles.max(f64::MIN_POSITIVE), 2) + "x"
+            )
+        ),
+    }
+}
+
+/// Node addresses in list order.
+fn node_addresses(list: &LinkedList<usize>) -> Vec<*const Node<usize>> {
+    let mut addresses = Vec::with_capacity(list.count);
+    let mut current = &list.head;
+    while let Some(node) = current {
+        addresses.push(&**node as *const Node<usize>);
+        current = &node.next;
+    }
+    addresses
+}
+
+/// Follows the links, prefetching the node `distance` positions ahead;
+/// `distance == 0` is a plain traversal. Returns the node count.
+fn traverse_prefetching(
+    list: &LinkedList<usize>,
+    addresses: &[*const Node<usize>],
+    distance: usize,
+) -> usize {
+    let mut current = &list.head;
+    let mut visited = 0;
+    while let Some(node) = current {
+        if distance > 0 {
+            if let Some(&ahead) = addresses.get(visited + distance) {
+                prefetch(ahead);
+            }
+        }
+        visited += 1;
+        current = &node.next;
+    }
+    visited
+}
+
+/// Hints the CPU to bring the line at `ptr` into L1. Never faults, so the
+/// pointer needn't be valid; a no-op where there is no prefetch instruction
+/// to use.
+#[inline(always)]
+fn prefetch<T>(ptr: *const T) {
+    #[cfg(all(target_arch = "x86_64", not(miri)))]
+    // SAFETY: prefetches are hints and never fault.
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8);
+    }
+    // PREFETCHT0 is an SSE instruction: present on i686, not on i586.
+    #[cfg(all(target_arch = "x86", target_feature = "sse", not(miri)))]
+    // SAFETY: as above.
+    unsafe {
+        use std::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8);
+    }
+    #[cfg(all(target_arch = "aarch64", not(miri)))]
+    // SAFETY: as above.
+    unsafe {
+        std::arch::asm!("prfm pldl1keep, [{0}]", in(reg) ptr, options(nostack, readonly, preserves_flags));
+    }
+    #[cfg(any(
+        miri,
+        not(any(
+            target_arch = "x86_64",
+            all(target_arch = "x86", target_feature = "sse"),
+            target_arch = "aarch64"
+        ))
+    ))]
+    let _ = ptr;
+}
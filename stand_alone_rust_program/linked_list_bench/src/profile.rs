@@ -0,0 +1,132 @@
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{self, Command};
+
+// Environment variables handed to the re-exec'd benchmark so it can talk to
+// `perf record --control`.
+const CTL_FIFO_ENV: &str = "LINKED_LIST_BENCH_PERF_CTL";
+const ACK_FIFO_ENV: &str = "LINKED_LIST_BENCH_PERF_ACK";
+
+/// Brackets one benchmark phase ("build" or "traverse") so a profiler only
+/// sees that phase instead of the whole multi-second run.
+///
+/// Markers always go to stderr. When running under [`record`], the marker
+/// also enables/disables perf's event collection through its control fifo.
+pub struct PhaseMarker {
+    phase: Option<String>,
+    perf: Option<(File, File)>,
+}
+
+impl PhaseMarker {
+    pub fn new(phase: Option<String>) -> Self {
+        let perf = match (env::var(CTL_FIFO_ENV), env::var(ACK_FIFO_ENV)) {
+            (Ok(ctl), Ok(ack)) => {
+                let ctl = OpenOptions::new().write(true).open(ctl);
+                let ack = File::open(ack);
+                match (ctl, ack) {
+                    (Ok(ctl), Ok(ack)) => Some((ctl, ack)),
+                    _ => {
+                        eprintln!(
+                            "Warning: could not open perf control fifos, recording whole run"
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        PhaseMarker { phase, perf }
+    }
+
+    pub fn begin(&mut self, phase: &str) {
+        if self.phase.as_deref() == Some(phase) {
+            eprintln!("[profile] begin {} (pid {})", phase, process::id());
+            self.perf_command("enable");
+        }
+    }
+
+    pub fn end(&mut self, phase: &str) {
+        if self.phase.as_deref() == Some(phase) {
+            self.perf_command("disable");
+            eprintln!("[profile] end {}", phase);
+        }
+    }
+
+    fn perf_command(&mut self, command: &str) {
+        if let Some((ctl, ack)) = self.perf.as_mut() {
+            // perf answers every command with "ack\n" once it has been applied.
+            let mut reply = [0u8; 4];
+            let result = writeln!(ctl, "{}", command).and_then(|_| ack.read_exact(&mut reply));
+            if let Err(e) = result {
+                eprintln!("Warning: perf control command '{}' failed: {}", command, e);
+            }
+        }
+    }
+}
+
+/// Re-executes this benchmark (minus `--perf-record`) under
+/// `perf record -D -1 --control`, with collection disabled until the chosen
+/// phase begins. Exits with the child's status.
+pub fn record(output: &str, args: &[String]) -> ! {
+    let mut child_args = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--perf-record" {
+            iter.next();
+        } else {
+            child_args.push(arg.clone());
+        }
+    }
+    // Without a phase there is nothing to bracket, so profile the traversal.
+    if !child_args.iter().any(|a| a == "--profile-phase") {
+        child_args.extend(["--profile-phase".to_string(), "traverse".to_string()]);
+    }
+
+    let dir = env::temp_dir().join(format!("linked_list_bench-perf-{}", process::id()));
+    let status = spawn_perf(&dir, output, &child_args);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    match status {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("Error: could not run perf record: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn spawn_perf(dir: &Path, output: &str, args: &[String]) -> io::Result<i32> {
+    if !cfg!(target_os = "linux") {
+        return Err(io::Error::other("perf is only available on Linux"));
+    }
+    std::fs::create_dir_all(dir)?;
+    let ctl = dir.join("ctl");
+    let ack = dir.join("ack");
+    for fifo in [&ctl, &ack] {
+        if !Command::new("mkfifo").arg(fifo).status()?.success() {
+            return Err(io::Error::other("mkfifo failed"));
+        }
+    }
+
+    // Hold both fifos open read-write so neither perf nor the benchmark
+    // blocks in open() waiting for the other end.
+    let _ctl_hold = OpenOptions::new().read(true).write(true).open(&ctl)?;
+    let _ack_hold = OpenOptions::new().read(true).write(true).open(&ack)?;
+
+    let status = Command::new("perf")
+        .args(["record", "-D", "-1", "-o", output])
+        .arg(format!(
+            "--control=fifo:{},{}",
+            ctl.display(),
+            ack.display()
+        ))
+        .arg("--")
+        .arg(env::current_exe()?)
+        .args(args)
+        .env(CTL_FIFO_ENV, &ctl)
+        .env(ACK_FIFO_ENV, &ack)
+        .status()?;
+    Ok(status.code().unwrap_or(1))
+}
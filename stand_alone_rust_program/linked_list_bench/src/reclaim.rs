@@ -0,0 +1,317 @@
+use std::io;
+use std::mem;
+use std::sync::Mutex;
+
+use crate::clock;
+use crate::lockfree::{self, Census, EpochStack, LeakyStack, Retired};
+use crate::locks;
+use crate::rng::Rng;
+use crate::stats::Summary;
+use crate::LinkedList;
+
+/// A stack of `usize` shared by every thread of a run, counting its live
+/// nodes in a [`Census`].
+trait Stack: Sync {
+    /// What a thread keeps for the stack while it works.
+    type Local: Default;
+    /// Bytes of one node.
+    const NODE_BYTES: usize;
+
+    fn new(census: &'static Census) -> Self;
+    fn push(&self, value: usize);
+    fn pop(&self, local: &mut Self::Local) -> Option<usize>;
+    /// Hands back a thread's `Local` once it has finished.
+    fn finish(&self, _local: Self::Local) {}
+    /// Frees whatever the stack left to be freed later, once it is gone.
+    fn settle(_census: &Census) {}
+}
+
+/// The std `Mutex`-guarded list the lock-free stacks are measured against:
+/// a pop frees its node on the spot.
+struct Locked {
+    list: Mutex<LinkedList<usize>>,
+    census: &'static Census,
+}
+
+impl Stack for Locked {
+    type Local = ();
+    const NODE_BYTES: usize = mem::size_of::<crate::Node<usize>>();
+
+    fn new(census: &'static Census) -> Self {
+        Locked {
+            list: Mutex::new(LinkedList::new()),
+            census,
+        }
+    }
+
+    fn push(&self, value: usize) {
+        self.census.allocated();
+        self.list.lock().unwrap().push(value);
+    }
+
+    fn pop(&self, _local: &mut ()) -> Option<usize> {
+        let value = self.list.lock().unwrap().pop();
+        if value.is_some() {
+            self.census.freed();
+        }
+        value
+    }
+}
+
+impl Drop for Locked {
+    fn drop(&mut self) {
+        let list = self.list.get_mut().unwrap();
+        while list.pop().is_some() {
+            self.census.freed();
+        }
+    }
+}
+
+impl Stack for LeakyStack<usize> {
+    type Local = Retired<usize>;
+    const NODE_BYTES: usize = LeakyStack::<usize>::NODE_BYTES;
+
+    fn new(census: &'static Census) -> Self {
+        LeakyStack::new(census)
+    }
+
+    fn push(&self, value: usize) {
+        LeakyStack::push(self, value)
+    }
+
+    fn pop(&self, local: &mut Retired<usize>) -> Option<usize> {
+        LeakyStack::pop(self, local)
+    }
+
+    fn finish(&self, local: Retired<usize>) {
+        self.retire(local)
+    }
+}
+
+impl Stack for EpochStack<usize> {
+    type Local = ();
+    const NODE_BYTES: usize = EpochStack::<usize>::NODE_BYTES;
+
+    fn new(census: &'static Census) -> Self {
+        EpochStack::new(census)
+    }
+
+    fn push(&self, value: usize) {
+        EpochStack::push(self, value)
+    }
+
+    fn pop(&self, _local: &mut ()) -> Option<usize> {
+        EpochStack::pop(self)
+    }
+
+    fn settle(census: &Census) {
+        lockfree::drain_epoch_garbage(census, 0);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Workload {
+    threads: usize,
+    ops: usize,
+    initial: usize,
+}
+
+/// One run of a workload on one stack.
+struct Run {
+    seconds: f64,
+    /// Each thread's cycles per operation.
+    cycles_per_op: Vec<f64>,
+    /// Most nodes allocated at once, the stack's own included.
+    peak_nodes: usize,
+    /// Popped nodes not yet freed when the last thread finished.
+    unreclaimed: usize,
+}
+
+type Measure = fn(Workload, &'static Census) -> io::Result<Run>;
+
+static LOCKED: Census = Census::new();
+static LEAKY: Census = Census::new();
+static EPOCH: Census = Census::new();
+
+const STACKS: [(&str, Measure, &Census, usize); 3] = [
+    ("mutex list", measure::<Locked>, &LOCKED, Locked::NODE_BYTES),
+    (
+        "leaky",
+        measure::<LeakyStack<usize>>,
+        &LEAKY,
+        <LeakyStack<usize> as Stack>::NODE_BYTES,
+    ),
+    (
+        "epoch",
+        measure::<EpochStack<usize>>,
+        &EPOCH,
+        <EpochStack<usize> as Stack>::NODE_BYTES,
+    ),
+];
+
+/// `reclaim [--threads <a,b,...>] [--ops <per thread>] [--initial <n>]
+/// [--iterations <k>]`: runs the same push/pop workload on a Treiber stack
+/// that never frees a popped node, one that frees them through
+/// crossbeam-epoch and a `Mutex`-guarded list, and reports each one's cost
+/// per operation next to its memory high-water mark. Leaking is the
+/// lock-free stack with reclamation free; the epoch stack's distance from
+/// it is what reclamation costs, in time and in memory still held.
+pub fn run(args: &[String]) {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads: Vec<usize> = std::iter::successors(Some(1), |&t| Some(t * 2))
+        .take_while(|&t| t <= (2 * cores).max(4))
+        .collect();
+    let mut ops: usize = 100_000;
+    let mut initial: usize = 1024;
+    let mut iterations: usize = 3;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--threads", Some(v)) => {
+                let parsed: Option<Vec<usize>> =
+                    v.split(',').map(|t| t.trim().parse().ok()).collect();
+                match parsed {
+                    Some(list) if !list.is_empty() && !list.contains(&0) => threads = list,
+                    _ => {
+                        eprintln!("Error: bad --threads '{}' (expected e.g. 1,2,4,8)", v);
+                        return;
+                    }
+                }
+            }
+            ("--ops", Some(v)) => ops = v.parse().unwrap_or(ops),
+            ("--initial", Some(v)) => initial = v.parse().unwrap_or(initial),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete reclaim option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let ops = ops.max(1);
+    let iterations = iterations.max(1);
+
+    println!("--- Lock-Free Stack Reclamation ---");
+    println!(
+        "{} ops per thread, half push and half pop, on a stack of {} nodes; {} iterations, available parallelism {}",
+        ops, initial, iterations, cores
+    );
+    println!(
+        "{:>7} {:<11} {:>10} {:>10} {:>9} {:>11} {:>10} {:>12}",
+        "Threads", "Stack", "Mops/s", "cyc/op", "vs mutex", "Peak nodes", "Peak KiB", "Unreclaimed"
+    );
+    for &count in &threads {
+        let workload = Workload {
+            threads: count,
+            ops,
+            initial,
+        };
+        // Stacks take turns within each iteration, so drift over the run
+        // hits all of them alike.
+        let mut runs: Vec<Vec<Run>> = STACKS.iter().map(|_| Vec::new()).collect();
+        for _ in 0..iterations {
+            for ((name, measure, census, _), runs) in STACKS.iter().zip(&mut runs) {
+                match measure(workload, census) {
+                    Ok(run) => runs.push(run),
+                    Err(e) => {
+                        eprintln!(
+                            "Error: could not run {} with {} threads: {}",
+                            name, count, e
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+        let mut mutex_cycles = None;
+        for ((name, _, _, node_bytes), runs) in STACKS.iter().zip(&runs) {
+            let throughput: Vec<f64> = runs
+                .iter()
+                .map(|r| (count * ops) as f64 / r.seconds.max(f64::MIN_POSITIVE) / 1e6)
+                .collect();
+            let cycles: Vec<f64> = runs
+                .iter()
+                .flat_map(|r| r.cycles_per_op.iter().copied())
+                .collect();
+            let cycles = Summary::of(&cycles).median;
+            let baseline = *mutex_cycles.get_or_insert(cycles);
+            // The worst iteration: a high-water mark is about the peak.
+            let peak = runs.iter().map(|r| r.peak_nodes).max().unwrap_or(0);
+            let unreclaimed = runs.iter().map(|r| r.unreclaimed).max().unwrap_or(0);
+            println!(
+                "{:>7} {:<11} {:>10.2} {:>10.1} {:>8.2}x {:>11} {:>10.1} {:>12}",
+                count,
+                name,
+                Summary::of(&throughput).median,
+                cycles,
+                cycles / baseline.max(f64::MIN_POSITIVE),
+                peak,
+                (peak * node_bytes) as f64 / 1024.0,
+                unreclaimed
+            );
+        }
+    }
+    println!("cyc/op: median over threads and iterations of each thread's cycles per operation");
+    println!("Peak and Unreclaimed: nodes, the largest over the iterations; Unreclaimed counts popped nodes not yet freed when the last thread finished");
+    println!("Nodes are counted in an untimed run before each timed one, so counting costs the timed runs nothing");
+}
+
+/// Runs `workload` twice, each on a fresh `S`: once counted, for the node
+/// counts, then once timed with the census paused, since its shared adds
+/// would contend like the stack's own head and be timed with it.
+fn measure<S: Stack>(workload: Workload, census: &'static Census) -> io::Result<Run> {
+    census.reset();
+    let counted = race::<S>(workload, census)?;
+    census.pause(true);
+    let timed = race::<S>(workload, census);
+    census.pause(false);
+    Ok(Run {
+        peak_nodes: counted.peak_nodes,
+        unreclaimed: counted.unreclaimed,
+        ..timed?
+    })
+}
+
+/// Runs `workload` once on a fresh `S`.
+fn race<S: Stack>(workload: Workload, census: &'static Census) -> io::Result<Run> {
+    let stack = S::new(census);
+    for i in 0..workload.initial {
+        stack.push(i);
+    }
+    let (results, seconds) = locks::race(workload.threads, "reclaim", |index| {
+        worker(&stack, workload, index as u64 + 1)
+    })?;
+    let pushes: usize = results.iter().map(|r| r.0).sum();
+    let pops: usize = results.iter().map(|r| r.1).sum();
+    let length = workload.initial + pushes - pops;
+    let run = Run {
+        seconds,
+        cycles_per_op: results.iter().map(|r| r.2).collect(),
+        peak_nodes: census.peak(),
+        unreclaimed: census.live().saturating_sub(length),
+    };
+    drop(stack);
+    S::settle(census);
+    Ok(run)
+}
+
+/// One thread's share of a run: its pushes, its successful pops and its
+/// cycles per operation.
+fn worker<S: Stack>(stack: &S, workload: Workload, seed: u64) -> (usize, usize, f64) {
+    let mut rng = Rng::new(seed);
+    let mut local = S::Local::default();
+    let (mut pushes, mut pops) = (0, 0);
+    let first = clock::timestamp();
+    for _ in 0..workload.ops {
+        if rng.below(2) == 0 {
+            stack.push(rng.next_u64() as usize);
+            pushes += 1;
+        } else if let Some(value) = stack.pop(&mut local) {
+            std::hint::black_box(value);
+            pops += 1;
+        }
+    }
+    let cycles = clock::timestamp().saturating_sub(first) as f64 / workload.ops as f64;
+    stack.finish(local);
+    (pushes, pops, cycles)
+}
@@ -0,0 +1,451 @@
+use std::fmt::Write;
+
+use crate::bench::{self, BenchResult};
+use crate::metadata::Metadata;
+use crate::plot;
+use crate::stats::Summary;
+
+/// How results are written to stdout.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Html,
+    /// A GitHub-flavoured markdown table, for pasting into an issue or PR.
+    Markdown,
+    /// One JSON object per line, written as each benchmark completes.
+    Ndjson,
+    /// The array github-action-benchmark's `customSmallerIsBetter` tool
+    /// reads.
+    GithubBenchmark,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "html" => Some(Format::Html),
+            "markdown" => Some(Format::Markdown),
+            "ndjson" => Some(Format::Ndjson),
+            "github-benchmark" => Some(Format::GithubBenchmark),
+            _ => None,
+        }
+    }
+}
+
+/// Renders a self-contained HTML report: metadata, the results table, the
+/// sweep chart (when there is more than one size; one curve per run of
+/// consecutive results with the same benchmark name) and a histogram for
+/// every result with more than one sample. Charts are inline SVG so the file can be
+/// attached to an issue as-is.
+pub fn html(title: &str, metadata: &Metadata, results: &[BenchResult]) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n\
+         body {{ font-family: sans-serif; margin: 2em; color: #222; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 1.5em; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 4px 10px; }}\n\
+         td.num {{ text-align: right; font-family: monospace; }}\n\
+         th {{ background: #f0f0f0; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(title),
+        escape(title)
+    );
+
+    out.push_str("<h2>Metadata</h2>\n<table>\n");
+    for (key, value) in [
+        ("Host", &metadata.host),
+        ("CPU", &metadata.cpu),
+        ("Commit", &metadata.git_commit),
+    ] {
+        let _ = writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", key, escape(value));
+    }
+    out.push_str("</table>\n");
+
+    out.push_str(
+        "<h2>Results</h2>\n<table>\n<tr><th>Benchmark</th><th>Nodes</th><th>Iterations</th>\
+                  <th>ns/node (median)</th><th>cycles/node min</th><th>median</th><th>mean</th>\
+                  <th>stddev</th><th>Mnodes/s</th><th>GB/s</th><th>Flagged</th></tr>\n",
+    );
+    for result in results {
+        let ns = result.ns_per_node();
+        let cycles = result.cycles_per_node();
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&result.name),
+            result.nodes,
+            result.samples.len(),
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.min, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.mean, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            bench::stat(&cycles, result.nodes_per_second().median / 1e6, 1),
+            bench::stat(&cycles, result.bytes_per_second().median / 1e9, 2),
+            result.flagged()
+        );
+    }
+    out.push_str("</table>\n");
+
+    let series: Vec<(&str, &[BenchResult])> = results
+        .chunk_by(|a, b| a.name == b.name)
+        .map(|run| (run[0].name.as_str(), run))
+        .collect();
+    if series.iter().any(|(_, run)| run.len() > 1) {
+        out.push_str("<h2>Sweep</h2>\n");
+        push_chart(&mut out, plot::cycles_per_node_svg(&series));
+    }
+
+    let sampled: Vec<&BenchResult> = results.iter().filter(|r| r.samples.len() > 1).collect();
+    if !sampled.is_empty() {
+        out.push_str("<h2>Histograms</h2>\n");
+        for result in sampled {
+            let _ = writeln!(
+                out,
+                "<details open>\n<summary>{} @ {} nodes</summary>",
+                escape(&result.name),
+                result.nodes
+            );
+            push_chart(&mut out, plot::histogram_svg(result));
+            out.push_str("</details>\n");
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// The results as a plain-text table, one row per result, the columns of
+/// the sweep's.
+pub fn text(results: &[BenchResult]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<24} {:>12} {:>6} {:>12} {:>14} {:>14} {:>10} {:>8} {:>8}",
+        "Benchmark",
+        "Nodes",
+        "Iters",
+        "ns/node",
+        "cycles/node",
+        "stddev",
+        "Mnodes/s",
+        "GB/s",
+        "Flagged"
+    );
+    for result in results {
+        let cycles = result.cycles_per_node();
+        let _ = writeln!(
+            out,
+            "{:<24} {:>12} {:>6} {:>12} {:>14} {:>14} {:>10} {:>8} {:>8}",
+            result.name,
+            result.nodes,
+            result.samples.len(),
+            bench::stat(&cycles, result.ns_per_node().median, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            bench::stat(&cycles, result.nodes_per_second().median / 1e6, 1),
+            bench::stat(&cycles, result.bytes_per_second().median / 1e9, 2),
+            result.flagged()
+        );
+    }
+    out
+}
+
+/// Renders the metadata and the results table of [`html`] as GitHub
+/// markdown; no charts, which a markdown file can't carry inline.
+pub fn markdown(title: &str, metadata: &Metadata, results: &[BenchResult]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", markdown_cell(title));
+    out.push_str("| | |\n|---|---|\n");
+    for (key, value) in [
+        ("Host", &metadata.host),
+        ("CPU", &metadata.cpu),
+        ("Commit", &metadata.git_commit),
+    ] {
+        let _ = writeln!(out, "| {} | {} |", key, markdown_cell(value));
+    }
+    out.push_str(
+        "\n## Results\n\n| Benchmark | Nodes | Iterations | ns/node (median) | cycles/node min \
+         | median | mean | stddev | Mnodes/s | GB/s | Flagged |\n\
+         |---|---:|---:|---:|---:|---:|---:|---:|---:|---:|---:|\n",
+    );
+    for result in results {
+        let ns = result.ns_per_node();
+        let cycles = result.cycles_per_node();
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+            markdown_cell(&result.name),
+            result.nodes,
+            result.samples.len(),
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.min, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.mean, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            bench::stat(&cycles, result.nodes_per_second().median / 1e6, 1),
+            bench::stat(&cycles, result.bytes_per_second().median / 1e9, 2),
+            result.flagged()
+        );
+    }
+    out
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn push_chart(out: &mut String, svg: Result<String, Box<dyn std::error::Error>>) {
+    match svg {
+        Ok(svg) => {
+            out.push_str("<div>\n");
+            out.push_str(&svg);
+            out.push_str("\n</div>\n");
+        }
+        Err(e) => {
+            let _ = writeln!(out, "<p>Chart unavailable: {}</p>", escape(&e.to_string()));
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// One result as a single-line JSON object carrying everything needed to
+/// interpret it on its own: the machine, the summary statistics, the layout
+/// and every raw sample. Statistics with no unflagged samples behind them
+/// are null, as are NaNs.
+pub fn ndjson(title: &str, metadata: &Metadata, result: &BenchResult) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{{\"run\":{},\"host\":{},\"cpu\":{},\"commit\":{},\"benchmark\":{},\"nodes\":{}",
+        json_string(title),
+        json_string(&metadata.host),
+        json_string(&metadata.cpu),
+        json_string(&metadata.git_commit),
+        json_string(&result.name),
+        result.nodes
+    );
+    let _ = write!(
+        out,
+        ",\"iterations\":{},\"flagged\":{},\"ns_per_node\":{},\"cycles_per_node\":{}",
+        result.samples.len(),
+        result.flagged(),
+        json_summary(&result.ns_per_node()),
+        json_summary(&result.cycles_per_node())
+    );
+    let _ = write!(
+        out,
+        ",\"nodes_per_second\":{},\"bytes_per_second\":{},\"layout\":{{\"median_stride\":{},\"adjacent_fraction\":{}}}",
+        json_median(&result.nodes_per_second()),
+        json_median(&result.bytes_per_second()),
+        result.layout.median_stride,
+        json_number(result.layout.adjacent_fraction)
+    );
+    out.push_str(",\"samples\":[");
+    for (index, sample) in result.samples.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "{{\"visited\":{},\"time_ns\":{},\"cycles\":{},\"anomaly\":{}}}",
+            sample.visited,
+            sample.time.as_nanos(),
+            sample.cycles,
+            sample
+                .anomaly
+                .map_or_else(|| "null".to_string(), |a| json_string(&a.to_string()))
+        );
+    }
+    out.push_str("]}");
+    out
+}
+
+/// Renders results for github-action-benchmark: one entry per result, valued
+/// in median cycles per node (smaller is better) with the spread as its
+/// range. Names include the node count so every size charts separately.
+/// Results with every sample flagged are left out: they have no value to
+/// chart, and a zero would read as a huge improvement.
+pub fn github_benchmark(metadata: &Metadata, results: &[BenchResult]) -> String {
+    let entries: Vec<String> = results
+        .iter()
+        .filter_map(|result| {
+            let cycles = result.cycles_per_node();
+            if cycles.count == 0 {
+                return None;
+            }
+            Some(format!(
+                "  {{\"name\":{},\"unit\":\"cycles/node\",\"value\":{},\"range\":{},\"extra\":{}}}",
+                json_string(&format!("{} ({} nodes)", result.name, result.nodes)),
+                json_number(cycles.median),
+                json_string(&format!("± {:.2}", cycles.stddev)),
+                json_string(&format!(
+                    "{:.2} ns/node median over {} of {} iterations\nhost {}, cpu {}, commit {}",
+                    result.ns_per_node().median,
+                    cycles.count,
+                    result.samples.len(),
+                    metadata.host,
+                    metadata.cpu,
+                    metadata.git_commit
+                ))
+            ))
+        })
+        .collect();
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
+fn json_summary(summary: &Summary) -> String {
+    if summary.count == 0 {
+        return "{\"min\":null,\"median\":null,\"mean\":null,\"stddev\":null}".to_string();
+    }
+    format!(
+        "{{\"min\":{},\"median\":{},\"mean\":{},\"stddev\":{}}}",
+        json_number(summary.min),
+        json_number(summary.median),
+        json_number(summary.mean),
+        json_number(summary.stddev)
+    )
+}
+
+fn json_median(summary: &Summary) -> String {
+    if summary.count == 0 {
+        "null".to_string()
+    } else {
+        json_number(summary.median)
+    }
+}
+
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Sample;
+    use crate::cache::Layout;
+    use crate::clock::Anomaly;
+    use std::time::Duration;
+
+    fn metadata() -> Metadata {
+        Metadata {
+            host: "bench-host".to_string(),
+            cpu: "Some CPU @ 3.00GHz".to_string(),
+            git_commit: "0123abc".to_string(),
+        }
+    }
+
+    /// One 1024-node result of a single sample, flagged with `anomaly`.
+    fn result(name: &str, anomaly: Option<Anomaly>) -> BenchResult {
+        BenchResult {
+            name: name.to_string(),
+            nodes: 1024,
+            samples: vec![Sample {
+                visited: 1024,
+                time: Duration::from_nanos(1500),
+                cycles: 4500,
+                anomaly,
+            }],
+            layout: Layout {
+                median_stride: 32,
+                adjacent_fraction: 0.75,
+                misses: None,
+            },
+        }
+    }
+
+    #[test]
+    fn fully_flagged_results_have_null_statistics() {
+        let line = ndjson(
+            "sweep",
+            &metadata(),
+            &result("traverse", Some(Anomaly::Backwards)),
+        );
+        let nulls = "{\"min\":null,\"median\":null,\"mean\":null,\"stddev\":null}";
+        assert!(
+            line.contains(&format!("\"ns_per_node\":{}", nulls)),
+            "{}",
+            line
+        );
+        assert!(
+            line.contains(&format!("\"cycles_per_node\":{}", nulls)),
+            "{}",
+            line
+        );
+        assert!(line.contains("\"nodes_per_second\":null"), "{}", line);
+        assert!(line.contains("\"bytes_per_second\":null"), "{}", line);
+    }
+
+    #[test]
+    fn tables_count_every_sample_and_the_flagged_ones() {
+        let results = [result("flagged", Some(Anomaly::Backwards))];
+        let markdown = markdown("sweep", &metadata(), &results);
+        assert!(
+            markdown.contains("| flagged | 1024 | 1 | flagged |"),
+            "{}",
+            markdown
+        );
+        assert!(markdown.contains(" | flagged | 1 |\n"), "{}", markdown);
+        let html = html("sweep", &metadata(), &results);
+        assert!(
+            html.contains("<td class=\"num\">1024</td><td class=\"num\">1</td>"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<td class=\"num\">flagged</td><td class=\"num\">1</td></tr>"),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn github_benchmark_leaves_out_fully_flagged_results() {
+        let results = [
+            result("traverse", None),
+            result("flagged", Some(Anomaly::Backwards)),
+        ];
+        let json = github_benchmark(&metadata(), &results);
+        assert!(
+            json.contains("\"name\":\"traverse (1024 nodes)\""),
+            "{}",
+            json
+        );
+        assert!(!json.contains("flagged"), "{}", json);
+        assert_eq!(json.matches("\"unit\"").count(), 1, "{}", json);
+    }
+}
@@ -0,0 +1,223 @@
+use std::fs;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::bench::{BenchResult, Sample};
+use crate::cache::Layout;
+use crate::clock::Anomaly;
+use crate::metadata::Metadata;
+
+const HEADER: &str = "# linked_list_bench results v1";
+
+/// Writes raw samples to a line-oriented, tab-separated file:
+///
+/// ```text
+/// meta    <key>    <value>
+/// result  <name>   <nodes>   <median stride>   <adjacent fraction>
+/// sample  <visited>   <time ns>   <cycles>   <anomaly or ->
+/// ```
+///
+/// `meta` keys are `host`, `cpu`, `commit` and `command`, the arguments
+/// the run was started with; `sample` lines belong to the `result` line
+/// above them.
+pub fn save(path: &str, metadata: &Metadata, results: &[BenchResult]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    write(&mut out, metadata, results)?;
+    out.flush()
+}
+
+/// Writes what [`save`] puts in a file to `out`.
+fn write(out: &mut impl Write, metadata: &Metadata, results: &[BenchResult]) -> io::Result<()> {
+    writeln!(out, "{}", HEADER)?;
+    writeln!(out, "meta\thost\t{}", metadata.host)?;
+    writeln!(out, "meta\tcpu\t{}", metadata.cpu)?;
+    writeln!(out, "meta\tcommit\t{}", metadata.git_commit)?;
+    writeln!(out, "meta\tcommand\t{}", command())?;
+    for result in results {
+        writeln!(
+            out,
+            "result\t{}\t{}\t{}\t{}",
+            result.name, result.nodes, result.layout.median_stride, result.layout.adjacent_fraction
+        )?;
+        for sample in &result.samples {
+            writeln!(
+                out,
+                "sample\t{}\t{}\t{}\t{}",
+                sample.visited,
+                sample.time.as_nanos(),
+                sample.cycles,
+                sample
+                    .anomaly
+                    .map_or_else(|| "-".to_string(), |a| a.to_string())
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// This process's arguments after the binary, space-separated.
+fn command() -> String {
+    std::env::args()
+        .skip(1)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(['\t', '\n'], " ")
+}
+
+/// Reads a file written by [`save`].
+pub fn load(path: &str) -> io::Result<(Metadata, Vec<BenchResult>)> {
+    parse(&fs::read_to_string(path)?, path)
+}
+
+/// Parses the contents of a file written by [`save`]; `source` names it in
+/// errors.
+pub fn parse(text: &str, source: &str) -> io::Result<(Metadata, Vec<BenchResult>)> {
+    let invalid = |line: usize, what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}:{}: {}", source, line + 1, what),
+        )
+    };
+
+    if text.lines().next() != Some(HEADER) {
+        return Err(invalid(0, "not a linked_list_bench results file"));
+    }
+
+    let mut metadata = Metadata {
+        host: String::new(),
+        cpu: String::new(),
+        git_commit: String::new(),
+    };
+    let mut results: Vec<BenchResult> = Vec::new();
+    for (number, line) in text.lines().enumerate().skip(1) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields.as_slice() {
+            ["meta", "host", value] => metadata.host = value.to_string(),
+            ["meta", "cpu", value] => metadata.cpu = value.to_string(),
+            ["meta", "commit", value] => metadata.git_commit = value.to_string(),
+            ["meta", ..] => {}
+            ["result", name, nodes, stride, adjacent] => results.push(BenchResult {
+                name: name.to_string(),
+                nodes: nodes
+                    .parse()
+                    .map_err(|_| invalid(number, "bad node count"))?,
+                samples: Vec::new(),
+                layout: Layout {
+                    median_stride: stride.parse().map_err(|_| invalid(number, "bad stride"))?,
+                    adjacent_fraction: adjacent
+                        .parse()
+                        .map_err(|_| invalid(number, "bad adjacency"))?,
+                    misses: None,
+                },
+            }),
+            ["sample", visited, time_ns, cycles, rest @ ..] if rest.len() <= 1 => {
+                let anomaly = match rest.first() {
+                    None | Some(&"-") => None,
+                    Some(text) => {
+                        Some(Anomaly::parse(text).ok_or_else(|| invalid(number, "bad anomaly"))?)
+                    }
+                };
+                let result = results
+                    .last_mut()
+                    .ok_or_else(|| invalid(number, "sample before any result"))?;
+                result.samples.push(Sample {
+                    visited: visited
+                        .parse()
+                        .map_err(|_| invalid(number, "bad visited count"))?,
+                    time: Duration::from_nanos(
+                        time_ns.parse().map_err(|_| invalid(number, "bad time"))?,
+                    ),
+                    cycles: cycles
+                        .parse()
+                        .map_err(|_| invalid(number, "bad cycle count"))?,
+                    anomaly,
+                });
+            }
+            [""] => {}
+            _ => return Err(invalid(number, "unrecognised line")),
+        }
+    }
+    Ok((metadata, results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_then_parse_round_trips() {
+        let metadata = Metadata {
+            host: "bench-host".to_string(),
+            cpu: "Some CPU @ 3.00GHz".to_string(),
+            git_commit: "0123abc".to_string(),
+        };
+        let sample = |visited, nanos, cycles, anomaly| Sample {
+            visited,
+            time: Duration::from_nanos(nanos),
+            cycles,
+            anomaly,
+        };
+        let results = vec![
+            BenchResult {
+                name: "traverse".to_string(),
+                nodes: 1024,
+                samples: vec![
+                    sample(1024, 1500, 4500, None),
+                    sample(1024, 1700, 0, Some(Anomaly::Backwards)),
+                    sample(1024, 1600, 4800, Some(Anomaly::Migrated { from: 0, to: 3 })),
+                ],
+                layout: Layout {
+                    median_stride: 32,
+                    adjacent_fraction: 0.75,
+                    misses: None,
+                },
+            },
+            BenchResult {
+                name: "vec".to_string(),
+                nodes: 1,
+                samples: Vec::new(),
+                layout: Layout {
+                    median_stride: 0,
+                    adjacent_fraction: 0.0,
+                    misses: None,
+                },
+            },
+        ];
+
+        let mut text = Vec::new();
+        write(&mut text, &metadata, &results).unwrap();
+        let (loaded_metadata, loaded) =
+            parse(&String::from_utf8(text).unwrap(), "saved.tsv").unwrap();
+
+        assert_eq!(loaded_metadata.host, metadata.host);
+        assert_eq!(loaded_metadata.cpu, metadata.cpu);
+        assert_eq!(loaded_metadata.git_commit, metadata.git_commit);
+        assert_eq!(loaded.len(), results.len());
+        for (loaded, saved) in loaded.iter().zip(&results) {
+            assert_eq!(loaded.name, saved.name);
+            assert_eq!(loaded.nodes, saved.nodes);
+            assert_eq!(loaded.layout.median_stride, saved.layout.median_stride);
+            assert_eq!(
+                loaded.layout.adjacent_fraction,
+                saved.layout.adjacent_fraction
+            );
+            assert_eq!(loaded.samples.len(), saved.samples.len());
+            for (loaded, saved) in loaded.samples.iter().zip(&saved.samples) {
+                assert_eq!(loaded.visited, saved.visited);
+                assert_eq!(loaded.time, saved.time);
+                assert_eq!(loaded.cycles, saved.cycles);
+                assert_eq!(loaded.anomaly, saved.anomaly);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_files() {
+        assert!(parse("not results\n", "x").is_err());
+        let orphan = format!("{}\nsample\t1\t2\t3\t-\n", HEADER);
+        assert!(parse(&orphan, "x").is_err());
+        let bad_nodes = format!("{}\nresult\tt\tmany\t0\t0\n", HEADER);
+        let error = parse(&bad_nodes, "file.tsv").err().unwrap();
+        assert_eq!(error.to_string(), "file.tsv:2: bad node count");
+    }
+}
@@ -0,0 +1,226 @@
+use crate::bench;
+use crate::clock;
+use crate::stats::Summary;
+use crate::{LinkedList, Node};
+
+/// A segment counts as slow at this multiple of the median segment.
+const SLOW: f64 = 1.5;
+
+const BAR_WIDTH: usize = 40;
+
+/// How the traversed list is laid out in memory.
+#[derive(Clone, Copy)]
+enum Layout {
+    /// Nodes in allocation order.
+    Sequential,
+    /// Links in a random order of the node addresses.
+    Shuffled,
+    /// The first half in allocation order, the second half allocated into
+    /// the holes of a fragmented heap.
+    Fragmented,
+}
+
+impl Layout {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "sequential" => Some(Layout::Sequential),
+            "shuffled" => Some(Layout::Shuffled),
+            "fragmented" => Some(Layout::Fragmented),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Layout::Sequential => "sequential",
+            Layout::Shuffled => "shuffled",
+            Layout::Fragmented => "fragmented",
+        }
+    }
+
+    fn build(self, nodes: usize) -> LinkedList<usize> {
+        match self {
+            Layout::Sequential => bench::build(nodes),
+            Layout::Shuffled => bench::build_shuffled(nodes, nodes as u64),
+            Layout::Fragmented => build_fragmented(nodes),
+        }
+    }
+}
+
+/// `segments [--nodes <n>] [--every <k>] [--layout sequential|shuffled|fragmented]
+/// [--iterations <i>]`: reads the cycle counter every K nodes during a
+/// traversal and reports cycles per node for each K-node segment, the
+/// median over the iterations. A whole-list average can't tell a uniformly
+/// slow list from one that is fast apart from a slow stretch; this can.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 20;
+    let mut every: Option<usize> = None;
+    let mut layout = Layout::Fragmented;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--every", Some(v)) => every = v.parse().ok().or(every),
+            ("--layout", Some(v)) => match Layout::parse(v) {
+                Some(l) => layout = l,
+                None => {
+                    eprintln!(
+                        "Error: unknown layout '{}' (sequential, shuffled, fragmented)",
+                        v
+                    );
+                    return;
+                }
+            },
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete segments option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+    // 32 segments unless asked otherwise: enough to localise a slow stretch
+    // while each segment still spans many timestamp costs.
+    let every = every.unwrap_or(nodes / 32).clamp(1, nodes);
+    let segments = nodes.div_ceil(every);
+
+    let list = layout.build(nodes);
+    let overhead = clock::timestamp_cost();
+    let mut stamps = Vec::with_capacity(segments + 1);
+    // per_segment[s] holds segment s's cycles per node from each unflagged
+    // traversal.
+    let mut per_segment: Vec<Vec<f64>> = vec![Vec::with_capacity(iterations); segments];
+    let mut flagged = 0;
+    for _ in 0..iterations.max(1) {
+        let start = clock::start();
+        stamped_traversal(&list, every, &mut stamps);
+        let reading = clock::stop(&start);
+        if reading.anomaly.is_some() || stamps.len() != segments + 1 {
+            flagged += 1;
+            continue;
+        }
+        for (segment, pair) in stamps.windows(2).enumerate() {
+            let length = segment_length(segment, every, nodes);
+            let cycles = pair[1].saturating_sub(pair[0]) as f64 - overhead;
+            per_segment[segment].push(cycles.max(0.0) / length as f64);
+        }
+    }
+
+    println!("--- Intra-Traversal Latency ---");
+    println!(
+        "{} nodes ({}), a timestamp every {} nodes, median of {} traversals",
+        nodes,
+        layout.name(),
+        every,
+        iterations.max(1) - flagged
+    );
+    println!(
+        "Timestamp cost: {:.1} cycles, subtracted from each segment",
+        overhead
+    );
+    if flagged == iterations.max(1) {
+        println!("Every traversal was flagged (migration or clock anomaly); no segments to show");
+        return;
+    }
+
+    let medians: Vec<f64> = per_segment.iter().map(|c| Summary::of(c).median).collect();
+    let typical = Summary::of(&medians).median;
+    let slowest = medians.iter().cloned().fold(0.0, f64::max);
+    println!(
+        "{:>8} {:>21} {:>12}  (bar scaled to the slowest, ! = slow)",
+        "Segment", "Nodes", "cycles/node"
+    );
+    for (segment, &cycles) in medians.iter().enumerate() {
+        let first = segment * every;
+        let last = first + segment_length(segment, every, nodes) - 1;
+        let bar = (cycles / slowest.max(f64::MIN_POSITIVE) * BAR_WIDTH as f64).round() as usize;
+        println!(
+            "{:>8} {:>10}-{:<10} {:>12.2}  {}{}",
+            segment,
+            first,
+            last,
+            cycles,
+            "#".repeat(bar),
+            if cycles > typical * SLOW { " !" } else { "" }
+        );
+    }
+
+    let slow: Vec<usize> = (0..segments)
+        .filter(|&s| medians[s] > typical * SLOW)
+        .collect();
+    let fastest = medians.iter().cloned().fold(f64::INFINITY, f64::min);
+    println!(
+        "\nSegments: fastest {:.2}, median {:.2}, slowest {:.2} cycles/node ({:.2}x median)",
+        fastest,
+        typical,
+        slowest,
+        slowest / typical.max(f64::MIN_POSITIVE)
+    );
+    match (slow.first(), slow.last()) {
+        (Some(&first), Some(&last)) => println!(
+            "Concentrated: {} of {} segments are over {}x the median, between nodes {} and {}",
+            slow.len(),
+            segments,
+            SLOW,
+            first * every,
+            last * every + segment_length(last, every, nodes) - 1
+        ),
+        _ => println!("Uniform: no segment is over {}x the median", SLOW),
+    }
+}
+
+/// Nodes in `segment`: `every`, except for a shorter last one.
+fn segment_length(segment: usize, every: usize, nodes: usize) -> usize {
+    every.min(nodes - segment * every)
+}
+
+/// Walks the list once, reading the cycle counter before the first node,
+/// after every `every`th node and after the last. `stamps` is cleared and
+/// reused so no allocation happens inside the traversal.
+fn stamped_traversal(list: &LinkedList<usize>, every: usize, stamps: &mut Vec<u64>) {
+    stamps.clear();
+    let mut current = &list.head;
+    let mut visited = 0;
+    let mut next_stamp = every;
+    stamps.push(clock::timestamp());
+    while let Some(node) = current {
+        std::hint::black_box(&node.data);
+        visited += 1;
+        current = &node.next;
+        if visited == next_stamp {
+            stamps.push(clock::timestamp());
+            next_stamp += every;
+        }
+    }
+    if visited % every != 0 {
+        stamps.push(clock::timestamp());
+    }
+}
+
+/// A list whose first half is allocated in order and whose second half is
+/// allocated after the heap around it has been fragmented: equally sized
+/// spacer allocations are made and every other one freed, so the later
+/// nodes land in scattered holes the way they would in a long-running
+/// process. Traversal visits nodes in allocation order.
+fn build_fragmented(nodes: usize) -> LinkedList<usize> {
+    let node = |data: usize| Box::new(Node { data, next: None });
+    let half = nodes / 2;
+    let mut boxes: Vec<Box<Node<usize>>> = Vec::with_capacity(nodes);
+    boxes.extend((0..half).map(node));
+
+    let mut spacers: Vec<Option<Box<Node<usize>>>> =
+        (0..2 * (nodes - half)).map(|i| Some(node(i))).collect();
+    for spacer in spacers.iter_mut().step_by(2) {
+        *spacer = None;
+    }
+    boxes.extend((half..nodes).map(node));
+    drop(spacers);
+
+    let mut list = LinkedList::new();
+    for node in boxes.into_iter().rev() {
+        list.push_node(node);
+    }
+    list
+}
@@ -0,0 +1,270 @@
+use crate::arena::ArenaList;
+use crate::bench;
+
+/// `simd [--min <bytes>] [--max <bytes>] [--iterations <n>]`: sums the
+/// payloads of the contiguous structures with the plain scalar loop and with
+/// an explicit SIMD kernel (AVX2 when the CPU has it, NEON on aarch64). While
+/// the data fits in cache the SIMD kernel should win by its vector width;
+/// once both run at the same speed the sum is memory-bound, and no amount of
+/// compute will make traversal faster.
+///
+/// The "scalar" loop is whatever the compiler makes of a simple fold at the
+/// baseline target features, which on x86_64 and i686 already means SSE2. The
+/// comparison is therefore against what safe, portable code gets for free.
+pub fn run(args: &[String]) {
+    let mut min_bytes: usize = 1 << 14;
+    let mut max_bytes: usize = 1 << 26;
+    let mut iterations: usize = 10;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--min", Some(v)) => min_bytes = v.parse().unwrap_or(min_bytes),
+            ("--max", Some(v)) => max_bytes = v.parse().unwrap_or(max_bytes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete simd option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    let Some(kernel) = Kernel::detect() else {
+        eprintln!("Error: no SIMD kernel for this CPU (needs AVX2 on x86, or aarch64)");
+        return;
+    };
+
+    println!("--- SIMD vs Scalar Payload Sum ({}) ---", kernel.name());
+    println!(
+        "{:<10} {:>12} {:>12} {:>14} {:>14} {:>8} {:>10}  Regime",
+        "Structure", "Bytes", "Elements", "scalar cyc/el", "simd cyc/el", "Speedup", "simd GB/s"
+    );
+
+    let mut bytes = min_bytes.max(64);
+    while bytes <= max_bytes {
+        let elements = bytes / std::mem::size_of::<u64>();
+        let vec: Vec<u64> = (0..elements as u64).collect();
+        compare(
+            "vec",
+            bytes,
+            elements,
+            iterations,
+            sum_scalar(&vec),
+            || sum_scalar(&vec),
+            || kernel.sum(&vec),
+        );
+
+        // Same byte budget: every arena node is a payload and a link. Order
+        // doesn't matter for a sum, so both kernels stream over the storage;
+        // walking the links gives the reference result.
+        let mut arena = ArenaList::new();
+        for i in 0..(elements / 2) as u64 {
+            arena.push(i);
+        }
+        compare(
+            "arena",
+            bytes,
+            arena.nodes.len(),
+            iterations,
+            arena.sum_by_links(),
+            || {
+                arena
+                    .nodes
+                    .iter()
+                    .fold(0u64, |sum, node| sum.wrapping_add(node.data))
+            },
+            || kernel.sum_even(arena.words()),
+        );
+
+        match bytes.checked_mul(2) {
+            Some(next) => bytes = next,
+            None => break,
+        }
+    }
+}
+
+/// Times both kernels on one structure, checks them against the reference
+/// sum, and prints a row.
+fn compare(
+    structure: &str,
+    bytes: usize,
+    elements: usize,
+    iterations: usize,
+    expected: u64,
+    scalar: impl Fn() -> u64,
+    simd: impl Fn() -> u64,
+) {
+    for (kernel, actual) in [("scalar", scalar()), ("SIMD", simd())] {
+        if actual != expected {
+            eprintln!(
+                "Error: {} {} sum {} does not match reference sum {}",
+                structure, kernel, actual, expected
+            );
+            std::process::exit(2);
+        }
+    }
+
+    let scalar_samples = bench::time_repeated(iterations, elements, &scalar);
+    let simd_samples = bench::time_repeated(iterations, elements, &simd);
+    let (_, scalar_cycles, _) = bench::per_operation(&scalar_samples);
+    let (simd_ns, simd_cycles, _) = bench::per_operation(&simd_samples);
+
+    let speedup = scalar_cycles.median / simd_cycles.median.max(f64::MIN_POSITIVE);
+    let bytes_per_element = bytes as f64 / elements.max(1) as f64;
+    let gb_per_s = bytes_per_element / simd_ns.median.max(f64::MIN_POSITIVE);
+    println!(
+        "{:<10} {:>12} {:>12} {:>14} {:>14} {:>8} {:>10}  {}",
+        structure,
+        bytes,
+        elements,
+        bench::stat(&scalar_cycles, scalar_cycles.median, 3),
+        bench::stat(&simd_cycles, simd_cycles.median, 3),
+        bench::ratio(&scalar_cycles, &simd_cycles, 2),
+        bench::stat(&simd_ns, gb_per_s, 2),
+        // A kernel that is twice as wide but not meaningfully faster is
+        // waiting on memory, not on arithmetic.
+        if scalar_cycles.count == 0 || simd_cycles.count == 0 {
+            "unknown"
+        } else if speedup > 1.3 {
+            "compute-bound"
+        } else {
+            "memory-bound"
+        }
+    );
+}
+
+fn sum_scalar(values: &[u64]) -> u64 {
+    values.iter().fold(0u64, |sum, &v| sum.wrapping_add(v))
+}
+
+/// An explicit SIMD summation kernel the running CPU supports.
+#[derive(Clone, Copy)]
+enum Kernel {
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    Avx2,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+
+// With no kernel for the target the enum is empty and the inputs go unused.
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")),
+    allow(unused_variables)
+)]
+impl Kernel {
+    fn detect() -> Option<Self> {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        if std::arch::is_x86_feature_detected!("avx2") {
+            return Some(Kernel::Avx2);
+        }
+        #[cfg(target_arch = "aarch64")]
+        return Some(Kernel::Neon);
+        #[allow(unreachable_code)]
+        None
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            Kernel::Avx2 => "AVX2",
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => "NEON",
+        }
+    }
+
+    /// Wrapping sum of all values.
+    fn sum(self, values: &[u64]) -> u64 {
+        match self {
+            // SAFETY: `detect` only returns `Avx2` when the CPU has it.
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            Kernel::Avx2 => unsafe { x86::sum_avx2(values, false) },
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => arm::sum_neon(values, false),
+        }
+    }
+
+    /// Wrapping sum of the values at even indices: the payloads of an
+    /// arena's `data, next` words.
+    fn sum_even(self, words: &[u64]) -> u64 {
+        match self {
+            // SAFETY: as above.
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+            Kernel::Avx2 => unsafe { x86::sum_avx2(words, true) },
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => arm::sum_neon(words, true),
+        }
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    /// Four 64-bit lanes, two independent accumulators to hide the add
+    /// latency. With `even_only` the odd lanes (arena links) are dropped at
+    /// the end; the chunk boundaries are all even so lanes 0 and 2 are
+    /// always payloads.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sum_avx2(values: &[u64], even_only: bool) -> u64 {
+        let mut acc0 = _mm256_setzero_si256();
+        let mut acc1 = _mm256_setzero_si256();
+        let chunks = values.chunks_exact(8);
+        let tail = chunks.remainder();
+        for chunk in chunks {
+            let p = chunk.as_ptr() as *const __m256i;
+            acc0 = _mm256_add_epi64(acc0, _mm256_loadu_si256(p));
+            acc1 = _mm256_add_epi64(acc1, _mm256_loadu_si256(p.add(1)));
+        }
+        let mut lanes = [0u64; 4];
+        _mm256_storeu_si256(
+            lanes.as_mut_ptr() as *mut __m256i,
+            _mm256_add_epi64(acc0, acc1),
+        );
+
+        let step = if even_only { 2 } else { 1 };
+        let vector = lanes
+            .iter()
+            .step_by(step)
+            .fold(0u64, |s, &v| s.wrapping_add(v));
+        tail.iter()
+            .step_by(step)
+            .fold(vector, |s, &v| s.wrapping_add(v))
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use std::arch::aarch64::*;
+
+    /// Two 64-bit lanes, two accumulators. With `even_only` lane 1 (the
+    /// arena link of every node) is dropped at the end.
+    pub fn sum_neon(values: &[u64], even_only: bool) -> u64 {
+        let chunks = values.chunks_exact(4);
+        let tail = chunks.remainder();
+        // SAFETY: NEON is mandatory on aarch64 and every load reads four
+        // in-bounds `u64`s of the current chunk.
+        let lanes = unsafe {
+            let mut acc0 = vdupq_n_u64(0);
+            let mut acc1 = vdupq_n_u64(0);
+            for chunk in chunks {
+                let p = chunk.as_ptr();
+                acc0 = vaddq_u64(acc0, vld1q_u64(p));
+                acc1 = vaddq_u64(acc1, vld1q_u64(p.add(2)));
+            }
+            let acc = vaddq_u64(acc0, acc1);
+            [vgetq_lane_u64::<0>(acc), vgetq_lane_u64::<1>(acc)]
+        };
+
+        let step = if even_only { 2 } else { 1 };
+        let vector = lanes
+            .iter()
+            .step_by(step)
+            .fold(0u64, |s, &v| s.wrapping_add(v));
+        tail.iter()
+            .step_by(step)
+            .fold(vector, |s, &v| s.wrapping_add(v))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::arena::{ArenaList, SoaList, NIL};
+use crate::bench;
+use crate::rng::Rng;
+
+/// `soa [--nodes <n>] [--iterations <k>]`: the same index-linked list stored
+/// as an array of structs (an [`ArenaList`], payload next to link) and as a
+/// struct of arrays (a [`SoaList`], separate link and payload arrays), under
+/// three workloads:
+///
+/// - `walk`: follow the links only. SoA touches half the bytes per step.
+/// - `walk+data`: follow the links and sum the payloads. SoA now needs two
+///   lines per step where AoS needs one.
+/// - `scan`: sum the payloads in storage order, ignoring the links. SoA
+///   streams over payloads only.
+///
+/// Each runs with the links in storage order (prefetcher-friendly) and in a
+/// random order (one miss per step).
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 20;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete soa option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+
+    println!("--- AoS vs SoA Layout ---");
+    println!("{} nodes of one u64 payload and one u64 link", nodes);
+    println!(
+        "{:<11} {:<11} {:>14} {:>14} {:>9}",
+        "Workload", "Links", "AoS cyc/node", "SoA cyc/node", "SoA/AoS"
+    );
+
+    let sequential: Vec<usize> = (0..nodes).collect();
+    let mut shuffled = sequential.clone();
+    Rng::new(nodes as u64).shuffle(&mut shuffled);
+
+    for (links, order) in [("sequential", &sequential), ("shuffled", &shuffled)] {
+        let aos = ArenaList::linked_in_order(order);
+        let soa = SoaList::linked_in_order(order);
+        let expected = (nodes as u64 - 1) * nodes as u64 / 2;
+
+        type Workload<'a> = (
+            &'a str,
+            Box<dyn Fn() -> u64 + 'a>,
+            Box<dyn Fn() -> u64 + 'a>,
+        );
+        let workloads: [Workload; 3] = [
+            (
+                "walk",
+                Box::new(|| walk_aos(&aos)),
+                Box::new(|| walk_soa(&soa)),
+            ),
+            (
+                "walk+data",
+                Box::new(|| aos.sum_by_links()),
+                Box::new(|| sum_by_links_soa(&soa)),
+            ),
+            (
+                "scan",
+                Box::new(|| {
+                    aos.nodes
+                        .iter()
+                        .fold(0u64, |sum, node| sum.wrapping_add(node.data))
+                }),
+                Box::new(|| soa.data.iter().fold(0u64, |sum, &v| sum.wrapping_add(v))),
+            ),
+        ];
+        for (workload, aos_run, soa_run) in workloads {
+            // `walk` counts nodes, the others sum payloads.
+            let want = if workload == "walk" {
+                nodes as u64
+            } else {
+                expected
+            };
+            if aos_run() != want || soa_run() != want {
+                eprintln!(
+                    "Error: {} over {} links gave a wrong result",
+                    workload, links
+                );
+                std::process::exit(2);
+            }
+            let (_, aos_cycles, _) =
+                bench::per_operation(&bench::time_repeated(iterations, nodes, &aos_run));
+            let (_, soa_cycles, _) =
+                bench::per_operation(&bench::time_repeated(iterations, nodes, &soa_run));
+            println!(
+                "{:<11} {:<11} {:>14} {:>14} {:>9}",
+                workload,
+                links,
+                bench::stat(&aos_cycles, aos_cycles.median, 2),
+                bench::stat(&soa_cycles, soa_cycles.median, 2),
+                bench::ratio(&soa_cycles, &aos_cycles, 2)
+            );
+        }
+    }
+}
+
+fn walk_aos(list: &ArenaList) -> u64 {
+    let mut visited = 0;
+    let mut current = list.head;
+    while current != NIL {
+        visited += 1;
+        current = list.nodes[current as usize].next;
+    }
+    visited
+}
+
+fn walk_soa(list: &SoaList) -> u64 {
+    let mut visited = 0;
+    let mut current = list.head;
+    while current != NIL {
+        visited += 1;
+        current = list.next[current as usize];
+    }
+    visited
+}
+
+fn sum_by_links_soa(list: &SoaList) -> u64 {
+    let mut sum: u64 = 0;
+    let mut current = list.head;
+    while current != NIL {
+        sum = sum.wrapping_add(list.data[current as usize]);
+        current = list.next[current as usize];
+    }
+    sum
+}
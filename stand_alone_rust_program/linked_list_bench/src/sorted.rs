@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use crate::bench;
+use crate::rng::Rng;
+use crate::LinkedList;
+
+/// `sorted [--keys <a,b,...>] [--iterations <k>] [--seed <s>]`: inserts N
+/// random keys one at a time into an ordered linked list, a sorted `Vec`
+/// (binary search, then shift the tail) and a `BTreeMap`, at each N.
+///
+/// Keeping a sequence ordered under inserts is the textbook argument for a
+/// linked list: no shifting. But finding the insertion point is a linear
+/// walk of dependent loads, while the `Vec` finds it in log N probes and
+/// shifts with a `memmove` that streams at memory bandwidth.
+pub fn run(args: &[String]) {
+    let mut sizes: Vec<usize> = vec![1 << 10, 1 << 12, 1 << 14];
+    let mut iterations: usize = 3;
+    let mut seed: u64 = 42;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--keys", Some(v)) => {
+                sizes = v
+                    .split(',')
+                    .filter_map(|n| n.trim().parse().ok())
+                    .filter(|&n| n > 0)
+                    .collect()
+            }
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--seed", Some(v)) => seed = v.parse().unwrap_or(seed),
+            _ => {
+                eprintln!("Error: unknown or incomplete sorted option '{}'", arg);
+                return;
+            }
+        }
+    }
+    if sizes.is_empty() {
+        eprintln!("Error: --keys needs at least one positive size");
+        return;
+    }
+
+    println!("--- Sorted Insert ---");
+    println!("Random keys inserted one at a time, order kept after every insert");
+    println!(
+        "{:>10} {:<10} {:>6} {:>12} {:>14} {:>12} {:>8}",
+        "Keys", "Structure", "Iters", "ns/insert", "cycles/insert", "vs list", "Flagged"
+    );
+    for &keys in &sizes {
+        let mut rng = Rng::new(seed);
+        let input: Vec<u64> = (0..keys).map(|_| rng.next_u64()).collect();
+        let mut expected = input.clone();
+        expected.sort_unstable();
+
+        let list = bench::time_repeated(iterations, keys, || list_insert(&input));
+        let vec = bench::time_repeated(iterations, keys, || vec_insert(&input));
+        let btree = bench::time_repeated(iterations, keys, || btree_insert(&input));
+
+        let ordered = list_insert(&input).into_vec() == expected
+            && vec_insert(&input) == expected
+            && btree_insert(&input).into_keys().collect::<Vec<_>>() == expected;
+        if !ordered {
+            eprintln!(
+                "Error: the structures disagree on the sorted order of {} keys",
+                keys
+            );
+            return;
+        }
+
+        let (_, list_cycles, _) = bench::per_operation(&list);
+        for (name, samples) in [("list", &list), ("sorted vec", &vec), ("btreemap", &btree)] {
+            let (ns, cycles, flagged) = bench::per_operation(samples);
+            println!(
+                "{:>10} {:<10} {:>6} {:>12} {:>14} {:>12} {:>8}",
+                keys,
+                name,
+                samples.len(),
+                bench::stat(&ns, ns.median, 2),
+                bench::stat(&cycles, cycles.median, 2),
+                bench::ratio(&list_cycles, &cycles, 1),
+                flagged
+            );
+        }
+    }
+    println!("\nvs list: how many times faster than the linked list per insert");
+}
+
+fn list_insert(keys: &[u64]) -> LinkedList<u64> {
+    let mut list = LinkedList::new();
+    for &key in keys {
+        list.insert_sorted(key);
+    }
+    list
+}
+
+fn vec_insert(keys: &[u64]) -> Vec<u64> {
+    let mut vec = Vec::with_capacity(keys.len());
+    for &key in keys {
+        // Past any equal keys, so they stay in insertion order as in the
+        // list.
+        let index = vec.partition_point(|&k| k <= key);
+        vec.insert(index, key);
+    }
+    vec
+}
+
+fn btree_insert(keys: &[u64]) -> BTreeMap<u64, ()> {
+    let mut map = BTreeMap::new();
+    for &key in keys {
+        map.insert(key, ());
+    }
+    map
+}
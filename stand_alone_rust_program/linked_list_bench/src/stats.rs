@@ -0,0 +1,406 @@
+/// Order statistics and moments of a set of samples.
+#[derive(Clone, Copy)]
+pub struct Summary {
+    pub count: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+}
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Self {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let count = sorted.len();
+        if count == 0 {
+            return Summary {
+                count,
+                min: 0.0,
+                mean: 0.0,
+                median: 0.0,
+                stddev: 0.0,
+            };
+        }
+
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let median = if count % 2 == 1 {
+            sorted[count / 2]
+        } else {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        };
+        // Sample (n - 1) standard deviation; zero for a single sample.
+        let variance = if count > 1 {
+            sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        Summary {
+            count,
+            min: sorted[0],
+            mean,
+            median,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+/// Fit of `y = constant * x` (no intercept).
+pub struct Fit {
+    pub constant: f64,
+    /// Root-mean-square of the relative residuals `(y - constant * x) / y`.
+    pub relative_error: f64,
+}
+
+/// Fits `y = constant * x` minimising the *relative* residuals, so that when
+/// x spans several orders of magnitude the largest points don't dominate.
+pub fn fit_through_origin(x: &[f64], y: &[f64]) -> Fit {
+    let points: Vec<(f64, f64)> = x
+        .iter()
+        .zip(y)
+        .filter(|(_, y)| **y > 0.0)
+        .map(|(x, y)| (*x, *y))
+        .collect();
+    let num: f64 = points.iter().map(|(x, y)| x / y).sum();
+    let den: f64 = points.iter().map(|(x, y)| (x / y).powi(2)).sum();
+    let constant = if den > 0.0 { num / den } else { 0.0 };
+
+    let ss: f64 = points
+        .iter()
+        .map(|(x, y)| ((y - constant * x) / y).powi(2))
+        .sum();
+    let relative_error = (ss / points.len().max(1) as f64).sqrt();
+    Fit {
+        constant,
+        relative_error,
+    }
+}
+
+/// Exponent `b` of the power law `y = a * x^b`, from a linear fit in log-log
+/// space. Needs two or more positive points at different sizes.
+pub fn power_law_exponent(x: &[f64], y: &[f64]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = x
+        .iter()
+        .zip(y)
+        .filter(|(x, y)| **x > 0.0 && **y > 0.0)
+        .map(|(x, y)| (x.ln(), y.ln()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxy: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    // Equal sizes leave only rounding in `sxx`; dividing by it would make up
+    // a slope, so the spread has to stand out against the magnitudes.
+    let scale: f64 = points.iter().map(|(x, _)| x * x).sum();
+    (sxx > f64::EPSILON * scale).then(|| sxy / sxx)
+}
+
+/// Result of a two-sample significance test.
+pub struct TestResult {
+    pub statistic: f64,
+    pub p_value: f64,
+}
+
+/// Welch's unequal-variance t-test (two-sided). Needs at least two samples
+/// on each side and some variance; returns `None` otherwise.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TestResult> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (sa, sb) = (Summary::of(a), Summary::of(b));
+    let va = sa.stddev.powi(2) / a.len() as f64;
+    let vb = sb.stddev.powi(2) / b.len() as f64;
+    if va + vb == 0.0 {
+        // Constant samples on both sides: the test is undefined.
+        return None;
+    }
+
+    let t = (sa.mean - sb.mean) / (va + vb).sqrt();
+    // Welch-Satterthwaite degrees of freedom.
+    let df =
+        (va + vb).powi(2) / (va.powi(2) / (a.len() - 1) as f64 + vb.powi(2) / (b.len() - 1) as f64);
+    let p_value = incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+    Some(TestResult {
+        statistic: t,
+        p_value,
+    })
+}
+
+/// Two-sided Mann-Whitney U test using the normal approximation with tie
+/// correction. `statistic` is U for the first sample: the number of pairs
+/// (a, b) with a > b, ties counting one half. Needs at least two samples on
+/// each side.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<TestResult> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&v| (v, true))
+        .chain(b.iter().map(|&v| (v, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Assign average ranks to ties and accumulate the tie correction term.
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i;
+        while j < pooled.len() && pooled[j].0 == pooled[i].0 {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.0;
+        rank_sum_a += rank * pooled[i..j].iter().filter(|p| p.1).count() as f64;
+        let ties = (j - i) as f64;
+        tie_term += ties.powi(3) - ties;
+        i = j;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return Some(TestResult {
+            statistic: u,
+            p_value: 1.0,
+        });
+    }
+    // Continuity-corrected z score.
+    let z = ((u - n1 * n2 / 2.0).abs() - 0.5).max(0.0) / variance.sqrt();
+    Some(TestResult {
+        statistic: u,
+        p_value: erfc(z / std::f64::consts::SQRT_2).min(1.0),
+    })
+}
+
+/// Complementary error function (Numerical Recipes `erfcc`, |error| < 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * poly.exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+/// Regularized incomplete beta function I_x(a, b), via the continued
+/// fraction from Numerical Recipes.
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly only below the mean.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for numerator in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Lanczos approximation of ln(Γ(x)) for x > 0.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -(tmp - (x + 0.5) * tmp.ln()) + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// R's `sleep` data set: extra hours of sleep under two drugs.
+    const SLEEP_1: [f64; 10] = [0.7, -1.6, -0.2, -1.2, -0.1, 3.4, 3.7, 0.8, 0.0, 2.0];
+    const SLEEP_2: [f64; 10] = [1.9, 0.8, 1.1, 0.1, -0.1, 4.4, 5.5, 1.6, 4.6, 3.4];
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn welch_t_test_matches_r() {
+        // t.test(extra ~ group, data = sleep):
+        // t = -1.8608, df = 17.776, p-value = 0.07939
+        let test = welch_t_test(&SLEEP_1, &SLEEP_2).unwrap();
+        assert_close(test.statistic, -1.8608, 1e-4);
+        assert_close(test.p_value, 0.07939, 1e-5);
+    }
+
+    #[test]
+    fn welch_t_test_needs_samples_and_variance() {
+        assert!(welch_t_test(&[1.0], &[1.0, 2.0]).is_none());
+        assert!(welch_t_test(&[3.0, 3.0], &[3.0, 3.0]).is_none());
+    }
+
+    #[test]
+    fn incomplete_beta_matches_t_table() {
+        // Two-sided 5% critical value of Student's t with 10 degrees of
+        // freedom is 2.228, so I_{10/(10+t^2)}(5, 1/2) is 0.05.
+        let t: f64 = 2.228;
+        assert_close(incomplete_beta(5.0, 0.5, 10.0 / (10.0 + t * t)), 0.05, 1e-4);
+    }
+
+    #[test]
+    fn incomplete_beta_closed_forms() {
+        for x in [0.1, 0.35, 0.8] {
+            // I_x(a, 1) = x^a, on both sides of the continued fraction's
+            // switch-over point.
+            assert_close(incomplete_beta(3.0, 1.0, x), x.powi(3), 1e-10);
+        }
+        assert_close(incomplete_beta(4.5, 4.5, 0.5), 0.5, 1e-10);
+        assert_eq!(incomplete_beta(2.0, 3.0, 0.0), 0.0);
+        assert_eq!(incomplete_beta(2.0, 3.0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn ln_gamma_matches_factorials() {
+        assert_close(ln_gamma(1.0), 0.0, 1e-10);
+        assert_close(ln_gamma(10.0), 362880f64.ln(), 1e-9);
+        assert_close(ln_gamma(0.5), std::f64::consts::PI.sqrt().ln(), 1e-10);
+    }
+
+    #[test]
+    fn fit_through_origin_recovers_exact_line() {
+        let x = [1.0, 10.0, 100.0, 1000.0];
+        let y = x.map(|x| 3.0 * x);
+        let fit = fit_through_origin(&x, &y);
+        assert_close(fit.constant, 3.0, 1e-12);
+        assert_close(fit.relative_error, 0.0, 1e-12);
+    }
+
+    #[test]
+    fn fit_through_origin_weighs_relative_residuals() {
+        // Minimising sum(((y - c x) / y)^2) gives c = sum(x/y) / sum((x/y)^2):
+        // 1.01449 here, with an RMS relative residual of 0.09707.
+        let fit = fit_through_origin(&[1.0, 2.0, 4.0], &[1.1, 1.8, 4.4]);
+        assert_close(fit.constant, 1.014488, 1e-6);
+        assert_close(fit.relative_error, 0.097071, 1e-6);
+        // Points without a positive y are left out.
+        let fit = fit_through_origin(&[1.0, 2.0, 5.0], &[2.0, 4.0, 0.0]);
+        assert_close(fit.constant, 2.0, 1e-12);
+    }
+
+    #[test]
+    fn power_law_exponent_matches_keplers_third_law() {
+        // Semi-major axis (AU) and orbital period (years), Mercury to
+        // Saturn: the period goes as the axis to the power 3/2.
+        let axis = [0.387, 0.723, 1.0, 1.524, 5.203, 9.537];
+        let period = [0.241, 0.615, 1.0, 1.881, 11.86, 29.46];
+        assert_close(power_law_exponent(&axis, &period).unwrap(), 1.5, 1e-3);
+    }
+
+    #[test]
+    fn power_law_exponent_needs_two_positive_points() {
+        let x = [1.0, 2.0, 4.0, 8.0];
+        assert_close(
+            power_law_exponent(&x, &x.map(|x| 5.0 * x * x)).unwrap(),
+            2.0,
+            1e-12,
+        );
+        assert!(power_law_exponent(&[4.0, 0.0], &[16.0, 0.0]).is_none());
+        assert!(power_law_exponent(&[4.0, 4.0], &[16.0, 17.0]).is_none());
+    }
+
+    #[test]
+    fn mann_whitney_u_matches_r() {
+        // wilcox.test(extra ~ group, data = sleep, exact = FALSE):
+        // W = 25.5, p-value = 0.06933
+        let test = mann_whitney_u(&SLEEP_1, &SLEEP_2).unwrap();
+        assert_eq!(test.statistic, 25.5);
+        assert_close(test.p_value, 0.06933, 1e-5);
+    }
+
+    #[test]
+    fn mann_whitney_u_of_identical_samples() {
+        let test = mann_whitney_u(&[2.0, 2.0, 2.0], &[2.0, 2.0]).unwrap();
+        assert_eq!(test.statistic, 3.0);
+        assert_eq!(test.p_value, 1.0);
+        assert!(mann_whitney_u(&[1.0, 2.0], &[3.0]).is_none());
+    }
+
+    #[test]
+    fn erfc_matches_table() {
+        assert_close(erfc(0.0), 1.0, 1.2e-7);
+        assert_close(erfc(0.5), 0.4795001222, 1.2e-7);
+        assert_close(erfc(1.0), 0.1572992071, 1.2e-7);
+        assert_close(erfc(2.0), 0.0046777350, 1.2e-7);
+        assert_close(erfc(-1.0), 1.8427007929, 1.2e-7);
+    }
+}
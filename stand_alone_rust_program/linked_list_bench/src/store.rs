@@ -0,0 +1,209 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::bench::{self, BenchResult};
+use crate::metadata::Metadata;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id                      INTEGER PRIMARY KEY,
+        recorded_at             INTEGER NOT NULL,
+        git_commit              TEXT NOT NULL,
+        host                    TEXT NOT NULL,
+        cpu                     TEXT NOT NULL,
+        benchmark               TEXT NOT NULL,
+        nodes                   INTEGER NOT NULL,
+        iterations              INTEGER NOT NULL,
+        min_cycles_per_node     REAL NOT NULL,
+        median_cycles_per_node  REAL NOT NULL,
+        mean_cycles_per_node    REAL NOT NULL,
+        stddev_cycles_per_node  REAL NOT NULL,
+        median_ns_per_node      REAL NOT NULL
+    )";
+
+/// Append-only SQLite log of benchmark runs for tracking results across
+/// commits and machines.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    /// Records `result`, unless every one of its samples was flagged: it
+    /// has no statistics to store, and zeros would read as a 100% speedup.
+    pub fn append(&self, metadata: &Metadata, result: &BenchResult) -> rusqlite::Result<()> {
+        let cycles = result.cycles_per_node();
+        if cycles.count == 0 {
+            return Ok(());
+        }
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.conn.execute(
+            "INSERT INTO runs (recorded_at, git_commit, host, cpu, benchmark, nodes, iterations,
+                               min_cycles_per_node, median_cycles_per_node, mean_cycles_per_node,
+                               stddev_cycles_per_node, median_ns_per_node)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                recorded_at as i64,
+                metadata.git_commit,
+                metadata.host,
+                metadata.cpu,
+                result.name,
+                result.nodes as i64,
+                result.samples.len() as i64,
+                cycles.min,
+                cycles.median,
+                cycles.mean,
+                cycles.stddev,
+                result.ns_per_node().median,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// `history <results.db> [--benchmark <name>] [--nodes <n>]`: prints the
+/// stored median cycles-per-node for a benchmark in the order it was recorded.
+pub fn history(args: &[String]) {
+    let Some(path) = args.first() else {
+        eprintln!("Usage: cargo run -- history <results.db> [--benchmark <name>] [--nodes <n>]");
+        return;
+    };
+
+    let mut benchmark = "traverse".to_string();
+    let mut nodes: Option<i64> = None;
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--benchmark", Some(v)) => benchmark = v.clone(),
+            ("--nodes", Some(v)) => nodes = v.parse().ok(),
+            _ => {
+                eprintln!("Error: unknown or incomplete history option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    if let Err(e) = print_history(path, &benchmark, nodes) {
+        eprintln!("Error: could not read history from {}: {}", path, e);
+    }
+}
+
+fn print_history(path: &str, benchmark: &str, nodes: Option<i64>) -> rusqlite::Result<()> {
+    let store = Store::open(path)?;
+    let mut stmt = store.conn.prepare(
+        "SELECT datetime(recorded_at, 'unixepoch'), git_commit, host, nodes, iterations,
+                median_cycles_per_node, median_ns_per_node
+         FROM runs
+         WHERE benchmark = ?1 AND (?2 IS NULL OR nodes = ?2)
+         ORDER BY nodes, recorded_at, id",
+    )?;
+    let rows = stmt.query_map(params![benchmark, nodes], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, f64>(5)?,
+            row.get::<_, f64>(6)?,
+        ))
+    })?;
+
+    println!("--- History: {} ---", benchmark);
+    println!(
+        "{:<20} {:<10} {:<16} {:>12} {:>6} {:>12} {:>10} {:>9}",
+        "Recorded (UTC)", "Commit", "Host", "Nodes", "Iters", "cycles/node", "ns/node", "Change"
+    );
+
+    // Change is relative to the previous run of the same list size. Older
+    // builds stored fully flagged results as zeros; they are shown as such
+    // and left out of the change.
+    let mut previous: Option<(i64, f64)> = None;
+    for row in rows {
+        let (recorded_at, commit, host, nodes, iterations, cycles, ns) = row?;
+        if cycles <= 0.0 {
+            println!(
+                "{:<20} {:<10} {:<16} {:>12} {:>6} {:>12} {:>10} {:>9}",
+                recorded_at,
+                commit,
+                host,
+                nodes,
+                iterations,
+                bench::ALL_FLAGGED,
+                bench::ALL_FLAGGED,
+                "-"
+            );
+            continue;
+        }
+        let change = match previous {
+            Some((prev_nodes, prev_cycles)) if prev_nodes == nodes && prev_cycles > 0.0 => {
+                format!("{:+.1}%", (cycles / prev_cycles - 1.0) * 100.0)
+            }
+            _ => "-".to_string(),
+        };
+        println!(
+            "{:<20} {:<10} {:<16} {:>12} {:>6} {:>12.2} {:>10.2} {:>9}",
+            recorded_at, commit, host, nodes, iterations, cycles, ns, change
+        );
+        previous = Some((nodes, cycles));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Sample;
+    use crate::cache::Layout;
+    use crate::clock::Anomaly;
+    use std::time::Duration;
+
+    #[test]
+    #[cfg_attr(miri, ignore = "SQLite is C, which Miri can't run")]
+    fn fully_flagged_results_are_not_stored() {
+        let metadata = Metadata {
+            host: "bench-host".to_string(),
+            cpu: "Some CPU @ 3.00GHz".to_string(),
+            git_commit: "0123abc".to_string(),
+        };
+        let result = |anomaly| BenchResult {
+            name: "traverse".to_string(),
+            nodes: 1024,
+            samples: vec![Sample {
+                visited: 1024,
+                time: Duration::from_nanos(1500),
+                cycles: 4500,
+                anomaly,
+            }],
+            layout: Layout {
+                median_stride: 32,
+                adjacent_fraction: 0.75,
+                misses: None,
+            },
+        };
+        let store = Store::open(":memory:").unwrap();
+        store
+            .append(&metadata, &result(Some(Anomaly::Backwards)))
+            .unwrap();
+        store.append(&metadata, &result(None)).unwrap();
+        let (rows, median): (i64, f64) = store
+            .conn
+            .query_row(
+                "SELECT COUNT(*), MAX(median_cycles_per_node) FROM runs",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(rows, 1);
+        assert!((median - 4500.0 / 1024.0).abs() < 1e-9);
+    }
+}
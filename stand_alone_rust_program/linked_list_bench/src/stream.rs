@@ -0,0 +1,103 @@
+use std::hint::black_box;
+
+use crate::bench::{self, Sample};
+use crate::stats::Summary;
+
+/// `stream [--min <bytes>] [--max <bytes>] [--iterations <n>]`: STREAM-style
+/// sequential kernels over one or two buffers of each size. `fill` only
+/// writes, `copy` reads one buffer and writes the other, `sum` only reads.
+/// The sum bandwidth is the ceiling for any read-only traversal, so the last
+/// column puts the linked list traversal of the same footprint against it.
+pub fn run(args: &[String]) {
+    let mut min_bytes: usize = 1 << 14;
+    let mut max_bytes: usize = 1 << 26;
+    let mut iterations: usize = 10;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--min", Some(v)) => min_bytes = v.parse().unwrap_or(min_bytes),
+            ("--max", Some(v)) => max_bytes = v.parse().unwrap_or(max_bytes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete stream option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    println!("--- Memory Bandwidth (STREAM-style) ---");
+    println!(
+        "{:>12} {:>10} {:>10} {:>10} {:>12} {:>14} {:>10}",
+        "Bytes", "fill GB/s", "copy GB/s", "sum GB/s", "sum cyc/B", "traverse GB/s", "% of sum"
+    );
+
+    let mut bytes = min_bytes.max(64);
+    while bytes <= max_bytes {
+        let elements = bytes / std::mem::size_of::<u64>();
+        let mut src: Vec<u64> = (0..elements as u64).collect();
+        let mut dst: Vec<u64> = vec![1; elements];
+
+        let mut value = 0u64;
+        let fill = bench::time_repeated(iterations, bytes, || {
+            value += 1;
+            dst.fill(value);
+            black_box(&mut dst);
+        });
+        let copy = bench::time_repeated(iterations, 2 * bytes, || {
+            dst.copy_from_slice(&src);
+            black_box(&mut dst);
+        });
+        let sum = bench::time_repeated(iterations, bytes, || {
+            black_box(&mut src)
+                .iter()
+                .fold(0u64, |sum, &v| sum.wrapping_add(v))
+        });
+        let (_, sum_cycles, _) = bench::per_operation(&sum);
+
+        // The list whose node data occupies the same number of bytes.
+        let traversal = bench::traverse(
+            (bytes / bench::NODE_BYTES).max(1),
+            bench::Iterations::Count(iterations),
+            false,
+        );
+        let traverse_rate = traversal.bytes_per_second();
+        let sum_rate = bytes_per_second(&sum);
+        let (fill_rate, copy_rate) = (bytes_per_second(&fill), bytes_per_second(&copy));
+
+        println!(
+            "{:>12} {:>10} {:>10} {:>10} {:>12} {:>14} {:>10}",
+            bytes,
+            bench::stat(&fill_rate, fill_rate.median / 1e9, 2),
+            bench::stat(&copy_rate, copy_rate.median / 1e9, 2),
+            bench::stat(&sum_rate, sum_rate.median / 1e9, 2),
+            bench::stat(&sum_cycles, sum_cycles.median, 3),
+            bench::stat(&traverse_rate, traverse_rate.median / 1e9, 2),
+            if sum_rate.count == 0 || traverse_rate.count == 0 {
+                bench::ALL_FLAGGED.to_string()
+            } else {
+                format!(
+                    "{:.1}%",
+                    traverse_rate.median / sum_rate.median.max(f64::MIN_POSITIVE) * 100.0
+                )
+            }
+        );
+
+        match bytes.checked_mul(2) {
+            Some(next) => bytes = next,
+            None => break,
+        }
+    }
+}
+
+/// Bytes per second of the unflagged samples, whose `visited` count is bytes
+/// moved.
+fn bytes_per_second(samples: &[Sample]) -> Summary {
+    Summary::of(
+        &samples
+            .iter()
+            .filter(|s| s.anomaly.is_none())
+            .map(Sample::nodes_per_second)
+            .collect::<Vec<_>>(),
+    )
+}
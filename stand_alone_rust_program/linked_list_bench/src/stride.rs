@@ -0,0 +1,86 @@
+use std::hint::black_box;
+
+use crate::bench;
+
+const DEFAULT_STRIDES: [usize; 11] = [8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192];
+
+/// `stride [--bytes <buffer>] [--strides <a,b,...>] [--accesses <k>]
+/// [--iterations <n>]`: reads one byte every `stride` bytes of a buffer much
+/// larger than the caches, wrapping around at the end. Below the line size
+/// several accesses share a line; up to a page they share a TLB entry; past
+/// it every access is a new line *and* a new page, so the steps in cycles per
+/// access mark the cache-line and page granularity.
+pub fn run(args: &[String]) {
+    let mut bytes: usize = 1 << 26;
+    let mut strides: Vec<usize> = DEFAULT_STRIDES.to_vec();
+    let mut accesses: usize = 1 << 20;
+    let mut iterations: usize = 5;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--bytes", Some(v)) => bytes = v.parse().unwrap_or(bytes),
+            ("--strides", Some(v)) => match v.split(',').map(str::parse).collect() {
+                Ok(list) => strides = list,
+                Err(_) => {
+                    eprintln!(
+                        "Error: --strides expects comma-separated byte counts, got '{}'",
+                        v
+                    );
+                    return;
+                }
+            },
+            ("--accesses", Some(v)) => accesses = v.parse().unwrap_or(accesses),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete stride option '{}'", arg);
+                return;
+            }
+        }
+    }
+    if strides.iter().any(|&s| s == 0 || s >= bytes) {
+        eprintln!("Error: strides must be non-zero and smaller than the buffer");
+        return;
+    }
+
+    // Distinct non-zero contents so no page is shared with the zero page.
+    let buffer: Vec<u8> = (0..bytes).map(|i| i as u8 | 1).collect();
+
+    println!("--- Stride Sweep ---");
+    println!("{} byte buffer, {} accesses per sample", bytes, accesses);
+    println!(
+        "{:>10} {:>6} {:>10} {:>14} {:>10} {:>8}",
+        "Stride", "Iters", "ns/access", "cycles/access", "stddev", "Flagged"
+    );
+    for stride in strides {
+        black_box(walk(&buffer, stride, accesses));
+        let samples =
+            bench::time_repeated(iterations, accesses, || walk(&buffer, stride, accesses));
+        let (ns, cycles, flagged) = bench::per_operation(&samples);
+        println!(
+            "{:>10} {:>6} {:>10} {:>14} {:>10} {:>8}",
+            stride,
+            samples.len(),
+            bench::stat(&ns, ns.median, 2),
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            flagged
+        );
+    }
+}
+
+/// Sums `accesses` bytes `stride` apart. Wrapping shifts the start by one
+/// byte each lap so repeated laps over a small buffer don't hit the exact
+/// same bytes.
+fn walk(buffer: &[u8], stride: usize, accesses: usize) -> u64 {
+    let mut offset = 0;
+    let mut sum: u64 = 0;
+    for _ in 0..accesses {
+        sum += buffer[offset] as u64;
+        offset += stride;
+        if offset >= buffer.len() {
+            offset = (offset + 1) % stride;
+        }
+    }
+    sum
+}
@@ -0,0 +1,366 @@
+use crate::bench::{self, BenchResult};
+use crate::cache;
+use crate::cold;
+use crate::environment;
+use crate::isolate;
+use crate::metadata::Metadata;
+use crate::mlock;
+use crate::plot;
+use crate::report::{self, Format};
+use crate::results;
+use crate::stats;
+use crate::store::Store;
+use crate::thermal;
+
+/// Runs the traversal benchmark for every power of two between `--min` and
+/// `--max` nodes, so cache cliffs show up as steps in cycles-per-node.
+///
+/// With `--isolate` every size runs in a child process of its own, so no
+/// size inherits the heap fragmentation and cache contents of the ones
+/// before it.
+pub fn run(args: &[String]) {
+    let mut min_nodes: usize = 1 << 10;
+    let mut max_nodes: usize = 1 << 24;
+    let mut iterations: usize = 1;
+    let mut budget = None;
+    let mut plot_path: Option<String> = None;
+    let mut store_path: Option<String> = None;
+    let mut save_path: Option<String> = None;
+    let mut format = Format::Text;
+    let mut verify = false;
+    let mut write = false;
+    let mut isolate = false;
+    let mut cold = false;
+    let mut mlock = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--verify" {
+            verify = true;
+            continue;
+        }
+        if arg == "--write" {
+            write = true;
+            continue;
+        }
+        if arg == "--isolate" {
+            isolate = true;
+            continue;
+        }
+        if arg == "--cold" {
+            cold = true;
+            continue;
+        }
+        if arg == "--mlock" {
+            mlock = true;
+            continue;
+        }
+        let value = iter.next();
+        match (arg.as_str(), value) {
+            ("--min", Some(v)) => min_nodes = v.parse().unwrap_or(min_nodes),
+            ("--max", Some(v)) => max_nodes = v.parse().unwrap_or(max_nodes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--measure-for", Some(v)) => match bench::parse_budget(v) {
+                Some(b) => budget = Some(b),
+                None => {
+                    eprintln!(
+                        "Error: bad --measure-for '{}' (expected e.g. 500ms, 5s or 1m)",
+                        v
+                    );
+                    return;
+                }
+            },
+            ("--plot", Some(v)) => plot_path = Some(v.clone()),
+            ("--store", Some(v)) => store_path = Some(v.clone()),
+            ("--save", Some(v)) => save_path = Some(v.clone()),
+            ("--format", Some(v)) => match Format::parse(v) {
+                Some(f) => format = f,
+                None => {
+                    eprintln!(
+                        "Error: unknown format '{}' (expected text, html, markdown, ndjson or github-benchmark)",
+                        v
+                    );
+                    return;
+                }
+            },
+            _ => {
+                eprintln!("Error: unknown or incomplete sweep option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    let store = match store_path.as_deref().map(Store::open).transpose() {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error: could not open results store: {}", e);
+            return;
+        }
+    };
+    if cold {
+        eprintln!("[cold] {}", cold::enable());
+    }
+    if mlock {
+        eprintln!("[mlock] {}", mlock::enable());
+    }
+    let metadata = Metadata::collect();
+    let environment = environment::snapshot();
+    thermal::start();
+
+    let text = format == Format::Text;
+    if text {
+        println!("--- Linked List Size Sweep ---");
+        print!(
+            "{:>12} {:>6} {:>12} {:>14} {:>14} {:>10} {:>8} {:>8}",
+            "Nodes", "Iters", "ns/node", "cycles/node", "stddev", "Mnodes/s", "GB/s", "Flagged"
+        );
+        if write {
+            print!(" {:>12} {:>8}", "write cyc/n", "w/r");
+        }
+        println!();
+    }
+
+    // A budget applies to every size, and to the reads and the writes each.
+    let iterations = match budget {
+        Some(budget) => bench::Iterations::Budget(budget),
+        None => bench::Iterations::Count(iterations),
+    };
+    let mut results: Vec<BenchResult> = Vec::new();
+    let mut write_results: Vec<BenchResult> = Vec::new();
+    let mut nodes = min_nodes.max(1);
+    while nodes <= max_nodes {
+        thermal::mark(&format!("{} nodes", nodes));
+        let (result, write_result) = if isolate {
+            match isolated(nodes, iterations, verify, write, cold, mlock) {
+                Ok(pair) => pair,
+                Err(e) => {
+                    eprintln!("Error: isolated run of {} nodes failed: {}", nodes, e);
+                    return;
+                }
+            }
+        } else if write {
+            let (read, write) = bench::traverse_read_write(nodes, iterations, verify);
+            (read, Some(write))
+        } else {
+            (bench::traverse(nodes, iterations, verify), None)
+        };
+        if text {
+            let cycles = result.cycles_per_node();
+            print!(
+                "{:>12} {:>6} {:>12} {:>14} {:>14} {:>10} {:>8} {:>8}",
+                result.nodes,
+                result.samples.len(),
+                bench::stat(&cycles, result.ns_per_node().median, 2),
+                bench::stat(&cycles, cycles.median, 2),
+                bench::stat(&cycles, cycles.stddev, 2),
+                bench::stat(&cycles, result.nodes_per_second().median / 1e6, 1),
+                bench::stat(&cycles, result.bytes_per_second().median / 1e9, 2),
+                result.flagged()
+            );
+            if let Some(write_result) = &write_result {
+                let write_cycles = write_result.cycles_per_node();
+                print!(
+                    " {:>12} {:>8}",
+                    bench::stat(&write_cycles, write_cycles.median, 2),
+                    bench::ratio(&write_cycles, &cycles, 2)
+                );
+            }
+            println!();
+        }
+        if let Some(store) = &store {
+            for result in std::iter::once(&result).chain(&write_result) {
+                if let Err(e) = store.append(&metadata, result) {
+                    eprintln!("Warning: could not store result: {}", e);
+                }
+            }
+        }
+        if format == Format::Ndjson {
+            // Written as each size completes, so an interrupted sweep still
+            // leaves every finished result behind.
+            for result in std::iter::once(&result).chain(&write_result) {
+                println!("{}", report::ndjson("sweep", &metadata, result));
+            }
+        }
+        results.push(result);
+        write_results.extend(write_result);
+
+        match nodes.checked_mul(2) {
+            Some(next) => nodes = next,
+            None => break,
+        }
+    }
+
+    if text && results.len() > 1 {
+        print_complexity_fit(&results);
+    }
+    if text {
+        print_cache_models(&results);
+    }
+    thermal::report(text);
+    environment::report(&environment, text);
+
+    if let Some(path) = plot_path {
+        let mut series = vec![("linked list", &results[..])];
+        if write {
+            series.push(("linked list (write)", &write_results[..]));
+        }
+        match plot::cycles_per_node(&path, &series) {
+            Ok(()) => eprintln!("\nPlot written to {}", path),
+            Err(e) => eprintln!("Error: could not write plot {}: {}", path, e),
+        }
+    }
+
+    // Reads then writes, so each benchmark stays one contiguous series.
+    results.extend(write_results);
+
+    if let Some(path) = save_path {
+        match results::save(&path, &metadata, &results) {
+            Ok(()) => eprintln!("Samples saved to {}", path),
+            Err(e) => eprintln!("Error: could not save samples to {}: {}", path, e),
+        }
+    }
+
+    match format {
+        Format::Html => print!(
+            "{}",
+            report::html("Linked List Size Sweep", &metadata, &results)
+        ),
+        Format::Markdown => print!(
+            "{}",
+            report::markdown("Linked List Size Sweep", &metadata, &results)
+        ),
+        Format::GithubBenchmark => print!("{}", report::github_benchmark(&metadata, &results)),
+        Format::Text | Format::Ndjson => {}
+    }
+}
+
+/// Runs one size as `sweep --min n --max n` in a child process and reads
+/// its results back from the samples file it saves.
+fn isolated(
+    nodes: usize,
+    iterations: bench::Iterations,
+    verify: bool,
+    write: bool,
+    cold: bool,
+    mlock: bool,
+) -> std::io::Result<(BenchResult, Option<BenchResult>)> {
+    let path = isolate::scratch_path(&nodes.to_string());
+    let mut args: Vec<String> = [
+        "sweep",
+        "--min",
+        &nodes.to_string(),
+        "--max",
+        &nodes.to_string(),
+    ]
+    .map(String::from)
+    .to_vec();
+    match iterations {
+        bench::Iterations::Count(count) => args.extend(["--iterations".into(), count.to_string()]),
+        bench::Iterations::Budget(budget) => args.extend([
+            "--measure-for".into(),
+            format!("{}ms", budget.as_secs_f64() * 1e3),
+        ]),
+    }
+    args.extend(verify.then(|| "--verify".to_string()));
+    args.extend(write.then(|| "--write".to_string()));
+    args.extend(cold.then(|| "--cold".to_string()));
+    args.extend(mlock.then(|| "--mlock".to_string()));
+    args.extend(["--save".into(), path.to_string_lossy().into_owned()]);
+
+    let loaded = isolate::child(&args).and_then(|_| results::load(&path.to_string_lossy()));
+    let _ = std::fs::remove_file(&path);
+    let mut results = loaded?.1.into_iter();
+    let read = results
+        .next()
+        .ok_or_else(|| std::io::Error::other("child saved no results"))?;
+    Ok((read, results.next()))
+}
+
+/// Fits total traversal time against N for the candidate complexity models
+/// and names the best one, so the per-node constant doesn't have to be read
+/// off the table by eye.
+fn print_complexity_fit(results: &[BenchResult]) {
+    let n: Vec<f64> = results.iter().map(|r| r.nodes as f64).collect();
+    let time_ns: Vec<f64> = results
+        .iter()
+        .map(|r| r.ns_per_node().median * r.nodes as f64)
+        .collect();
+
+    type Model = (&'static str, &'static str, fn(f64) -> f64);
+    let models: [Model; 2] = [
+        ("O(n)", "ns/node", |n| n),
+        ("O(n log n)", "ns/(node*log2 n)", |n| n * n.log2()),
+    ];
+
+    println!("\n[Complexity Fit]");
+    println!(
+        "{:<12} {:>12} {:<18} {:>10}",
+        "Model", "Constant", "", "rms error"
+    );
+    let mut best: Option<(&str, &str, stats::Fit)> = None;
+    for (name, unit, model) in models {
+        let x: Vec<f64> = n.iter().map(|&n| model(n)).collect();
+        let fit = stats::fit_through_origin(&x, &time_ns);
+        println!(
+            "{:<12} {:>12.3} {:<18} {:>9.1}%",
+            name,
+            fit.constant,
+            unit,
+            fit.relative_error * 100.0
+        );
+        if best
+            .as_ref()
+            .is_none_or(|b| fit.relative_error < b.2.relative_error)
+        {
+            best = Some((name, unit, fit));
+        }
+    }
+
+    if let Some(exponent) = stats::power_law_exponent(&n, &time_ns) {
+        println!("Power law:   time ~ n^{:.2}", exponent);
+    }
+    if let Some((name, unit, fit)) = best {
+        println!(
+            "Best fit:    {} {}, {:.2} {} (rms error {:.1}%)",
+            results[0].name,
+            name,
+            fit.constant,
+            unit,
+            fit.relative_error * 100.0
+        );
+    }
+}
+
+/// Compares every size against the cache model derived from the detected
+/// cache hierarchy and the measured node layout.
+fn print_cache_models(results: &[BenchResult]) {
+    let levels = cache::detect();
+    println!("\n[Cache Model]");
+    println!(
+        "{:<12} {:>8} {:>9} {:>10} {:>10} {:>10}  Verdict",
+        "Nodes", "Resident", "Adjacent", "Misses", "Expected", "Measured"
+    );
+    for result in results {
+        let model = cache::model::<usize>(result.nodes, &result.layout, &levels);
+        let cycles = result.cycles_per_node();
+        println!(
+            "{:<12} {:>8} {:>8.0}% {:>10.2} {:>10.2} {:>10}  {}",
+            result.nodes,
+            model.resident,
+            result.layout.adjacent_fraction * 100.0,
+            model.misses_per_node,
+            model.expected_cycles,
+            bench::stat(&cycles, cycles.median, 2),
+            if cycles.count == 0 {
+                "no verdict: every sample was flagged"
+            } else {
+                cache::verdict(&model, &result.layout, cycles.median)
+            }
+        );
+    }
+    if results.iter().any(|result| result.layout.misses.is_some()) {
+        println!("Misses: counted by the hardware over one traversal of each size");
+    } else {
+        println!("Misses: expected from the layout (hardware cache counters unavailable)");
+    }
+}
@@ -0,0 +1,149 @@
+//! The two hand-rolled locks the `locks` subcommand measures against
+//! `std::sync::Mutex` and `parking_lot::Mutex`. Neither parks: waiters spin
+//! on the lock word, yielding the CPU after a while so that a waiter can't
+//! burn a whole timeslice while the holder sits preempted on the same core.
+
+use std::cell::UnsafeCell;
+use std::hint;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+/// Spins before each `yield_now`: long enough to cover a short critical
+/// section on another core, short against a scheduler tick.
+const SPINS_BEFORE_YIELD: u32 = 128;
+
+/// One step of a wait loop.
+fn relax(spins: &mut u32) {
+    if *spins < SPINS_BEFORE_YIELD {
+        *spins += 1;
+        hint::spin_loop();
+    } else {
+        *spins = 0;
+        thread::yield_now();
+    }
+}
+
+/// Test-and-test-and-set spinlock: waiters read the flag until it looks
+/// free and only then try to take it, so they spin in their own cache
+/// rather than bouncing the line between cores. No fairness: whichever
+/// waiter sees the release first wins.
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the flag hands out the value to one thread at a time.
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub fn new(value: T) -> Self {
+        SpinLock {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> SpinGuard<'_, T> {
+        let mut spins = 0;
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.locked.load(Ordering::Relaxed) {
+                relax(&mut spins);
+            }
+        }
+        SpinGuard { lock: self }
+    }
+}
+
+pub struct SpinGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> Deref for SpinGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the lock.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for SpinGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the lock, and `&mut self` makes this the
+        // only reference through it.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for SpinGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// Ticket lock: each waiter takes the next ticket and waits for it to be
+/// served, so the lock is handed out in arrival order. The price of that
+/// fairness is that a preempted next-in-line holds up everyone behind it.
+pub struct TicketLock<T> {
+    next: AtomicUsize,
+    serving: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: only the holder of the ticket being served touches the value.
+unsafe impl<T: Send> Sync for TicketLock<T> {}
+
+impl<T> TicketLock<T> {
+    pub fn new(value: T) -> Self {
+        TicketLock {
+            next: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> TicketGuard<'_, T> {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut spins = 0;
+        while self.serving.load(Ordering::Acquire) != ticket {
+            relax(&mut spins);
+        }
+        TicketGuard { lock: self }
+    }
+}
+
+pub struct TicketGuard<'a, T> {
+    lock: &'a TicketLock<T>,
+}
+
+impl<T> Deref for TicketGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard's ticket is being served.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for TicketGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard's ticket is being served, and `&mut self` makes
+        // this the only reference through it.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for TicketGuard<'_, T> {
+    fn drop(&mut self) {
+        // Only the holder writes `serving`, so a plain increment is enough.
+        let serving = self.lock.serving.load(Ordering::Relaxed);
+        self.lock
+            .serving
+            .store(serving.wrapping_add(1), Ordering::Release);
+    }
+}
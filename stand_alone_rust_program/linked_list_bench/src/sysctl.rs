@@ -0,0 +1,65 @@
+//! Reading macOS `sysctl` values by name, for the hardware details Linux
+//! exposes through /proc and /sys.
+
+use std::ffi::{c_void, CString};
+use std::ptr;
+
+/// An integer value (`hw.l2cachesize`, ...), whether the kernel stores it
+/// as 32 or 64 bits.
+pub fn u64(name: &str) -> Option<u64> {
+    let name = CString::new(name).ok()?;
+    let mut value = [0u8; 8];
+    let mut size = value.len();
+    // SAFETY: `value` is writable for `size` bytes and `name` is a valid C
+    // string; no new value is set.
+    let status = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            value.as_mut_ptr() as *mut c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    match (status, size) {
+        (0, 4) => Some(u32::from_ne_bytes(value[..4].try_into().ok()?) as u64),
+        (0, 8) => Some(u64::from_ne_bytes(value)),
+        _ => None,
+    }
+}
+
+/// A string value (`machdep.cpu.brand_string`, ...).
+pub fn string(name: &str) -> Option<String> {
+    let name = CString::new(name).ok()?;
+    let mut size = 0;
+    // SAFETY: a null buffer asks only for the size.
+    let status = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            ptr::null_mut(),
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if status != 0 || size == 0 {
+        return None;
+    }
+    let mut buffer = vec![0u8; size];
+    // SAFETY: `buffer` is writable for `size` bytes.
+    let status = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr(),
+            buffer.as_mut_ptr() as *mut c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if status != 0 {
+        return None;
+    }
+    buffer.truncate(size);
+    let text = String::from_utf8_lossy(&buffer);
+    Some(text.trim_end_matches('\0').trim().to_string())
+}
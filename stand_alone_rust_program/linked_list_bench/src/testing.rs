@@ -0,0 +1,17 @@
+//! What the test modules share.
+
+use proptest::prelude::ProptestConfig;
+
+/// Fewer cases under Miri, which interprets every one, and no regressions
+/// file, which Miri's isolation can't open.
+pub fn proptest_config() -> ProptestConfig {
+    if cfg!(miri) {
+        ProptestConfig {
+            cases: 4,
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        }
+    } else {
+        ProptestConfig::default()
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::clock;
+
+/// Minimum spacing of readings: the sysfs reads cost microseconds, and a
+/// throttle worth reporting lasts seconds.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// A reading counts as throttled when the frequency sits this far below the
+/// highest one seen so far...
+const DROP: f64 = 0.10;
+
+/// ...and it is sustained once this many consecutive readings are.
+const SUSTAINED: usize = 5;
+
+struct Reading {
+    at: Duration,
+    mhz: Option<f64>,
+    celsius: Option<f64>,
+    /// What the run was doing, from the last [`mark`].
+    label: String,
+}
+
+struct Monitor {
+    began: Instant,
+    last: Option<Instant>,
+    temperature: Option<String>,
+    label: String,
+    readings: Vec<Reading>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MONITOR: Mutex<Option<Monitor>> = Mutex::new(None);
+
+/// Starts watching the frequency of whichever CPU the benchmark runs on and
+/// the package temperature. Does nothing where neither is exposed (off
+/// Linux, in most VMs, under Miri).
+pub fn start() {
+    if cfg!(miri) {
+        return;
+    }
+    let temperature = temperature_sensor();
+    if temperature.is_none() && current_mhz().is_none() {
+        return;
+    }
+    *MONITOR.lock().unwrap() = Some(Monitor {
+        began: Instant::now(),
+        last: None,
+        temperature,
+        label: String::new(),
+        readings: Vec::new(),
+    });
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Names the part of the run that the following readings belong to.
+pub fn mark(label: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(monitor) = MONITOR.lock().unwrap().as_mut() {
+        monitor.label = label.to_string();
+    }
+}
+
+/// Takes a reading if one is due. The timing helpers call this between
+/// samples, never inside a measured region.
+pub fn tick() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut guard = MONITOR.lock().unwrap();
+    let Some(monitor) = guard.as_mut() else {
+        return;
+    };
+    let now = Instant::now();
+    if monitor.last.is_some_and(|last| now - last < INTERVAL) {
+        return;
+    }
+    monitor.last = Some(now);
+    let celsius = monitor.temperature.as_deref().and_then(|path| {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|t| t.trim().parse::<f64>().ok())
+            .map(|millidegrees| millidegrees / 1000.0)
+    });
+    let reading = Reading {
+        at: now - monitor.began,
+        mhz: current_mhz(),
+        celsius,
+        label: monitor.label.clone(),
+    };
+    monitor.readings.push(reading);
+}
+
+/// The first sustained frequency drop: where it began, the peak it fell
+/// from and the lowest reading during it.
+struct Throttle<'a> {
+    onset: &'a Reading,
+    peak_mhz: f64,
+    low_mhz: f64,
+}
+
+fn throttle(readings: &[Reading]) -> Option<Throttle<'_>> {
+    let mut peak: f64 = 0.0;
+    let mut run_start = None;
+    for (index, reading) in readings.iter().enumerate() {
+        let Some(mhz) = reading.mhz else {
+            continue;
+        };
+        if mhz < peak * (1.0 - DROP) {
+            let start = *run_start.get_or_insert(index);
+            if index + 1 - start >= SUSTAINED {
+                let low_mhz = readings[start..]
+                    .iter()
+                    .filter_map(|r| r.mhz)
+                    .fold(f64::INFINITY, f64::min);
+                return Some(Throttle {
+                    onset: &readings[start],
+                    peak_mhz: peak,
+                    low_mhz,
+                });
+            }
+        } else {
+            run_start = None;
+            peak = peak.max(mhz);
+        }
+    }
+    None
+}
+
+/// Warns about sustained throttling as a section of a text report, or on
+/// stderr when stdout carries a machine-readable format. Silent when the
+/// frequency held, so steady machines see no extra output.
+pub fn report(text: bool) {
+    let guard = MONITOR.lock().unwrap();
+    let Some(monitor) = guard.as_ref() else {
+        return;
+    };
+    let Some(throttle) = throttle(&monitor.readings) else {
+        return;
+    };
+
+    let temperatures: Vec<f64> = monitor.readings.iter().filter_map(|r| r.celsius).collect();
+    let mut lines = vec![format!(
+        "sustained throttling from {:.1} s into the run ({}): {:.0} MHz fell to {:.0} MHz",
+        throttle.onset.at.as_secs_f64(),
+        if throttle.onset.label.is_empty() {
+            "unlabelled"
+        } else {
+            &throttle.onset.label
+        },
+        throttle.peak_mhz,
+        throttle.low_mhz
+    )];
+    if let (Some(first), Some(max)) = (
+        temperatures.first(),
+        temperatures.iter().cloned().reduce(f64::max),
+    ) {
+        lines.push(format!(
+            "package temperature {:.0} C at the start, {:.0} C peak, {} at the onset",
+            first,
+            max,
+            throttle
+                .onset
+                .celsius
+                .map_or_else(|| "unknown".to_string(), |c| format!("{:.0} C", c))
+        ));
+    }
+    lines.push(
+        "results from the onset on ran at a lower clock than earlier ones: compare them only with each other"
+            .to_string(),
+    );
+
+    if text {
+        println!("\n[Thermal]");
+    }
+    for line in lines {
+        if text {
+            println!("Warning: {}", line);
+        } else {
+            eprintln!("Warning: {}", line);
+        }
+    }
+}
+
+/// cpufreq's view of the current CPU. On intel_pstate and amd-pstate this
+/// is the average delivered frequency since the previous read (APERF/MPERF),
+/// which is what throttling lowers.
+fn current_mhz() -> Option<f64> {
+    let cpu = clock::current_cpu().unwrap_or(0);
+    fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_cur_freq",
+        cpu
+    ))
+    .ok()?
+    .trim()
+    .parse::<f64>()
+    .ok()
+    .map(|khz| khz / 1000.0)
+}
+
+/// The thermal zone that tracks the CPU package: x86_pkg_temp on Intel,
+/// the SoC's cpu zone on ARM boards, else whichever zone there is.
+fn temperature_sensor() -> Option<String> {
+    let mut zones: Vec<(String, String)> = fs::read_dir("/sys/class/thermal")
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().to_string_lossy().into_owned())
+        .filter(|path| path.contains("thermal_zone"))
+        .filter_map(|path| {
+            let kind = fs::read_to_string(format!("{}/type", path)).ok()?;
+            Some((kind.trim().to_string(), format!("{}/temp", path)))
+        })
+        .collect();
+    zones.sort();
+    let preferred = ["x86_pkg_temp", "cpu-thermal", "cpu_thermal", "TCPU"];
+    preferred
+        .iter()
+        .find_map(|wanted| zones.iter().find(|(kind, _)| kind == wanted))
+        .or(zones.first())
+        .map(|(_, temp)| temp.clone())
+}
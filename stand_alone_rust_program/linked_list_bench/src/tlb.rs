@@ -0,0 +1,160 @@
+use std::hint::black_box;
+
+use crate::bench;
+
+const LINE: usize = 64;
+const PAGE: usize = 4 << 10;
+const HUGE_PAGE: usize = 2 << 20;
+
+/// `tlb [--nodes <n>] [--iterations <k>] [--huge]`: walks the same number of
+/// nodes laid out with different spacing in one buffer, links in address
+/// order:
+///
+/// - 16 B: densely packed like `LinkedList<usize>` nodes, four to a line;
+/// - 64 B: one node per cache line, so every step is a new line but a page
+///   holds 64 of them;
+/// - 4 KiB: one node per page, so every step is a new line *and* a new TLB
+///   entry;
+/// - 2 MiB (with `--huge`): one node per huge-page-sized region, missing in
+///   the 2 MiB TLB and the page-walk caches as well.
+///
+/// The 64 B and 4 KiB rows touch the same number of cache lines, so the
+/// difference between them is the TLB. Nodes wider than a line are shifted
+/// by one line per node so they spread over all cache sets instead of
+/// conflicting in one.
+pub fn run(args: &[String]) {
+    let mut nodes: usize = 1 << 14;
+    let mut iterations: usize = 10;
+    let mut huge = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--huge" {
+            huge = true;
+            continue;
+        }
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => nodes = v.parse().unwrap_or(nodes),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete tlb option '{}'", arg);
+                return;
+            }
+        }
+    }
+    let nodes = nodes.max(1);
+
+    let mut spacings = vec![("16 B (dense)", 16), ("64 B", LINE), ("4 KiB", PAGE)];
+    if huge {
+        spacings.push(("2 MiB", HUGE_PAGE));
+    }
+
+    println!("--- TLB Stress ---");
+    println!("{} nodes per layout", nodes);
+    if let Some(mode) = transparent_huge_pages() {
+        println!("Transparent huge pages: {}", mode);
+    }
+    println!(
+        "{:<14} {:>12} {:>10} {:>12} {:>10} {:>10} {:>8}",
+        "Spacing", "Span", "Pages", "cycles/node", "stddev", "vs dense", "Flagged"
+    );
+
+    let mut dense_cycles = None;
+    for (name, spacing) in spacings {
+        let Some(buffer) = spaced_chain(nodes, spacing) else {
+            println!(
+                "{:<14} could not reserve {} MiB of address space, try fewer --nodes",
+                name,
+                (nodes as u64 * spacing as u64) >> 20
+            );
+            continue;
+        };
+        black_box(walk(&buffer));
+        let samples = bench::time_repeated(iterations, nodes, || walk(&buffer));
+        let (_, cycles, flagged) = bench::per_operation(&samples);
+        let dense = *dense_cycles.get_or_insert(cycles);
+
+        let span = nodes * spacing;
+        println!(
+            "{:<14} {:>9} KiB {:>10} {:>12} {:>10} {:>10} {:>8}",
+            name,
+            span >> 10,
+            // Pages actually touched; a sparse layout skips the ones between.
+            if spacing >= PAGE {
+                nodes
+            } else {
+                span.div_ceil(PAGE)
+            },
+            bench::stat(&cycles, cycles.median, 2),
+            bench::stat(&cycles, cycles.stddev, 2),
+            bench::ratio(&cycles, &dense, 2),
+            flagged
+        );
+    }
+}
+
+/// A buffer of words holding `nodes` nodes `spacing` bytes apart. Each node
+/// is one word holding the word index of its successor (`usize::MAX` ends
+/// the chain); the first node is at index 0. `None` when the address space
+/// for the layout cannot be reserved, or doesn't even fit in a 32-bit
+/// `usize`.
+fn spaced_chain(nodes: usize, spacing: usize) -> Option<Vec<usize>> {
+    let word = std::mem::size_of::<usize>();
+    nodes.checked_mul(spacing)?.checked_add(spacing)?;
+    let position = |i: usize| {
+        let colour = if spacing > LINE {
+            (i * LINE) % spacing
+        } else {
+            0
+        };
+        (i * spacing + colour) / word
+    };
+    let mut buffer = zeroed_words(position(nodes - 1) + 1)?;
+    for i in 0..nodes {
+        buffer[position(i)] = if i + 1 < nodes {
+            position(i + 1)
+        } else {
+            usize::MAX
+        };
+    }
+    Some(buffer)
+}
+
+/// Like `vec![0; len]`, but reports allocation failure instead of aborting.
+/// Zeroed memory comes straight from the kernel and stays unbacked until
+/// written, so a sparse layout only costs one physical page per node.
+fn zeroed_words(len: usize) -> Option<Vec<usize>> {
+    let layout = std::alloc::Layout::array::<usize>(len).ok()?;
+    // SAFETY: `len` is at least one so the layout is not zero-sized, all-zero
+    // bytes are a valid `usize`, and the Vec takes ownership of an
+    // allocation made with exactly its element type and capacity.
+    unsafe {
+        let ptr = std::alloc::alloc_zeroed(layout) as *mut usize;
+        if ptr.is_null() {
+            return None;
+        }
+        Some(Vec::from_raw_parts(ptr, len, len))
+    }
+}
+
+fn walk(buffer: &[usize]) -> usize {
+    let mut visited = 0;
+    let mut index = 0;
+    while index != usize::MAX {
+        visited += 1;
+        index = buffer[index];
+    }
+    visited
+}
+
+#[cfg(not(miri))]
+fn transparent_huge_pages() -> Option<String> {
+    std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled")
+        .ok()
+        .map(|mode| mode.trim().to_string())
+}
+
+#[cfg(miri)]
+fn transparent_huge_pages() -> Option<String> {
+    None
+}
@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Sparkline, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::bench::{self, Sample};
+use crate::stats::Summary;
+use crate::LinkedList;
+
+/// How long to wait for a key while nothing is running.
+const IDLE_POLL: Duration = Duration::from_millis(250);
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Which traversal a row times.
+#[derive(Clone, Copy)]
+enum Kind {
+    /// Nodes in allocation order.
+    Sequential,
+    /// Links in a random order of the node addresses.
+    Shuffled,
+    /// Sequential, incrementing every payload.
+    Write,
+}
+
+struct Benchmark {
+    name: String,
+    nodes: usize,
+    kind: Kind,
+    samples: Vec<Sample>,
+    /// Built when the benchmark starts and dropped when it finishes, so
+    /// only the running benchmark's list is resident.
+    list: Option<LinkedList<usize>>,
+}
+
+impl Benchmark {
+    fn new(kind: Kind, nodes: usize) -> Self {
+        let name = match kind {
+            Kind::Sequential => "traverse",
+            Kind::Shuffled => "shuffled",
+            Kind::Write => "traverse-write",
+        };
+        Benchmark {
+            name: name.to_string(),
+            nodes,
+            kind,
+            samples: Vec::new(),
+            list: None,
+        }
+    }
+
+    /// Times one more traversal, building the list first if needed.
+    fn step(&mut self) {
+        let nodes = self.nodes;
+        let kind = self.kind;
+        let list = self.list.get_or_insert_with(|| match kind {
+            Kind::Shuffled => bench::build_shuffled(nodes, nodes as u64),
+            Kind::Sequential | Kind::Write => bench::build(nodes),
+        });
+        let sample = match kind {
+            Kind::Write => bench::time_write_traversals(list, 1),
+            Kind::Sequential | Kind::Shuffled => bench::time_traversals(list, 1),
+        };
+        self.samples.extend(sample);
+    }
+
+    fn cycles(&self) -> Summary {
+        Summary::of(
+            &self
+                .samples
+                .iter()
+                .filter(|s| s.anomaly.is_none())
+                .map(Sample::cycles_per_node)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// One bar per unflagged sample, scaled to the slowest one.
+    fn history(&self, width: usize) -> String {
+        let values: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|s| s.anomaly.is_none())
+            .map(Sample::cycles_per_node)
+            .collect();
+        let values = &values[values.len().saturating_sub(width)..];
+        let max = values.iter().cloned().fold(f64::MIN_POSITIVE, f64::max);
+        values
+            .iter()
+            .map(|&v| {
+                BARS[((v / max * (BARS.len() - 1) as f64).round() as usize).min(BARS.len() - 1)]
+            })
+            .collect()
+    }
+}
+
+struct App {
+    benchmarks: Vec<Benchmark>,
+    iterations: usize,
+    /// Benchmarks waiting to run, front first.
+    queue: VecDeque<usize>,
+    table: TableState,
+}
+
+impl App {
+    fn running(&self) -> Option<usize> {
+        self.queue.front().copied()
+    }
+
+    /// Drops the old samples of `index` and queues it again.
+    fn rerun(&mut self, index: usize) {
+        if self.running() == Some(index) {
+            self.benchmarks[index].samples.clear();
+            return;
+        }
+        self.queue.retain(|&i| i != index);
+        self.benchmarks[index].samples.clear();
+        self.queue.push_back(index);
+    }
+
+    /// Runs one iteration of the benchmark at the front of the queue.
+    fn step(&mut self) {
+        let Some(index) = self.running() else {
+            return;
+        };
+        let benchmark = &mut self.benchmarks[index];
+        benchmark.step();
+        if benchmark.samples.len() >= self.iterations {
+            benchmark.list = None;
+            self.queue.pop_front();
+        }
+    }
+}
+
+/// `tui [--nodes <a,b,...>] [--iterations <k>]`: an interactive table of
+/// sequential, shuffled and write traversals at each size, filled in live as
+/// iterations complete, with a sparkline of every benchmark's cycles per
+/// node across iterations. Arrow keys (or j/k) select a row, `r` or Enter
+/// reruns it, `a` reruns everything and `q` quits.
+///
+/// Iterations run on the UI thread between redraws, so drawing never
+/// competes with a measurement for the CPU.
+pub fn run(args: &[String]) {
+    let mut sizes: Vec<usize> = vec![1 << 12, 1 << 16, 1 << 20];
+    let mut iterations: usize = 20;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--nodes", Some(v)) => {
+                sizes = v
+                    .split(',')
+                    .filter_map(|n| n.trim().parse().ok())
+                    .filter(|&n| n > 0)
+                    .collect()
+            }
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            _ => {
+                eprintln!("Error: unknown or incomplete tui option '{}'", arg);
+                return;
+            }
+        }
+    }
+    if sizes.is_empty() {
+        eprintln!("Error: --nodes needs at least one positive size");
+        return;
+    }
+    if !io::stdout().is_terminal() {
+        eprintln!("Error: tui needs an interactive terminal on stdout");
+        return;
+    }
+
+    let benchmarks: Vec<Benchmark> = [Kind::Sequential, Kind::Shuffled, Kind::Write]
+        .into_iter()
+        .flat_map(|kind| sizes.iter().map(move |&nodes| Benchmark::new(kind, nodes)))
+        .collect();
+    let mut app = App {
+        queue: (0..benchmarks.len()).collect(),
+        benchmarks,
+        iterations: iterations.max(1),
+        table: TableState::default().with_selected(Some(0)),
+    };
+
+    let result = ratatui::try_init().and_then(|mut terminal| event_loop(&mut terminal, &mut app));
+    ratatui::restore();
+    if let Err(e) = result {
+        eprintln!("Error: tui failed: {}", e);
+    }
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+        let timeout = if app.running().is_some() {
+            Duration::ZERO
+        } else {
+            IDLE_POLL
+        };
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let last = app.benchmarks.len() - 1;
+                let selected = app.table.selected().unwrap_or(0);
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.table.select(Some(selected.saturating_sub(1)))
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.table.select(Some((selected + 1).min(last)))
+                    }
+                    KeyCode::Char('r') | KeyCode::Enter => app.rerun(selected),
+                    KeyCode::Char('a') => (0..=last).for_each(|i| app.rerun(i)),
+                    _ => {}
+                }
+                continue;
+            }
+        }
+        app.step();
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [table_area, chart_area, help_area] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(8),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let running = app.running();
+    let history_width = 24;
+    let rows = app.benchmarks.iter().enumerate().map(|(index, b)| {
+        let cycles = b.cycles();
+        let status = if running == Some(index) {
+            "running"
+        } else if app.queue.contains(&index) {
+            "queued"
+        } else {
+            "done"
+        };
+        let flagged = b.samples.iter().filter(|s| s.anomaly.is_some()).count();
+        let number = |value: f64| {
+            if cycles.count == 0 {
+                "-".to_string()
+            } else {
+                format!("{:.2}", value)
+            }
+        };
+        Row::new(vec![
+            Cell::from(b.name.clone()),
+            Cell::from(b.nodes.to_string()),
+            Cell::from(format!("{}/{}", b.samples.len(), app.iterations)),
+            Cell::from(number(cycles.median)),
+            Cell::from(number(cycles.min)),
+            Cell::from(number(cycles.stddev)),
+            Cell::from(flagged.to_string()),
+            Cell::from(status),
+            Cell::from(b.history(history_width)),
+        ])
+    });
+    let header = Row::new(vec![
+        "Benchmark",
+        "Nodes",
+        "Iters",
+        "cyc/node",
+        "min",
+        "stddev",
+        "Flagged",
+        "Status",
+        "History",
+    ])
+    .style(Style::new().add_modifier(Modifier::BOLD));
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(15),
+            Constraint::Length(9),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(history_width as u16),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title(" Linked List Benchmarks "))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, table_area, &mut app.table);
+
+    let selected = &app.benchmarks[app.table.selected().unwrap_or(0)];
+    // Sparklines take integers: hundredths of a cycle keep the detail.
+    let data: Vec<u64> = selected
+        .samples
+        .iter()
+        .filter(|s| s.anomaly.is_none())
+        .map(|s| (s.cycles_per_node() * 100.0) as u64)
+        .collect();
+    let sparkline = Sparkline::default()
+        .block(Block::bordered().title(format!(
+            " {} {}: cycles/node per iteration ",
+            selected.name, selected.nodes
+        )))
+        .data(&data);
+    frame.render_widget(sparkline, chart_area);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(
+            " ↑/↓ select   r/Enter rerun selected   a rerun all   q quit",
+        )),
+        help_area,
+    );
+}
@@ -0,0 +1,65 @@
+//! Stand-ins for the modules whose dependencies only build natively:
+//! plotters pulls in font rendering, rusqlite bundles SQLite's C source and
+//! ratatui drives a terminal.
+//! wasm32 builds use these instead, so every caller compiles unchanged and
+//! reports the feature as unavailable at run time.
+
+const REASON: &str = "not available in wasm32 builds";
+
+pub mod plot {
+    use std::error::Error;
+
+    use crate::bench::BenchResult;
+
+    pub fn cycles_per_node(
+        _path: &str,
+        _series: &[(&str, &[BenchResult])],
+    ) -> Result<(), Box<dyn Error>> {
+        Err(format!("charts are {}", super::REASON).into())
+    }
+
+    pub fn cycles_per_node_svg(
+        _series: &[(&str, &[BenchResult])],
+    ) -> Result<String, Box<dyn Error>> {
+        Err(format!("charts are {}", super::REASON).into())
+    }
+
+    pub fn histogram_svg(_result: &BenchResult) -> Result<String, Box<dyn Error>> {
+        Err(format!("charts are {}", super::REASON).into())
+    }
+}
+
+pub mod store {
+    use std::io;
+
+    use crate::bench::BenchResult;
+    use crate::metadata::Metadata;
+
+    pub struct Store;
+
+    impl Store {
+        pub fn open(_path: &str) -> io::Result<Self> {
+            Err(io::Error::other(format!(
+                "the results store is {}",
+                super::REASON
+            )))
+        }
+
+        pub fn append(&self, _metadata: &Metadata, _result: &BenchResult) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub fn history(_args: &[String]) {
+        eprintln!(
+            "Error: history needs the results store, which is {}",
+            super::REASON
+        );
+    }
+}
+
+pub mod tui {
+    pub fn run(_args: &[String]) {
+        eprintln!("Error: the tui is {}", super::REASON);
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::bench;
+use crate::clock;
+use crate::histogram::{self, Latencies};
+use crate::isolate;
+use crate::rng::Rng;
+use crate::LinkedList;
+
+const HEADER: &str = "# linked_list_bench trace v1";
+
+/// One operation of a mixed workload. Indices count from the front and are
+/// always in range for the length the structure has at that point of the
+/// trace, so any structure can replay any trace.
+#[derive(Clone, Copy)]
+pub enum Op {
+    Push(u64),
+    Pop,
+    Get(usize),
+    Insert(usize, u64),
+    Remove(usize),
+}
+
+/// Relative weights of push, pop, get, insert and remove.
+type Mix = [u32; 5];
+
+const DEFAULT_MIX: Mix = [20, 20, 40, 10, 10];
+
+/// A mixed workload of `initial` pushes followed by `ops` operations drawn
+/// from `mix`. Operations that need an element become pushes while the
+/// structure is empty.
+pub fn generate(seed: u64, initial: usize, ops: usize, mix: &Mix) -> Vec<Op> {
+    let mut rng = Rng::new(seed);
+    let total: u32 = mix.iter().sum::<u32>().max(1);
+    let mut trace: Vec<Op> = (0..initial).map(|_| Op::Push(rng.next_u64())).collect();
+    let mut len = initial;
+    for _ in 0..ops {
+        let mut pick = rng.below(total as usize) as u32;
+        let kind = mix
+            .iter()
+            .position(|&weight| {
+                let hit = pick < weight;
+                pick = pick.saturating_sub(weight);
+                hit
+            })
+            .unwrap_or(0);
+        let op = match kind {
+            _ if len == 0 && kind != 3 => Op::Push(rng.next_u64()),
+            1 => Op::Pop,
+            2 => Op::Get(rng.below(len)),
+            3 => Op::Insert(rng.below(len + 1), rng.next_u64()),
+            4 => Op::Remove(rng.below(len)),
+            _ => Op::Push(rng.next_u64()),
+        };
+        match op {
+            Op::Push(_) | Op::Insert(..) => len += 1,
+            Op::Pop | Op::Remove(_) => len -= 1,
+            Op::Get(_) => {}
+        }
+        trace.push(op);
+    }
+    trace
+}
+
+/// Writes a trace as tab-separated lines below a header:
+///
+/// ```text
+/// seed    <seed the trace was generated from>
+/// <op>    <index or ->    <value or ->
+/// ```
+pub fn save(path: &str, seed: u64, trace: &[Op]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(out, "{}", HEADER)?;
+    writeln!(out, "seed\t{}", seed)?;
+    for op in trace {
+        match *op {
+            Op::Push(value) => writeln!(out, "push\t-\t{}", value)?,
+            Op::Pop => writeln!(out, "pop\t-\t-")?,
+            Op::Get(index) => writeln!(out, "get\t{}\t-", index)?,
+            Op::Insert(index, value) => writeln!(out, "insert\t{}\t{}", index, value)?,
+            Op::Remove(index) => writeln!(out, "remove\t{}\t-", index)?,
+        }
+    }
+    out.flush()
+}
+
+/// Reads a trace written by [`save`], checking that every index is in range
+/// so a replay cannot panic halfway through.
+pub fn load(path: &str) -> io::Result<(u64, Vec<Op>)> {
+    let text = fs::read_to_string(path)?;
+    let invalid = |line: usize, what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}:{}: {}", path, line + 1, what),
+        )
+    };
+
+    if text.lines().next() != Some(HEADER) {
+        return Err(invalid(0, "not a linked_list_bench trace file"));
+    }
+
+    let mut seed = 0;
+    let mut trace = Vec::new();
+    let mut len: usize = 0;
+    for (number, line) in text.lines().enumerate().skip(1) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let index = |text: &str, limit: usize| {
+            text.parse::<usize>()
+                .ok()
+                .filter(|&i| i < limit)
+                .ok_or_else(|| invalid(number, "index missing or out of range"))
+        };
+        let value = |text: &str| {
+            text.parse::<u64>()
+                .map_err(|_| invalid(number, "bad value"))
+        };
+        let op = match fields.as_slice() {
+            ["seed", s] => {
+                seed = s.parse().map_err(|_| invalid(number, "bad seed"))?;
+                continue;
+            }
+            ["push", "-", v] => Op::Push(value(v)?),
+            ["pop", "-", "-"] if len > 0 => Op::Pop,
+            ["get", i, "-"] => Op::Get(index(i, len)?),
+            ["insert", i, v] => Op::Insert(index(i, len + 1)?, value(v)?),
+            ["remove", i, "-"] => Op::Remove(index(i, len)?),
+            [""] => continue,
+            _ => return Err(invalid(number, "unrecognised or out-of-range operation")),
+        };
+        match op {
+            Op::Push(_) | Op::Insert(..) => len += 1,
+            Op::Pop | Op::Remove(_) => len -= 1,
+            Op::Get(_) => {}
+        }
+        trace.push(op);
+    }
+    Ok((seed, trace))
+}
+
+/// The operations a trace needs. Push and pop work on the front, which is
+/// where a singly linked list is cheap and a `Vec` has to shift everything.
+trait Structure: Default {
+    fn push(&mut self, value: u64);
+    fn pop(&mut self) -> Option<u64>;
+    fn get(&self, index: usize) -> Option<u64>;
+    fn insert(&mut self, index: usize, value: u64);
+    fn remove(&mut self, index: usize) -> Option<u64>;
+}
+
+impl Structure for LinkedList<u64> {
+    fn push(&mut self, value: u64) {
+        LinkedList::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        LinkedList::pop(self)
+    }
+
+    fn get(&self, index: usize) -> Option<u64> {
+        let mut current = &self.head;
+        for _ in 0..index {
+            current = &current.as_ref()?.next;
+        }
+        current.as_ref().map(|node| node.data)
+    }
+
+    fn insert(&mut self, index: usize, value: u64) {
+        self.insert_at(index, value);
+    }
+
+    fn remove(&mut self, index: usize) -> Option<u64> {
+        self.remove_at(index)
+    }
+}
+
+impl Structure for Vec<u64> {
+    fn push(&mut self, value: u64) {
+        Vec::insert(self, 0, value);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        (!self.is_empty()).then(|| Vec::remove(self, 0))
+    }
+
+    fn get(&self, index: usize) -> Option<u64> {
+        self.as_slice().get(index).copied()
+    }
+
+    fn insert(&mut self, index: usize, value: u64) {
+        Vec::insert(self, index, value);
+    }
+
+    fn remove(&mut self, index: usize) -> Option<u64> {
+        (index < self.len()).then(|| Vec::remove(self, index))
+    }
+}
+
+impl Structure for VecDeque<u64> {
+    fn push(&mut self, value: u64) {
+        self.push_front(value);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        self.pop_front()
+    }
+
+    fn get(&self, index: usize) -> Option<u64> {
+        VecDeque::get(self, index).copied()
+    }
+
+    fn insert(&mut self, index: usize, value: u64) {
+        VecDeque::insert(self, index, value);
+    }
+
+    fn remove(&mut self, index: usize) -> Option<u64> {
+        VecDeque::remove(self, index)
+    }
+}
+
+/// Applies the trace to an empty structure. Returns a position-weighted
+/// checksum of every value read back, which is identical for any two
+/// structures that implement the operations correctly, and the structure
+/// itself so it is dropped outside the timed region.
+fn replay<S: Structure>(trace: &[Op]) -> (u64, S) {
+    let mut structure = S::default();
+    let mut checksum: u64 = 0;
+    for (position, op) in trace.iter().enumerate() {
+        if let Some(value) = apply(&mut structure, *op) {
+            checksum = checksum.wrapping_add(value.wrapping_mul(position as u64 + 1));
+        }
+    }
+    (checksum, structure)
+}
+
+/// Applies the trace to an empty structure like [`replay`], timing every
+/// operation on its own.
+fn replay_timed<S: Structure>(trace: &[Op], latencies: &mut Latencies) -> S {
+    let mut structure = S::default();
+    for op in trace {
+        let before = clock::timestamp();
+        let read = apply(&mut structure, *op);
+        latencies.record(clock::timestamp().saturating_sub(before));
+        std::hint::black_box(read);
+    }
+    structure
+}
+
+/// Performs one operation, returning the value it read back, if any.
+fn apply<S: Structure>(structure: &mut S, op: Op) -> Option<u64> {
+    match op {
+        Op::Push(value) => {
+            structure.push(value);
+            None
+        }
+        Op::Pop => structure.pop(),
+        Op::Get(index) => structure.get(index),
+        Op::Insert(index, value) => {
+            structure.insert(index, value);
+            None
+        }
+        Op::Remove(index) => structure.remove(index),
+    }
+}
+
+/// `workload [--structure list|vec|deque|all] [--initial <n>] [--ops <k>]
+/// [--mix <push,pop,get,insert,remove>] [--seed <s>] [--record <trace>]
+/// [--replay <trace>] [--iterations <n>] [--isolate] [--hdr <file>]`: runs a
+/// random mix of front pushes and pops and indexed gets, inserts and
+/// removes against each structure, then replays it once more per iteration
+/// timing every operation, for the latency percentiles a per-operation
+/// mean hides. `--hdr` writes those latencies as an HdrHistogram interval
+/// log. `--record` saves the operation sequence and `--replay` runs a
+/// saved one instead of generating it, so a workload that turned out
+/// pathological for one structure can be rerun, operation for operation,
+/// against another. `--isolate` replays the trace against each structure in
+/// a child process of its own, so the later ones don't run on a heap the
+/// earlier ones fragmented.
+pub fn run(args: &[String]) {
+    let mut structure = "all".to_string();
+    let mut initial: usize = 1 << 10;
+    let mut ops: usize = 1 << 14;
+    let mut mix = DEFAULT_MIX;
+    let mut seed: u64 = 42;
+    let mut record: Option<String> = None;
+    let mut replay_path: Option<String> = None;
+    let mut iterations: usize = 5;
+    let mut isolate = false;
+    let mut hdr_path: Option<String> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--isolate" {
+            isolate = true;
+            continue;
+        }
+        match (arg.as_str(), iter.next()) {
+            ("--structure", Some(v)) if ["list", "vec", "deque", "all"].contains(&v.as_str()) => {
+                structure = v.clone()
+            }
+            ("--initial", Some(v)) => initial = v.parse().unwrap_or(initial),
+            ("--ops", Some(v)) => ops = v.parse().unwrap_or(ops),
+            ("--mix", Some(v)) => match parse_mix(v) {
+                Some(m) => mix = m,
+                None => {
+                    eprintln!(
+                        "Error: --mix takes five comma-separated weights (push,pop,get,insert,remove)"
+                    );
+                    return;
+                }
+            },
+            ("--seed", Some(v)) => seed = v.parse().unwrap_or(seed),
+            ("--record", Some(v)) => record = Some(v.clone()),
+            ("--replay", Some(v)) => replay_path = Some(v.clone()),
+            ("--iterations", Some(v)) => iterations = v.parse().unwrap_or(iterations),
+            ("--hdr", Some(v)) => hdr_path = Some(v.clone()),
+            _ => {
+                eprintln!("Error: unknown or incomplete workload option '{}'", arg);
+                return;
+            }
+        }
+    }
+
+    if isolate && hdr_path.is_some() {
+        eprintln!("Error: --hdr can't be combined with --isolate");
+        return;
+    }
+
+    let (seed, trace, source) = match &replay_path {
+        Some(path) => match load(path) {
+            Ok((seed, trace)) => (seed, trace, format!("replayed from {}", path)),
+            Err(e) => {
+                eprintln!("Error: could not load trace: {}", e);
+                return;
+            }
+        },
+        None => (
+            seed,
+            generate(seed, initial, ops, &mix),
+            format!(
+                "generated, {} initial pushes then mix {}",
+                initial,
+                mix.map(|w| w.to_string()).join(",")
+            ),
+        ),
+    };
+    if let Some(path) = &record {
+        match save(path, seed, &trace) {
+            Ok(()) => eprintln!("Trace of {} operations recorded to {}", trace.len(), path),
+            Err(e) => eprintln!("Error: could not record trace to {}: {}", path, e),
+        }
+    }
+
+    println!("--- Mixed Workload ---");
+    println!("{} operations (seed {}, {})", trace.len(), seed, source);
+    println!(
+        "{:<9} {:>6} {:>12} {:>12} {:>10} {:>20} {:>8}",
+        "Structure", "Iters", "ns/op", "cycles/op", "Mops/s", "Checksum", "Flagged"
+    );
+
+    let mut checksums = Vec::new();
+    let mut latency_rows = Vec::new();
+    let mut histograms = Vec::new();
+    let wanted = |name: &str| structure == "all" || structure == name;
+    if isolate {
+        let path = isolate::scratch_path("trace");
+        if let Err(e) = save(&path.to_string_lossy(), seed, &trace) {
+            eprintln!(
+                "Error: could not write trace for the child processes: {}",
+                e
+            );
+            return;
+        }
+        for name in ["list", "vec", "deque"].into_iter().filter(|n| wanted(n)) {
+            match isolated(name, &path.to_string_lossy(), iterations) {
+                Ok((checksum, latency_row)) => {
+                    checksums.push(checksum);
+                    latency_rows.push(latency_row);
+                }
+                Err(e) => {
+                    eprintln!("Error: isolated run of {} failed: {}", name, e);
+                    break;
+                }
+            }
+        }
+        let _ = fs::remove_file(&path);
+    } else {
+        let reports: [(&str, Report); 3] = [
+            ("list", report::<LinkedList<u64>>),
+            ("vec", report::<Vec<u64>>),
+            ("deque", report::<VecDeque<u64>>),
+        ];
+        for (name, report) in reports.into_iter().filter(|(n, _)| wanted(n)) {
+            let (checksum, latencies, length) = report(name, &trace, iterations);
+            checksums.push(checksum);
+            latency_rows.push(format!("{:<9} {}", name, latencies.cells()));
+            histograms.push((name.to_string(), latencies, length));
+        }
+    }
+
+    println!("\n[Per-Operation Latency]");
+    println!("{:<9} {}", "Structure", Latencies::columns());
+    for row in &latency_rows {
+        println!("{}", row);
+    }
+    println!(
+        "Every operation timed on its own in {} more replays; each includes ~{:.0} cycles of timestamp cost",
+        iterations.max(1),
+        clock::timestamp_cost()
+    );
+    if checksums.windows(2).any(|pair| pair[0] != pair[1]) {
+        eprintln!("Error: structures disagree on the values read back: at least one is wrong");
+    }
+
+    if let Some(path) = hdr_path {
+        let histograms: Vec<(String, &Latencies, Duration)> = histograms
+            .iter()
+            .map(|(name, latencies, length)| (name.clone(), latencies, *length))
+            .collect();
+        match histogram::export(&path, &histograms) {
+            Ok(()) => eprintln!("Latency histograms written to {}", path),
+            Err(e) => eprintln!("Error: could not write histograms to {}: {}", path, e),
+        }
+    }
+}
+
+/// Replays the trace at `path` against one structure in a child process,
+/// prints the child's table row as its own and returns the checksum from it
+/// with the child's latency row.
+fn isolated(name: &str, path: &str, iterations: usize) -> io::Result<(u64, String)> {
+    let args = [
+        "workload",
+        "--structure",
+        name,
+        "--replay",
+        path,
+        "--iterations",
+        &iterations.to_string(),
+    ]
+    .map(String::from);
+    let stdout = isolate::child(&args)?;
+    // The structure's row in the results table, then in the latency one.
+    let mut rows = stdout
+        .lines()
+        .filter(|line| line.split_whitespace().next() == Some(name));
+    let row = rows
+        .next()
+        .ok_or_else(|| io::Error::other("no result row in the child's output"))?;
+    println!("{}", row);
+    let checksum = row
+        .split_whitespace()
+        .nth(5)
+        .and_then(|c| u64::from_str_radix(c.trim_start_matches("0x"), 16).ok())
+        .ok_or_else(|| io::Error::other("no checksum in the child's result row"))?;
+    let latency_row = rows
+        .next()
+        .ok_or_else(|| io::Error::other("no latency row in the child's output"))?;
+    Ok((checksum, latency_row.to_string()))
+}
+
+fn parse_mix(text: &str) -> Option<Mix> {
+    let weights: Vec<u32> = text
+        .split(',')
+        .map(|w| w.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let mix: Mix = weights.try_into().ok()?;
+    (mix.iter().sum::<u32>() > 0).then_some(mix)
+}
+
+type Report = fn(&str, &[Op], usize) -> (u64, Latencies, Duration);
+
+/// Prints one structure's row and returns its checksum with the latencies
+/// of the timed-per-operation replays and how long they took.
+fn report<S: Structure>(name: &str, trace: &[Op], iterations: usize) -> (u64, Latencies, Duration) {
+    let (checksum, _) = replay::<S>(trace);
+    let samples = bench::time_repeated(iterations, trace.len(), || replay::<S>(trace));
+    let (ns, cycles, flagged) = bench::per_operation(&samples);
+    println!(
+        "{:<9} {:>6} {:>12} {:>12} {:>10} {:>#20x} {:>8}",
+        name,
+        samples.len(),
+        bench::stat(&ns, ns.median, 2),
+        bench::stat(&cycles, cycles.median, 2),
+        bench::stat(&ns, 1e3 / ns.median.max(f64::MIN_POSITIVE), 2),
+        checksum,
+        flagged
+    );
+
+    let mut latencies = Latencies::new();
+    let start = clock::start();
+    for _ in 0..iterations.max(1) {
+        drop(replay_timed::<S>(trace, &mut latencies));
+    }
+    (checksum, latencies, clock::stop(&start).time)
+}